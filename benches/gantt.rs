@@ -0,0 +1,72 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+mod common;
+use common::Rng;
+
+use gram::gantt::layout::{GanttLayoutConfig, WidthMode, layout, layout_with_config};
+use gram::gantt::parser::parse;
+use gram::gantt::renderer::render;
+
+const TASKS: usize = 500;
+
+/// Tasks over about two years in sections of 25, starting on dates or continuing the task
+/// before them
+fn random_input(tasks: usize) -> String {
+    let mut rng = Rng(0x5eed_cafe_f00d_0002);
+    let mut lines = Vec::new();
+    for i in 0..tasks {
+        if i % 25 == 0 {
+            lines.push(format!("section Phase {}", i / 25));
+        }
+        let duration = 1 + rng.below(30);
+        let start = if i > 0 && rng.below(3) == 0 {
+            "continue".to_string()
+        } else {
+            let day = 1 + rng.below(28);
+            let month = 1 + rng.below(12);
+            let year = 2025 + rng.below(2);
+            format!("{:02}-{:02}-{}", day, month, year)
+        };
+        let overlay = match rng.below(4) {
+            0 => " | done",
+            1 => " | crit",
+            _ => "",
+        };
+        lines.push(format!("Task {}: {}, {}d{}", i, start, duration, overlay));
+    }
+    lines.join("\n")
+}
+
+fn bench_gantt(c: &mut Criterion) {
+    let input = random_input(TASKS);
+    let gantt_chart = parse(&input).unwrap();
+    let gantt_layout = layout(&gantt_chart);
+
+    c.bench_function("gantt parse 500 tasks", |b| {
+        b.iter(|| parse(black_box(&input)))
+    });
+    c.bench_function("gantt layout 500 tasks", |b| {
+        b.iter(|| layout(black_box(&gantt_chart)))
+    });
+    c.bench_function("gantt render 500 tasks", |b| {
+        b.iter(|| render(black_box(&gantt_layout)))
+    });
+
+    let mut group = c.benchmark_group("gantt layout chart width");
+    for chart_width in [60, 120, 240] {
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(chart_width),
+            ..GanttLayoutConfig::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chart_width),
+            &config,
+            |b, config| b.iter(|| layout_with_config(black_box(&gantt_chart), config)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_gantt);
+criterion_main!(benches);
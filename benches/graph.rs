@@ -0,0 +1,59 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+mod common;
+use common::Rng;
+
+use gram::graph::layout::{assign_ranks, build_adjacency_graph, order_nodes, sources};
+use gram::graph::parser::{Graph, parse};
+
+/// A random DAG with about two edges per node. Edges only point to later nodes, so there is
+/// no cycle.
+fn random_dag(nodes: usize) -> Graph {
+    let mut rng = Rng(0x5eed_cafe_f00d_0003);
+    let input: String = (1..nodes)
+        .flat_map(|to| {
+            let edges = 1 + rng.below(3);
+            (0..edges)
+                .map(|_| format!("n{} -> n{}\n", rng.below(to), to))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    parse(&input).unwrap()
+}
+
+fn bench_graph(c: &mut Criterion) {
+    let graphs: Vec<(usize, Graph)> = [10, 100, 1000]
+        .into_iter()
+        .map(|nodes| (nodes, random_dag(nodes)))
+        .collect();
+
+    let mut group = c.benchmark_group("graph assign_ranks");
+    for (nodes, graph) in &graphs {
+        let adjacency = build_adjacency_graph(graph);
+        group.bench_with_input(BenchmarkId::from_parameter(nodes), graph, |b, graph| {
+            b.iter(|| assign_ranks(black_box(graph), &adjacency))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("graph order_nodes");
+    for (nodes, graph) in &graphs {
+        let ranks = assign_ranks(graph, &build_adjacency_graph(graph));
+        group.bench_with_input(BenchmarkId::from_parameter(nodes), graph, |b, graph| {
+            b.iter(|| order_nodes(black_box(graph), &ranks))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("graph sources");
+    for (nodes, graph) in &graphs {
+        group.bench_with_input(BenchmarkId::from_parameter(nodes), graph, |b, graph| {
+            b.iter(|| sources(black_box(graph)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_graph);
+criterion_main!(benches);
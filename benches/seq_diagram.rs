@@ -0,0 +1,53 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+mod common;
+use common::Rng;
+
+use gram::layout::calculate_sequence_layout;
+use gram::parser::parse;
+use gram::renderer::render;
+use gram::tokenizer::tokenize;
+
+const LINES: usize = 1000;
+const PARTICIPANTS: usize = 30;
+
+/// Edges between random participants in both directions, with a note every few lines
+fn random_input(lines: usize) -> String {
+    let mut rng = Rng(0x5eed_cafe_f00d_0001);
+    (0..lines)
+        .map(|i| {
+            let from = rng.below(PARTICIPANTS);
+            if rng.below(10) == 0 {
+                return format!("note over P{}: note {}", from, i);
+            }
+            let to = (from + 1 + rng.below(PARTICIPANTS - 1)) % PARTICIPANTS;
+            let arrow = if rng.below(2) == 0 { "->" } else { "<-" };
+            format!("P{} {} P{}: message {}", from, arrow, to, i)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bench_seq_diagram(c: &mut Criterion) {
+    let input = random_input(LINES);
+    let tokens = tokenize(&input).unwrap();
+    let diagram = parse(tokens.clone()).unwrap();
+    let layout = calculate_sequence_layout(&diagram);
+
+    c.bench_function("tokenize 1000 lines", |b| {
+        b.iter(|| tokenize(black_box(&input)))
+    });
+    c.bench_function("parse 1000 lines", |b| {
+        b.iter_batched(|| tokens.clone(), parse, BatchSize::SmallInput)
+    });
+    c.bench_function("layout 1000 lines", |b| {
+        b.iter(|| calculate_sequence_layout(black_box(&diagram)))
+    });
+    c.bench_function("render 1000 lines", |b| {
+        b.iter(|| render(black_box(&layout)))
+    });
+}
+
+criterion_group!(benches, bench_seq_diagram);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use gram::layout::calculate_sequence_layout;
+use gram::parser::parse;
+use gram::tokenizer::tokenize;
+
+/// Edges between neighbouring participants, walking back and forth across the diagram
+fn diagram_input(participants: usize, edges: usize) -> String {
+    (0..edges)
+        .map(|i| {
+            let from = i % (participants - 1);
+            format!("P{} -> P{}: message {}", from, from + 1, i)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bench_sequence_layout(c: &mut Criterion) {
+    for (participants, edges) in [(20, 200), (200, 2000)] {
+        let diagram = parse(tokenize(&diagram_input(participants, edges)).unwrap()).unwrap();
+
+        c.bench_function(
+            &format!("layout {} participants {} edges", participants, edges),
+            |b| b.iter(|| calculate_sequence_layout(black_box(&diagram))),
+        );
+    }
+}
+
+criterion_group!(benches, bench_sequence_layout);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+//! Feeds arbitrary input to the gantt parser, which may reject it but must not panic. Charts
+//! it accepts must also survive the queries over their dates.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gram::gantt::parser::parse;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+
+    if let Ok(gantt_chart) = parse(&input) {
+        let _ = gantt_chart.duration_days();
+        let _ = gantt_chart.gaps();
+    }
+});
@@ -0,0 +1,16 @@
+//! Lays out every chart the gantt parser accepts from arbitrary input, which must not panic
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gram::gantt::layout::layout;
+use gram::gantt::parser::parse;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+
+    if let Ok(gantt_chart) = parse(&input) {
+        let _ = layout(&gantt_chart);
+    }
+});
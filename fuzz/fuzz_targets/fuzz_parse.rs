@@ -0,0 +1,38 @@
+//! Feeds token sequences to the sequence diagram parser, which may reject them but must not
+//! panic. The tokens come from tokenizing the input, then are reordered, dropped and repeated
+//! so that the parser also sees sequences the tokenizer never produces.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gram::parser::parse;
+use gram::tokenizer::{Token, tokenize_iter};
+
+fuzz_target!(|data: &[u8]| {
+    // The first bytes drive the edits, the rest is the text to tokenize
+    let (edits, text) = data.split_at(data.len().min(8));
+    let input = String::from_utf8_lossy(text);
+
+    // Lines that fail to tokenize are left out
+    let mut tokens: Vec<Token> = tokenize_iter(&input).filter_map(Result::ok).collect();
+    for pair in edits.chunks(2) {
+        if tokens.is_empty() {
+            break;
+        }
+        let index = pair[0] as usize % tokens.len();
+        let other = pair.get(1).map_or(0, |&byte| byte as usize % tokens.len());
+        match pair[0] % 3 {
+            0 => {
+                tokens.remove(index);
+            }
+            1 => tokens.swap(index, other),
+            _ => {
+                let token = tokens[index].clone();
+                tokens.insert(other, token);
+            }
+        }
+    }
+
+    let _ = parse(tokens);
+});
@@ -0,0 +1,20 @@
+//! Feeds arbitrary input to the sequence diagram tokenizer, which may reject it but must not
+//! panic
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gram::tokenizer::{TokenizeConfig, tokenize, tokenize_with_config};
+
+fuzz_target!(|data: &[u8]| {
+    // The tokenizer takes text, so invalid UTF-8 is replaced rather than skipped
+    let input = String::from_utf8_lossy(data);
+
+    let _ = tokenize(&input);
+    let config = TokenizeConfig {
+        require_arrow_spacing: true,
+        strict_participants: true,
+    };
+    let _ = tokenize_with_config(&input, &config);
+});
@@ -0,0 +1,303 @@
+use crate::color::{Style, styled_row};
+
+/// How a canvas is written out, shared by the render options of sequence diagrams and gantt
+/// charts
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// Style the output with ANSI escape codes: edge styles of sequence diagrams, bar colors of
+    /// gantt charts
+    pub color: bool,
+    /// Strip the spaces padding each line to the canvas width
+    pub trim_trailing_whitespace: bool,
+    /// End the output with a newline, unless the canvas has no rows
+    pub final_newline: bool,
+}
+
+/// A label that does not fit on the canvas as laid out
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClippedLabel {
+    /// The label as laid out, before it is clipped
+    pub label: String,
+    pub message: String,
+}
+
+/// A grid of chars, each with an optional style, that diagrams are drawn on
+#[derive(Debug)]
+pub struct Canvas {
+    pub grid: Vec<Vec<char>>,
+    /// Style of each cell. Unstyled cells are written without escape codes.
+    pub styles: Vec<Vec<Option<Style>>>,
+    pub width: usize,
+    pub height: usize,
+    /// Labels that did not fit, in the order they were drawn. `draw_label` adds the labels
+    /// clipped at the edges, and renderers add the labels they shorten.
+    pub clipped_labels: Vec<ClippedLabel>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        let grid = vec![vec![' '; width]; height];
+        let styles = vec![vec![None; width]; height];
+        Canvas {
+            grid,
+            styles,
+            width,
+            height,
+            clipped_labels: Vec::new(),
+        }
+    }
+
+    /// Writes an unstyled char. Writes outside the canvas are clipped.
+    pub fn set_char(&mut self, x: usize, y: usize, ch: char) {
+        if y < self.height && x < self.width {
+            self.grid[y][x] = ch;
+            self.styles[y][x] = None;
+        }
+    }
+
+    /// Styles the chars of a rectangle, inclusive of both corners, keeping the chars.
+    /// Clipped at the canvas edges.
+    pub fn set_style(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, style: Style) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        for y in y0..=y1.min(self.height - 1) {
+            for x in x0..=x1.min(self.width - 1) {
+                self.styles[y][x] = Some(style);
+            }
+        }
+    }
+
+    /// Writes `label` from `(x, y)`, one char per column and clipped at the canvas edges. A label
+    /// that does not fit is added to `clipped_labels`, named by `what`, e.g. `Tick label`.
+    pub fn draw_label(&mut self, what: &str, x: usize, y: usize, label: &str) {
+        for (i, ch) in label.chars().enumerate() {
+            self.set_char(x + i, y, ch);
+        }
+
+        let message = if y >= self.height {
+            format!("{} '{}' is below the last row", what, label)
+        } else if x + label.chars().count() > self.width {
+            format!("{} '{}' is clipped at column {}", what, label, self.width)
+        } else {
+            return;
+        };
+        self.clipped_labels.push(ClippedLabel {
+            label: label.to_string(),
+            message,
+        });
+    }
+
+    /// Reads a char. Positions outside the canvas read as blank.
+    pub fn get_char(&self, x: usize, y: usize) -> char {
+        if y < self.height && x < self.width {
+            self.grid[y][x]
+        } else {
+            ' '
+        }
+    }
+
+    /// Copies the `w` x `h` area starting at `(x, y)` into a new canvas. The area is clipped to
+    /// this canvas.
+    pub fn crop(&self, x: usize, y: usize, w: usize, h: usize) -> Canvas {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let w = w.min(self.width - x);
+        let h = h.min(self.height - y);
+
+        let grid = self.grid[y..y + h]
+            .iter()
+            .map(|row| row[x..x + w].to_vec())
+            .collect();
+        let styles = self.styles[y..y + h]
+            .iter()
+            .map(|row| row[x..x + w].to_vec())
+            .collect();
+        Canvas {
+            grid,
+            styles,
+            width: w,
+            height: h,
+            clipped_labels: Vec::new(),
+        }
+    }
+
+    /// Inclusive `(min_x, min_y, max_x, max_y)` of the non-space chars. `None` for a blank canvas.
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, &ch) in row.iter().enumerate() {
+                if ch == ' ' {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                    None => (x, y, x, y),
+                });
+            }
+        }
+
+        bounds
+    }
+
+    /// Crops the canvas to its non-space chars. A blank canvas becomes empty.
+    pub fn trim_whitespace(&self) -> Canvas {
+        match self.bounding_box() {
+            Some((min_x, min_y, max_x, max_y)) => {
+                self.crop(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+            }
+            None => Canvas::new(0, 0),
+        }
+    }
+
+    /// Joins the rows with `\n`, with the right padding and the final newline set by `options`
+    pub fn to_string_with_options(&self, options: &OutputOptions) -> String {
+        let mut output = self
+            .grid
+            .iter()
+            .zip(&self.styles)
+            .map(|(chars, styles)| {
+                let len = if options.trim_trailing_whitespace {
+                    chars.iter().rposition(|&ch| ch != ' ').map_or(0, |x| x + 1)
+                } else {
+                    chars.len()
+                };
+                styled_row(&chars[..len], &styles[..len])
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if options.final_newline && self.height > 0 {
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl std::fmt::Display for Canvas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.to_string_with_options(&OutputOptions::default())
+        )
+    }
+}
+
+/// Inclusive `(min_x, min_y, max_x, max_y)` of the non-space chars of a rendered diagram, to
+/// check the bounding boxes of layouts against the output
+#[cfg(test)]
+pub(crate) fn rendered_bounds(output: &str) -> (usize, usize, usize, usize) {
+    let lines: Vec<&str> = output.lines().collect();
+    let width = lines.iter().map(|line| line.chars().count()).max();
+    let mut canvas = Canvas::new(width.unwrap_or(0), lines.len());
+    for (y, line) in lines.iter().enumerate() {
+        for (x, ch) in line.chars().enumerate() {
+            canvas.set_char(x, y, ch);
+        }
+    }
+    canvas
+        .bounding_box()
+        .expect("Internal error. Nothing was rendered")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::{Color, RESET_CODE};
+
+    #[test]
+    fn test_clips_out_of_range() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set_char(1, 1, 'x');
+        canvas.set_char(2, 0, 'y');
+        canvas.set_char(0, 5, 'z');
+
+        assert_eq!(canvas.to_string(), "  \n x");
+        assert_eq!(canvas.get_char(1, 1), 'x');
+        assert_eq!(canvas.get_char(7, 7), ' ');
+    }
+
+    #[test]
+    fn test_draw_label() {
+        let mut canvas = Canvas::new(6, 2);
+        canvas.draw_label("Name", 1, 0, "fits");
+        assert!(canvas.clipped_labels.is_empty());
+
+        canvas.draw_label("Name", 3, 1, "long");
+        canvas.draw_label("Name", 0, 2, "below");
+        assert_eq!(canvas.to_string(), " fits \n   lon");
+        let messages: Vec<&str> = canvas
+            .clipped_labels
+            .iter()
+            .map(|clipped| clipped.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Name 'long' is clipped at column 6",
+                "Name 'below' is below the last row"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut canvas = Canvas::new(4, 3);
+        for (y, row) in ["abcd", "efgh", "ijkl"].iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                canvas.set_char(x, y, ch);
+            }
+        }
+
+        assert_eq!(canvas.crop(1, 1, 2, 2).to_string(), "fg\njk");
+        // Clipped to the canvas
+        assert_eq!(canvas.crop(3, 2, 5, 5).to_string(), "l");
+        assert_eq!(canvas.crop(9, 9, 2, 2).width, 0);
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        let mut canvas = Canvas::new(5, 5);
+        canvas.set_char(2, 2, 'x');
+
+        let trimmed = canvas.trim_whitespace();
+        assert_eq!((trimmed.width, trimmed.height), (1, 1));
+        assert_eq!(trimmed.to_string(), "x");
+
+        let blank = Canvas::new(3, 3).trim_whitespace();
+        assert_eq!((blank.width, blank.height), (0, 0));
+    }
+
+    #[test]
+    fn test_output_toggles() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.set_char(0, 0, 'a');
+        canvas.set_char(1, 1, 'b');
+        let render = |canvas: &Canvas, trim_trailing_whitespace, final_newline| {
+            canvas.to_string_with_options(&OutputOptions {
+                trim_trailing_whitespace,
+                final_newline,
+                ..OutputOptions::default()
+            })
+        };
+
+        assert_eq!(render(&canvas, false, false), canvas.to_string());
+        assert_eq!(render(&canvas, false, false), "a   \n b  \n    ");
+        assert_eq!(render(&canvas, true, false), "a\n b\n");
+        assert_eq!(render(&canvas, false, true), "a   \n b  \n    \n");
+        assert_eq!(render(&canvas, true, true), "a\n b\n\n");
+
+        // Styled padding is trimmed too
+        let style = Style {
+            color: Some(Color::Green),
+            ..Style::default()
+        };
+        canvas.set_style(0, 0, 3, 0, style);
+        let (style, reset) = (style.escape_code(), RESET_CODE);
+        assert_eq!(
+            render(&canvas, true, false),
+            format!("{style}a{reset}\n b\n")
+        );
+    }
+}
@@ -0,0 +1,100 @@
+/// ANSI foreground color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Color {
+    pub const NAMES: [&str; 6] = ["red", "green", "yellow", "blue", "magenta", "cyan"];
+
+    /// Parses a lowercase color name, e.g. `red`
+    pub fn from_name(name: &str) -> Option<Color> {
+        match name {
+            "red" => Some(Color::Red),
+            "green" => Some(Color::Green),
+            "yellow" => Some(Color::Yellow),
+            "blue" => Some(Color::Blue),
+            "magenta" => Some(Color::Magenta),
+            "cyan" => Some(Color::Cyan),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as accepted by `from_name`
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+        }
+    }
+}
+
+/// Style of a canvas cell
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl Style {
+    pub fn escape_code(self) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.dim {
+            codes.push("2".to_string());
+        }
+        if let Some(color) = self.color {
+            codes.push(color.code().to_string());
+        }
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
+
+pub const RESET_CODE: &str = "\x1b[0m";
+
+/// Joins a row of cells into a line, wrapping each run of styled cells in escape codes
+pub(crate) fn styled_row(chars: &[char], styles: &[Option<Style>]) -> String {
+    let mut line = String::new();
+    let mut current_style = None;
+
+    for (&ch, &style) in chars.iter().zip(styles) {
+        if style != current_style {
+            if current_style.is_some() {
+                line.push_str(RESET_CODE);
+            }
+            if let Some(style) = style {
+                line.push_str(&style.escape_code());
+            }
+            current_style = style;
+        }
+        line.push(ch);
+    }
+    if current_style.is_some() {
+        line.push_str(RESET_CODE);
+    }
+
+    line
+}
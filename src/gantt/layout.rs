@@ -1,144 +1,2537 @@
-use crate::gantt::parser::GanttChart;
-use chrono::{NaiveDate, TimeDelta};
-use num_rational::Ratio;
 use std::cmp::{max, min};
 
+use crate::gantt::parser::{GanttChart, ParseError, Task, TaskStatus, parse, working_days_between};
+use crate::layout::truncate_name;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use num_rational::Ratio;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 #[derive(Debug, PartialEq)]
 pub struct TaskLayout {
     pub x_start: usize,
     pub x_end: usize,
     pub y: usize,
+    /// Empty in resource lanes when the name does not fit in the bar
     pub name: String,
+    /// Column of the name in the left label gutter. `None` when the name is drawn in the bar.
+    pub name_x: Option<usize>,
+    /// Duration drawn inside the bar in place of the name, with `DurationLabels::NarrowBars`
+    pub duration: Option<String>,
+    /// Progress fills the box interior from `x_start + 1` up to, excluding, this column
+    pub fill_until_x: Option<usize>,
+    /// Index into `GanttLayout::section_layouts`. `None` for tasks before the first section.
+    pub section: Option<usize>,
+    pub status: Option<TaskStatus>,
+    pub critical: bool,
+    /// Part of `GanttChart::critical_path`
+    pub on_critical_path: bool,
+    pub actual: Option<ActualLayout>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ActualLayout {
+    pub x_start: usize,
+    pub x_end: usize,
+    pub y: usize,
+}
+
+/// A faint horizontal line across the chart area, behind the bars
+#[derive(Debug, PartialEq)]
+pub struct GuideLayout {
+    pub x_start: usize,
+    pub x_end: usize,
+    pub y: usize,
+}
+
+/// Shading behind the rows of a task or milestone, from `GanttLayoutConfig::row_stripes`
+#[derive(Debug, PartialEq)]
+pub struct StripeLayout {
+    pub x_start: usize,
+    pub x_end: usize,
+    pub y_start: usize,
+    /// Last row (inclusive)
+    pub y_end: usize,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct TickLayout {
     pub x: usize,
     pub date: NaiveDate,
+    /// Date label centered under the tick. `None` when it would collide with a neighbour.
+    pub label: Option<String>,
+}
+
+/// A single date, drawn as a diamond with its name beside it
+#[derive(Debug, PartialEq)]
+pub struct MilestoneLayout {
+    pub x: usize,
+    pub y: usize,
+    pub name: String,
+    /// Column of the name: right of the diamond, left of it when it would run off the
+    /// chart, or in the label gutter
+    pub name_x: usize,
+    /// Index into `GanttLayout::section_layouts`. `None` for milestones before the first section.
+    pub section: Option<usize>,
+    pub status: Option<TaskStatus>,
+    pub critical: bool,
+    /// Part of `GanttChart::critical_path`
+    pub on_critical_path: bool,
+    pub actual: Option<ActualLayout>,
+}
+
+/// Connector from a task to a task that starts after it. It runs horizontally from
+/// `(from_x, from_y)` to `turn_x`, down to `to_y`, and right to the arrowhead at `(to_x, to_y)`.
+#[derive(Debug, PartialEq)]
+pub struct DependencyLayout {
+    pub from_x: usize,
+    pub from_y: usize,
+    /// Column of the vertical segment
+    pub turn_x: usize,
+    pub to_x: usize,
+    pub to_y: usize,
+}
+
+/// Header row of a section
+#[derive(Debug, PartialEq)]
+pub struct SectionLayout {
+    pub x: usize,
+    pub y: usize,
+    pub name: String,
+}
+
+/// The rows of an assignee's tasks, from `GanttLayoutConfig::resource_lanes`
+#[derive(Debug, PartialEq)]
+pub struct LaneLayout {
+    /// Column of the labels in the label gutter
+    pub x: usize,
+    /// Row of the assignee name, the name row of the first row of bars
+    pub y: usize,
+    /// Assignee name, or `UNASSIGNED_LANE` for the tasks without an assignee
+    pub name: String,
+    /// Name rows of the extra rows of bars, labelled with `LANE_OVERLAP_LABEL`. Tasks
+    /// overlapping another task of the assignee are stacked onto them.
+    pub overlap_ys: Vec<usize>,
+}
+
+/// Thin bar spanning the planned dates of the whole chart or of a section, from
+/// `GanttLayoutConfig::summary_rows`
+#[derive(Debug, PartialEq)]
+pub struct SummaryLayout {
+    pub x_start: usize,
+    pub x_end: usize,
+    pub y: usize,
+    /// First column of the label
+    pub label_x: usize,
+    /// Name and span, as in `Project: 01-01-2026 → 06-02-2026, 36d`. Padded with a space on
+    /// each side when it is drawn over the bar.
+    pub label: String,
+}
+
+/// A line of the title or subtitle, centered over the chart area
+#[derive(Debug, PartialEq)]
+pub struct TitleLayout {
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
+    /// Drawn in a lighter style than the title
+    pub subtitle: bool,
+}
+
+/// What a legend entry explains
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegendKind {
+    /// Index into `GanttLayout::section_layouts`
+    Section(usize),
+    Status(TaskStatus),
+    Critical,
+    Milestone,
+}
+
+/// A legend entry: a symbol followed by its meaning
+#[derive(Debug, PartialEq)]
+pub struct LegendEntryLayout {
+    pub x: usize,
+    pub y: usize,
+    pub symbol: char,
+    pub label: String,
+    pub kind: LegendKind,
+}
+
+/// A month on the month header row
+#[derive(Debug, PartialEq)]
+pub struct MonthLayout {
+    /// Column of the first day of the month, or of the axis start for a month cut off by it
+    pub x_start: usize,
+    /// Column of the first day of the next month, or of the axis end
+    pub x_end: usize,
+    /// Month name, shortened or clipped to fit between `x_start` and `x_end`
+    pub label: String,
+    /// The month starts on the chart and gets a separator at `x_start`
+    pub starts_on_chart: bool,
+}
+
+impl MonthLayout {
+    /// First column of the label, centered between the separators
+    pub fn label_x(&self) -> usize {
+        let room = self.x_end.saturating_sub(self.x_start + 1);
+        self.x_start + 1 + room.saturating_sub(self.label.width()) / 2
+    }
+}
+
+/// Vertical line marking the current date
+#[derive(Debug, PartialEq)]
+pub struct TodayMarkerLayout {
+    pub x: usize,
+    pub date: NaiveDate,
+}
+
+/// A task bar or milestone of a vertical chart, in a column band of its own
+#[derive(Debug, PartialEq)]
+pub struct ColumnLayout {
+    /// First column of the band, and of the name above the chart
+    pub x_start: usize,
+    /// Last column (inclusive) of the band
+    pub x_end: usize,
+    /// Top border of the bar, or the row of the milestone
+    pub y_start: usize,
+    /// Bottom border of the bar. Equal to `y_start` for milestones.
+    pub y_end: usize,
+    pub name: String,
+    pub milestone: bool,
+    /// Progress fills the box interior from `y_start + 1` down to, excluding, this row
+    pub fill_until_y: Option<usize>,
+    /// Index into `GanttChart::sections`. `None` for tasks before the first section.
+    pub section: Option<usize>,
+    pub status: Option<TaskStatus>,
+    pub critical: bool,
+    /// Part of `GanttChart::critical_path`
+    pub on_critical_path: bool,
+}
+
+impl ColumnLayout {
+    /// Column of a milestone's diamond, in the middle of the band
+    pub fn center_x(&self) -> usize {
+        self.x_start + (self.x_end - self.x_start) / 2
+    }
+}
+
+/// A tick of a vertical chart: a line across the chart, with the date left of the axis
+#[derive(Debug, PartialEq)]
+pub struct RowTickLayout {
+    pub y: usize,
+    pub date: NaiveDate,
+    /// Left-aligned at `VerticalLayout::label_x`. `None` when the previous tick is on the same
+    /// row.
+    pub label: Option<String>,
+}
+
+/// A chart with time flowing downwards, from `Orientation::Vertical`
+#[derive(Debug)]
+pub struct VerticalLayout {
+    pub columns: Vec<ColumnLayout>,
+    pub ticks: Vec<RowTickLayout>,
+    /// Column of the tick labels
+    pub label_x: usize,
+    /// Column of the axis line between the tick labels and the chart
+    pub axis_x: usize,
+    /// First column of the chart area
+    pub chart_x: usize,
+    /// Last column (inclusive) of the chart area
+    pub chart_end_x: usize,
+    /// Row of the task names, above the chart
+    pub header_y: usize,
+    /// First row of the axis line
+    pub axis_start_y: usize,
+    /// Last row (inclusive) of the axis line
+    pub axis_end_y: usize,
+    /// Row of the today marker. `None` when the marker is disabled or today is outside the
+    /// chart's date range.
+    pub today_y: Option<usize>,
 }
 
 #[derive(Debug)]
 pub struct GanttLayout {
+    /// Bars of a horizontal chart. Empty for vertical charts, whose tasks are
+    /// `VerticalLayout::columns`.
     pub task_layouts: Vec<TaskLayout>,
+    pub milestone_layouts: Vec<MilestoneLayout>,
+    pub dependency_layouts: Vec<DependencyLayout>,
     pub tick_layouts: Vec<TickLayout>,
+    /// Time between two ticks
+    pub tick_interval: TickInterval,
+    pub section_layouts: Vec<SectionLayout>,
+    /// Lanes from `GanttLayoutConfig::resource_lanes`. Empty without them.
+    pub lane_layouts: Vec<LaneLayout>,
+    /// Rows from `GanttLayoutConfig::summary_rows`
+    pub summary_layouts: Vec<SummaryLayout>,
+    /// Rows of the horizontal rules between sections, spanning the ticks
+    pub section_rules: Vec<usize>,
+    /// Row guides from `GanttLayoutConfig::row_guides`
+    pub guides: Vec<GuideLayout>,
+    /// Character the guides are drawn with
+    pub guide_char: char,
+    /// Every other task row, shaded with `GanttLayoutConfig::row_stripes`
+    pub stripes: Vec<StripeLayout>,
+    /// Columns of the break markers drawn in the middle of compressed gaps
+    pub axis_breaks: Vec<usize>,
+    /// Months of the month header row. Empty without `GanttLayoutConfig::month_header` or
+    /// when the chart covers a single month.
+    pub months: Vec<MonthLayout>,
+    /// Row of the month names, between the tick lines and the tick labels
+    pub month_y: Option<usize>,
+    /// Row of the weekday initials, right above the tick labels. Only set for daily ticks with
+    /// `GanttLayoutConfig::weekday_letters`.
+    pub weekday_y: Option<usize>,
+    /// Inclusive column ranges between the ticks of Saturdays and Sundays, shaded with
+    /// `GanttLayoutConfig::weekend_shading`. Empty unless ticks are daily.
+    pub weekend_columns: Vec<(usize, usize)>,
+    /// Entries of the legend below the axis. Empty without `GanttLayoutConfig::legend`.
+    pub legend: Vec<LegendEntryLayout>,
+    /// Lines of the title and subtitle above the chart, wrapped to the canvas width
+    pub title_layouts: Vec<TitleLayout>,
+    /// `None` when the marker is disabled or today is outside the chart's date range
+    pub today_marker: Option<TodayMarkerLayout>,
+    /// First row of the vertical tick lines
+    pub tick_start_y: usize,
+    /// Last row (inclusive) of the vertical tick lines
+    pub tick_end_y: usize,
+    /// Row of the tick date labels
+    pub axis_y: usize,
+    /// Set with `Orientation::Vertical`. The fields for horizontal charts are then empty or
+    /// zero, except for `tick_interval`, `legend` and `title_layouts`.
+    pub vertical: Option<VerticalLayout>,
     pub width: usize,
     pub height: usize,
 }
 
+impl GanttLayout {
+    /// Smallest area holding every drawn character, as inclusive
+    /// `(min_x, min_y, max_x, max_y)` canvas coordinates. `(0, 0, 0, 0)` when nothing is drawn.
+    /// Task names are placed as with the default theme, inside the bars when they fit.
+    pub fn bounding_box(&self) -> (usize, usize, usize, usize) {
+        let last_x = self.width.saturating_sub(1);
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        // Text is clipped at the right edge of the canvas
+        let mut extend = |min_x: usize, min_y: usize, max_x: usize, max_y: usize| {
+            let max_x = max_x.min(last_x);
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => {
+                    (x0.min(min_x), y0.min(min_y), x1.max(max_x), y1.max(max_y))
+                }
+                None => (min_x, min_y, max_x, max_y),
+            });
+        };
+        let text_end = |x: usize, text: &str| x + text.width().saturating_sub(1);
+
+        if let Some(vertical) = &self.vertical {
+            extend(
+                vertical.axis_x,
+                vertical.axis_start_y,
+                vertical.axis_x,
+                vertical.axis_end_y,
+            );
+            for tick in &vertical.ticks {
+                extend(vertical.axis_x, tick.y, vertical.chart_end_x, tick.y);
+                if let Some(label) = &tick.label {
+                    extend(
+                        vertical.label_x,
+                        tick.y,
+                        text_end(vertical.label_x, label),
+                        tick.y,
+                    );
+                }
+            }
+            for column in &vertical.columns {
+                let name_y = vertical.header_y;
+                extend(
+                    column.x_start,
+                    name_y,
+                    text_end(column.x_start, &column.name),
+                    name_y,
+                );
+                if column.milestone {
+                    let x = column.center_x();
+                    extend(x, column.y_start, x, column.y_start);
+                } else {
+                    extend(column.x_start, column.y_start, column.x_end, column.y_end);
+                }
+            }
+            if let Some(today_y) = vertical.today_y {
+                let label_x = vertical.chart_end_x + 2;
+                extend(
+                    vertical.chart_x,
+                    today_y,
+                    text_end(label_x, TODAY_LABEL),
+                    today_y,
+                );
+            }
+        }
+
+        for tick in &self.tick_layouts {
+            extend(tick.x, self.tick_start_y, tick.x, self.tick_end_y);
+            if let Some(label) = &tick.label {
+                let label_x = tick.x.saturating_sub(label.width() / 2);
+                extend(label_x, self.axis_y, text_end(label_x, label), self.axis_y);
+            }
+        }
+
+        for task in &self.task_layouts {
+            // Boxes are three rows high
+            extend(task.x_start, task.y, task.x_end, task.y + 2);
+
+            let name_y = task.y + 1;
+            if let Some(name_x) = task.name_x {
+                extend(name_x, name_y, text_end(name_x, &task.name), name_y);
+            } else if task.name.width() > task.x_end.saturating_sub(task.x_start + 1) {
+                let (name_x, name) =
+                    outside_name_placement(&task.name, task.x_start, task.x_end, self.width);
+                if !name.is_empty() {
+                    extend(name_x, name_y, text_end(name_x, &name), name_y);
+                }
+            }
+
+            if let Some(actual) = &task.actual {
+                extend(actual.x_start, actual.y, actual.x_end, actual.y);
+            }
+        }
+
+        for milestone in &self.milestone_layouts {
+            extend(milestone.x, milestone.y, milestone.x, milestone.y);
+            extend(
+                milestone.name_x,
+                milestone.y,
+                text_end(milestone.name_x, &milestone.name),
+                milestone.y,
+            );
+            if let Some(actual) = &milestone.actual {
+                extend(actual.x_start, actual.y, actual.x_end, actual.y);
+            }
+        }
+
+        for dependency in &self.dependency_layouts {
+            extend(
+                dependency.turn_x.min(dependency.to_x),
+                dependency.from_y,
+                dependency.from_x.max(dependency.to_x),
+                dependency.to_y,
+            );
+        }
+
+        if let (Some(first_tick), Some(last_tick)) =
+            (self.tick_layouts.first(), self.tick_layouts.last())
+        {
+            for &y in &self.section_rules {
+                extend(first_tick.x, y, last_tick.x, y);
+            }
+        }
+
+        for &x in &self.axis_breaks {
+            extend(x, self.tick_start_y, x, self.axis_y);
+        }
+
+        for guide in &self.guides {
+            extend(guide.x_start, guide.y, guide.x_end, guide.y);
+        }
+
+        for section in &self.section_layouts {
+            // Drawn with a "▸ " prefix
+            let header_width = section.name.width() + 2;
+            extend(
+                section.x,
+                section.y,
+                section.x + header_width - 1,
+                section.y,
+            );
+        }
+
+        for lane in &self.lane_layouts {
+            extend(lane.x, lane.y, text_end(lane.x, &lane.name), lane.y);
+            for &y in &lane.overlap_ys {
+                extend(lane.x, y, text_end(lane.x, LANE_OVERLAP_LABEL), y);
+            }
+        }
+
+        for summary in &self.summary_layouts {
+            extend(summary.x_start, summary.y, summary.x_end, summary.y);
+            if !summary.label.is_empty() {
+                let label_end = text_end(summary.label_x, &summary.label);
+                extend(summary.label_x, summary.y, label_end, summary.y);
+            }
+        }
+
+        for line in &self.title_layouts {
+            extend(line.x, line.y, text_end(line.x, &line.text), line.y);
+        }
+
+        for entry in &self.legend {
+            extend(
+                entry.x,
+                entry.y,
+                entry.x + 2 + entry.label.width() - 1,
+                entry.y,
+            );
+        }
+
+        if let Some(weekday_y) = self.weekday_y {
+            for tick in &self.tick_layouts {
+                extend(tick.x, weekday_y, tick.x, weekday_y);
+            }
+        }
+
+        for &(x_start, x_end) in &self.weekend_columns {
+            extend(x_start, self.tick_start_y, x_end, self.tick_end_y);
+        }
+
+        for stripe in &self.stripes {
+            extend(stripe.x_start, stripe.y_start, stripe.x_end, stripe.y_end);
+        }
+
+        if let Some(month_y) = self.month_y {
+            for month in &self.months {
+                if month.starts_on_chart {
+                    extend(month.x_start, month_y, month.x_start, month_y);
+                }
+                if !month.label.is_empty() {
+                    let label_x = month.label_x();
+                    extend(label_x, month_y, text_end(label_x, &month.label), month_y);
+                }
+            }
+        }
+
+        if let Some(today_marker) = &self.today_marker {
+            extend(
+                today_marker.x,
+                self.tick_start_y,
+                today_marker.x,
+                self.tick_end_y,
+            );
+            let label_y = self.axis_y + 1;
+            if label_y < self.height {
+                let label_x = today_marker.x.saturating_sub(TODAY_LABEL.width() / 2);
+                extend(label_x, label_y, text_end(label_x, TODAY_LABEL), label_y);
+            }
+        }
+
+        bounds.unwrap_or((0, 0, 0, 0))
+    }
+}
+
+/// Label under the today marker
+pub const TODAY_LABEL: &str = "today";
+/// Lane name of the tasks without an assignee
+pub const UNASSIGNED_LANE: &str = "unassigned";
+/// Name of the row summarizing the whole chart
+pub const PROJECT_SUMMARY_NAME: &str = "Project";
+/// Gutter label of the extra rows of a lane with overlapping tasks
+pub const LANE_OVERLAP_LABEL: &str = "↳ overlap";
+
 pub const MARGIN_LEFT: usize = 6;
 pub const MARGIN_RIGHT: usize = 6;
 pub const MARGIN_TOP: usize = 2;
 pub const MARGIN_BOTTOM: usize = 3;
 
 pub const CHART_WIDTH: usize = 120;
+/// Rows of the timeline of a vertical chart
+pub const CHART_HEIGHT: usize = 40;
+/// Blank columns between the bands of a vertical chart
+pub const COLUMN_GAP: usize = 2;
+/// Narrowest band of a vertical chart, leaving one column inside the box
+pub const MIN_COLUMN_WIDTH: usize = 3;
 pub const TASK_HEIGHT: usize = 3;
+pub const MILESTONE_HEIGHT: usize = 1;
+/// Columns between a milestone's diamond and its name
+pub const MILESTONE_LABEL_GAP: usize = 2;
+pub const ACTUAL_BAR_HEIGHT: usize = 1;
+pub const SUMMARY_HEIGHT: usize = 1;
+pub const SECTION_HEADER_HEIGHT: usize = 1;
+/// Rows between the last title line and the chart: a blank row and the top of the tick lines
+pub const TITLE_SPACING: usize = 2;
+/// Blank rows above a section header that follows other rows
+pub const SECTION_SPACING: usize = 1;
 pub const MIN_TICK_SPACING: usize = 12;
+/// Tick lines closer than this many columns are thinned out to a coarser interval
+pub const MIN_TICK_LINE_SPACING: usize = 2;
+/// Blank columns between two legend entries
+pub const LEGEND_ENTRY_GAP: usize = 2;
+/// Minimum distance between the left and right border of a task bar, leaving at least one
+/// column inside the box
+pub const MIN_BAR_SPAN: usize = 2;
+
+/// Charts spanning fewer days than this get a tick every day
+pub const DAILY_TICKS_MAX_DAYS: i64 = 14;
+/// Charts spanning fewer months than this get a tick every Monday, longer ones every month
+pub const WEEKLY_TICKS_MAX_MONTHS: u32 = 3;
+
+pub const TICK_LABEL_FORMAT: &str = "%d-%m-%Y";
+/// Columns taken by a compressed gap, from the tick before it to the tick after it
+pub const COMPRESSED_GAP_WIDTH: usize = 4;
+
+/// Blank columns between the task names in the label gutter and the chart
+pub const GUTTER_PADDING: usize = 2;
+/// Blank columns left of the task names in the label gutter
+pub const GUTTER_MARGIN_LEFT: usize = 1;
+
+/// Faint horizontal guides that help follow a row across a long chart
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RowGuides {
+    #[default]
+    Off,
+    /// A dotted guide on every other row
+    AlternateRows,
+    /// A line along the name row of each task and the row of each milestone. Bars are stacked
+    /// without blank rows, so this is the row free of box borders.
+    TaskLines,
+}
+
+impl RowGuides {
+    /// Character used when `GanttLayoutConfig::guide_char` is not set
+    pub fn default_char(self) -> char {
+        match self {
+            RowGuides::Off | RowGuides::AlternateRows => '·',
+            RowGuides::TaskLines => '┈',
+        }
+    }
+}
+
+/// Task durations added to the chart, e.g. `10d`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DurationLabels {
+    #[default]
+    Off,
+    /// After each task name, as in `Implementation (10d)`
+    Append,
+    /// Inside the bars that have no room for the name, or no name with the label gutter
+    NarrowBars,
+}
+
+/// Rows summarizing the span of several tasks as a single bar
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SummaryRows {
+    #[default]
+    Off,
+    /// A row above the first task spanning the whole chart
+    Project,
+    /// A row below each section header spanning the tasks of the section
+    Sections,
+}
+
+/// Width of the timeline area of a horizontal chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WidthMode {
+    /// This many char columns, with the days spread over them
+    Fixed(usize),
+    /// This many columns per day, so that short charts are not stretched and long charts are
+    /// not cramped
+    FitDays(usize),
+    /// `CHART_WIDTH` columns, narrowed so that the whole chart, with the label gutter and the
+    /// margins, is at most this many columns wide
+    Max(usize),
+}
+
+impl Default for WidthMode {
+    fn default() -> Self {
+        WidthMode::Fixed(CHART_WIDTH)
+    }
+}
+
+impl From<usize> for WidthMode {
+    fn from(chart_width: usize) -> Self {
+        WidthMode::Fixed(chart_width)
+    }
+}
+
+/// Direction time flows in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Orientation {
+    /// Left to right, with a row per task
+    #[default]
+    Horizontal,
+    /// Downwards, with a column band per task and the dates left of the chart. The bands are
+    /// `VerticalLayout::columns`, and `GanttLayout::task_layouts` stays empty. Dependencies,
+    /// actual dates and section headers are left out, and the `GanttLayoutConfig` options
+    /// `chart_width`, `label_placement`, `section_rules`, `row_guides`, `row_stripes`,
+    /// `month_header`, `weekday_letters`, `weekend_shading`, `compress_gaps`, `resource_lanes`
+    /// and `summary_rows` are ignored.
+    Vertical,
+}
+
+/// Where task names are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LabelPlacement {
+    /// Inside the bar, or right of it when the bar is too narrow
+    #[default]
+    Inline,
+    /// Left-aligned in a column left of the chart, sized to the longest task name
+    Gutter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Margins {
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            left: MARGIN_LEFT,
+            right: MARGIN_RIGHT,
+            top: MARGIN_TOP,
+            bottom: MARGIN_BOTTOM,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GanttLayoutConfig {
+    /// Width of the timeline area, excluding margins. Vertical charts ignore it.
+    pub chart_width: WidthMode,
+    pub orientation: Orientation,
+    /// Height of the timeline area in rows with `Orientation::Vertical`
+    pub chart_height: usize,
+    pub task_height: usize,
+    pub margins: Margins,
+    pub min_tick_spacing: usize,
+    /// Vertical charts ignore it
+    pub label_placement: LabelPlacement,
+    /// Draw the today marker even without the `today marker: on` directive
+    pub today_marker: bool,
+    /// Draw a horizontal rule in the blank row above each section header. Vertical charts
+    /// ignore it.
+    pub section_rules: bool,
+    /// List the sections, statuses and markers used by the chart below the axis
+    pub legend: bool,
+    /// Name the months on a row above the tick labels, for charts covering several months.
+    /// Vertical charts ignore it.
+    pub month_header: bool,
+    /// Print the weekday initial (`M T W T F S S`) above the tick labels when ticks are daily.
+    /// Vertical charts ignore it.
+    pub weekday_letters: bool,
+    /// Fill the blank columns of Saturdays and Sundays with `·` when ticks are daily. Vertical
+    /// charts ignore it.
+    pub weekend_shading: bool,
+    /// Horizontal guides across the chart area, drawn behind the bars. Vertical charts ignore
+    /// it.
+    pub row_guides: RowGuides,
+    /// Overrides the character of the row guides
+    pub guide_char: Option<char>,
+    /// Shade the rows of the first, third, fifth... task or milestone across the chart area,
+    /// behind the bars and the tick lines. Vertical charts ignore it.
+    pub row_stripes: bool,
+    pub duration_labels: DurationLabels,
+    /// Count durations in working days, Monday to Friday, shown as `10wd`
+    pub working_days: bool,
+    /// Collapse stretches of more than this many days in which no task starts or ends to
+    /// `COMPRESSED_GAP_WIDTH` columns, marked with a break. Vertical charts ignore it.
+    pub compress_gaps: Option<usize>,
+    /// Give each assignee a row of bars named in the label gutter, in order of their first
+    /// task, followed by the tasks without an assignee. Names are drawn inside the bars or
+    /// left out. Dependencies, actual dates and section headers are not drawn in lanes, and
+    /// vertical charts ignore it.
+    pub resource_lanes: bool,
+    /// Summary bars are laid out from the planned dates and are not tasks: they are left out
+    /// of dependencies and of `continue`. Resource lanes and vertical charts have none.
+    pub summary_rows: SummaryRows,
+    /// Source of today's date for the today marker
+    pub clock: fn() -> NaiveDate,
+}
+
+fn system_today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+impl Default for GanttLayoutConfig {
+    fn default() -> Self {
+        GanttLayoutConfig {
+            chart_width: WidthMode::default(),
+            orientation: Orientation::Horizontal,
+            chart_height: CHART_HEIGHT,
+            task_height: TASK_HEIGHT,
+            margins: Margins::default(),
+            min_tick_spacing: MIN_TICK_SPACING,
+            label_placement: LabelPlacement::default(),
+            today_marker: false,
+            section_rules: false,
+            legend: false,
+            month_header: false,
+            weekday_letters: false,
+            weekend_shading: false,
+            row_guides: RowGuides::Off,
+            guide_char: None,
+            row_stripes: false,
+            duration_labels: DurationLabels::Off,
+            working_days: false,
+            compress_gaps: None,
+            resource_lanes: false,
+            summary_rows: SummaryRows::Off,
+            clock: system_today,
+        }
+    }
+}
 
 pub fn layout(gantt_chart: &GanttChart) -> GanttLayout {
-    let (min_date, max_date) = find_date_range(gantt_chart);
-    let total_days = (max_date - min_date).num_days() as usize;
+    layout_with_config(gantt_chart, &GanttLayoutConfig::default())
+}
+
+/// Width and height in char cells of the rendered chart, without rendering it
+pub fn measure(input: &str) -> Result<(usize, usize), ParseError> {
+    measure_with_config(input, &GanttLayoutConfig::default())
+}
+
+pub fn measure_with_config(
+    input: &str,
+    config: &GanttLayoutConfig,
+) -> Result<(usize, usize), ParseError> {
+    let gantt_layout = layout_with_config(&parse(input)?, config);
+    Ok((gantt_layout.width, gantt_layout.height))
+}
+
+/// Why a chart has no timeline of its own, from `try_layout_with_config`
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    /// The chart has no tasks to take the dates of the axis from
+    NoTasks,
+    /// Every task, milestone and actual date of the chart falls on this date.
+    /// `layout_with_config` draws such a chart on an axis of one tick interval from the date.
+    ZeroSpan(NaiveDate),
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::NoTasks => write!(f, "Layout error: the chart has no tasks"),
+            LayoutError::ZeroSpan(date) => write!(
+                f,
+                "Layout error: every task falls on {}",
+                date.format(TICK_LABEL_FORMAT)
+            ),
+        }
+    }
+}
+
+pub fn try_layout(gantt_chart: &GanttChart) -> Result<GanttLayout, LayoutError> {
+    try_layout_with_config(gantt_chart, &GanttLayoutConfig::default())
+}
+
+/// Lays out the chart like `layout_with_config`, but fails where that has no dates to take the
+/// axis from: a chart without tasks, which `layout_with_config` gives an empty axis around
+/// today, and a chart whose dates are all one date, which it spreads over a tick interval. Use
+/// it to reject such charts; `layout_with_config` draws every chart.
+pub fn try_layout_with_config(
+    gantt_chart: &GanttChart,
+    config: &GanttLayoutConfig,
+) -> Result<GanttLayout, LayoutError> {
+    if gantt_chart.tasks.is_empty() {
+        return Err(LayoutError::NoTasks);
+    }
+    let (min_date, max_date) = gantt_chart.date_range();
+    if min_date == max_date {
+        return Err(LayoutError::ZeroSpan(min_date));
+    }
+    Ok(layout_with_config(gantt_chart, config))
+}
+
+pub fn layout_with_config(gantt_chart: &GanttChart, config: &GanttLayoutConfig) -> GanttLayout {
+    if config.orientation == Orientation::Vertical {
+        return layout_vertical(gantt_chart, config);
+    }
+
+    let margins = &config.margins;
+    // A chart without tasks gets an empty axis around today
+    let (min_date, max_date) = if gantt_chart.tasks.is_empty() {
+        let today = (config.clock)();
+        (today, today)
+    } else {
+        gantt_chart.date_range()
+    };
+
+    let quiet_periods = match config.compress_gaps {
+        Some(min_days) => quiet_periods(gantt_chart, min_days),
+        None => Vec::new(),
+    };
+    // Ticks are spaced for the days that stay on the axis
+    let quiet_days: i64 = quiet_periods
+        .iter()
+        .map(|&(start, end)| (end - start).num_days())
+        .sum();
+    let shown_end = min_date
+        .checked_add_days(Days::new(
+            ((max_date - min_date).num_days() - quiet_days) as u64,
+        ))
+        .unwrap_or(max_date);
+
+    // Columns per day before the axis is rounded to whole ticks, which the ticks are picked for
+    let shown_days = max((shown_end - min_date).num_days(), 1) as usize;
+    let max_chart_width = |max_width: usize| {
+        min(
+            CHART_WIDTH,
+            max_width.saturating_sub(reserved_width(gantt_chart, config) + margins.right),
+        )
+    };
+    let pixels_per_day = match config.chart_width {
+        WidthMode::Fixed(chart_width) => Ratio::new(chart_width, shown_days),
+        WidthMode::FitDays(columns) => Ratio::from_integer(columns),
+        WidthMode::Max(max_width) => Ratio::new(max_chart_width(max_width), shown_days),
+    };
+
+    // The axis is widened to whole tick intervals so that ticks land on calendar dates
+    let tick_interval =
+        TickInterval::for_scale(min_date, shown_end, pixels_per_day, config.min_tick_spacing);
+    let axis_start = tick_interval.floor(min_date);
+    // A chart of milestones on a single date has no span. Give it one tick interval so that
+    // the days can be spread over the chart width.
+    let axis_end = max(tick_interval.ceil(max_date), tick_interval.next(axis_start));
+
+    let gaps = compressed_gaps(
+        &quiet_periods,
+        tick_interval,
+        config.compress_gaps.unwrap_or(0),
+    );
+    let timeline = match config.chart_width {
+        WidthMode::Fixed(chart_width) => Timeline::new(axis_start, axis_end, gaps, chart_width),
+        WidthMode::FitDays(columns) => {
+            Timeline::with_day_width(axis_start, axis_end, gaps, columns)
+        }
+        WidthMode::Max(max_width) => {
+            Timeline::new(axis_start, axis_end, gaps, max_chart_width(max_width))
+        }
+    };
+    let chart_width = timeline.width;
+
+    let lanes = config
+        .resource_lanes
+        .then(|| assign_lanes(gantt_chart, &timeline));
+
+    // First column of the timeline
+    let chart_x = match (&lanes, config.label_placement) {
+        (Some(lanes), _) => lane_gutter_width(lanes) + GUTTER_PADDING,
+        (None, LabelPlacement::Inline) => margins.left,
+        (None, LabelPlacement::Gutter) => gutter_width(gantt_chart, config) + GUTTER_PADDING,
+    };
+    let width = chart_x + chart_width + margins.right;
+
+    let title_layouts = layout_title(gantt_chart, chart_x + chart_width / 2, width, margins.top);
+    // First row of the tasks, below the title
+    let top_y = title_end_y(&title_layouts).unwrap_or(margins.top);
+
+    let TaskRows {
+        task_layouts,
+        milestone_layouts,
+        dependency_layouts,
+        section_layouts,
+        lane_layouts,
+        summary_layouts,
+        end_y: tasks_end_y,
+    } = match &lanes {
+        Some(lanes) => layout_lanes(gantt_chart, lanes, &timeline, chart_x, top_y, config),
+        None => layout_tasks(gantt_chart, &timeline, chart_x, top_y, config),
+    };
+    let tick_layouts = layout_ticks(&timeline, tick_interval, chart_x);
+    let axis_breaks = timeline
+        .gaps
+        .iter()
+        .map(|&(start, _)| timeline.date_to_x(start) + COMPRESSED_GAP_WIDTH / 2 + chart_x)
+        .collect();
+
+    // The spacer row above a header separates it from the rows before it
+    let section_rules = if config.section_rules {
+        section_layouts
+            .iter()
+            .filter(|section| section.y > top_y)
+            .map(|section| section.y - SECTION_SPACING)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Guides stay in the chart area, out of the margins and the label gutter
+    let guide_row = |y: usize| GuideLayout {
+        x_start: chart_x,
+        x_end: chart_x + chart_width.saturating_sub(1),
+        y,
+    };
+    let guides = match config.row_guides {
+        RowGuides::Off => Vec::new(),
+        RowGuides::AlternateRows => (top_y..tasks_end_y).step_by(2).map(guide_row).collect(),
+        RowGuides::TaskLines => {
+            let task_rows = task_layouts.iter().map(|task| task.y + 1);
+            let milestone_rows = milestone_layouts.iter().map(|milestone| milestone.y);
+            let mut rows: Vec<usize> = task_rows.chain(milestone_rows).collect();
+            rows.sort();
+            // Bars in resource lanes share rows
+            rows.dedup();
+            rows.into_iter().map(guide_row).collect()
+        }
+    };
+
+    let stripes = if config.row_stripes {
+        layout_stripes(
+            &task_layouts,
+            &milestone_layouts,
+            chart_x,
+            chart_x + chart_width.saturating_sub(1),
+        )
+    } else {
+        Vec::new()
+    };
+
+    let today_marker = if gantt_chart.today_marker || config.today_marker {
+        let today = (config.clock)();
+        (min_date <= today && today <= max_date).then(|| TodayMarkerLayout {
+            x: timeline.date_to_x(today) + chart_x,
+            date: today,
+        })
+    } else {
+        None
+    };
+
+    let months = if config.month_header {
+        layout_months(&timeline, chart_x)
+    } else {
+        Vec::new()
+    };
 
-    // How many pixels (char columns) represent one day
-    let pixels_per_day = Ratio::new(CHART_WIDTH, total_days);
+    let daily = tick_interval == TickInterval::Day;
+    let weekend_columns = if daily && config.weekend_shading {
+        layout_weekends(&timeline, chart_x)
+    } else {
+        Vec::new()
+    };
 
-    let task_layouts = layout_tasks(gantt_chart, min_date, pixels_per_day);
-    let tick_layouts = layout_ticks(min_date, total_days);
+    let tick_end_y = tasks_end_y;
+    // The month header and the weekday initials take the rows between the tick lines and the
+    // tick labels
+    let month_y = (!months.is_empty()).then_some(tick_end_y + 1);
+    let weekday_y = (daily && config.weekday_letters).then_some(month_y.unwrap_or(tick_end_y) + 1);
+    let axis_y = weekday_y.or(month_y).unwrap_or(tick_end_y) + 1;
+    let mut height = axis_y - 1 + margins.bottom;
 
-    let height = TASK_HEIGHT * gantt_chart.tasks.len() + MARGIN_TOP + MARGIN_BOTTOM;
-    let width = CHART_WIDTH + MARGIN_LEFT + MARGIN_RIGHT;
+    // The legend goes below the bottom margin, which holds the axis labels
+    let legend = if config.legend {
+        let legend_x = match config.label_placement {
+            LabelPlacement::Inline if !config.resource_lanes => chart_x,
+            _ => GUTTER_MARGIN_LEFT,
+        };
+        let legend = layout_legend(gantt_chart, legend_x, height, width - margins.right);
+        if let Some(last_entry) = legend.last() {
+            height = last_entry.y + 1;
+        }
+        legend
+    } else {
+        Vec::new()
+    };
 
     GanttLayout {
         task_layouts,
+        milestone_layouts,
+        dependency_layouts,
         tick_layouts,
+        tick_interval,
+        section_layouts,
+        lane_layouts,
+        summary_layouts,
+        section_rules,
+        guides,
+        guide_char: config
+            .guide_char
+            .unwrap_or_else(|| config.row_guides.default_char()),
+        stripes,
+        axis_breaks,
+        months,
+        month_y,
+        weekday_y,
+        weekend_columns,
+        legend,
+        title_layouts,
+        today_marker,
+        tick_start_y: top_y.saturating_sub(1),
+        tick_end_y,
+        axis_y,
+        vertical: None,
         width,
         height,
     }
 }
 
+/// Lays out a chart with time flowing downwards: the task names on a row above the chart, a
+/// column band per task below them, and the tick dates left of the axis
+fn layout_vertical(gantt_chart: &GanttChart, config: &GanttLayoutConfig) -> GanttLayout {
+    let margins = &config.margins;
+    let (min_date, max_date) = if gantt_chart.tasks.is_empty() {
+        let today = (config.clock)();
+        (today, today)
+    } else {
+        gantt_chart.date_range()
+    };
+
+    let tick_interval = TickInterval::for_range(min_date, max_date);
+    let axis_start = tick_interval.floor(min_date);
+    let axis_end = max(tick_interval.ceil(max_date), tick_interval.next(axis_start));
+    // Rows are spread over the days like the columns of a horizontal chart
+    let timeline = Timeline::new(axis_start, axis_end, Vec::new(), config.chart_height);
+
+    let label_x = margins.left;
+    let label_width = axis_start.format(TICK_LABEL_FORMAT).to_string().width();
+    let axis_x = label_x + label_width + 1;
+    let chart_x = axis_x + 2;
+
+    // Bands are as wide as the task names
+    let names: Vec<String> = gantt_chart
+        .tasks
+        .iter()
+        .map(|task| task_label(task, config))
+        .collect();
+    let band_widths: Vec<usize> = names
+        .iter()
+        .map(|name| max(name.width(), MIN_COLUMN_WIDTH))
+        .collect();
+    let bands_width =
+        band_widths.iter().sum::<usize>() + COLUMN_GAP * band_widths.len().saturating_sub(1);
+    let chart_end_x = max(chart_x, (chart_x + bands_width).saturating_sub(1));
+    let width = chart_end_x + 1 + margins.right;
+
+    let title_layouts = layout_title(
+        gantt_chart,
+        chart_x + (chart_end_x - chart_x) / 2,
+        width,
+        margins.top,
+    );
+    let header_y = title_end_y(&title_layouts).unwrap_or(margins.top);
+    // A blank row between the names and the first tick
+    let chart_y = header_y + 2;
+    let chart_end_y = chart_y + config.chart_height;
+
+    let critical_path = gantt_chart.critical_path_indices();
+    let mut columns = Vec::new();
+    let mut x = chart_x;
+    for (index, (task, name)) in gantt_chart.tasks.iter().zip(names).enumerate() {
+        let band_width = band_widths[index];
+        let milestone = task.is_milestone();
+
+        let mut y_start = timeline.date_to_x(task.start_date) + chart_y;
+        let mut y_end = timeline.date_to_x(task.end_date) + chart_y;
+        // Short tasks are lengthened downwards, or upwards at the end of the chart
+        if !milestone && y_end < y_start + MIN_BAR_SPAN {
+            y_end = y_start + MIN_BAR_SPAN;
+            if y_end > chart_end_y {
+                y_end = chart_end_y;
+                y_start = chart_end_y.saturating_sub(MIN_BAR_SPAN);
+            }
+        }
+        let fill_until_y = task.progress.filter(|_| !milestone).map(|progress| {
+            let interior_height = y_end.saturating_sub(y_start + 1);
+            y_start + 1 + interior_height * progress as usize / 100
+        });
+
+        columns.push(ColumnLayout {
+            x_start: x,
+            x_end: x + band_width - 1,
+            y_start,
+            y_end,
+            name,
+            milestone,
+            fill_until_y,
+            section: gantt_chart
+                .sections
+                .iter()
+                .position(|section| section.tasks.contains(&index)),
+            status: task.status,
+            critical: task.critical,
+            on_critical_path: critical_path.contains(&index),
+        });
+        x += band_width + COLUMN_GAP;
+    }
+
+    let mut ticks: Vec<RowTickLayout> = Vec::new();
+    let mut date = axis_start;
+    while date <= axis_end {
+        let y = timeline.date_to_x(date) + chart_y;
+        let row_taken = ticks
+            .iter()
+            .rev()
+            .find(|tick| tick.label.is_some())
+            .is_some_and(|tick| tick.y == y);
+        ticks.push(RowTickLayout {
+            y,
+            date,
+            label: (!row_taken).then(|| date.format(TICK_LABEL_FORMAT).to_string()),
+        });
+        let next_date = tick_interval.next(date);
+        if next_date == date {
+            // Reached the last representable date
+            break;
+        }
+        date = next_date;
+    }
+
+    let today_y = if gantt_chart.today_marker || config.today_marker {
+        let today = (config.clock)();
+        (min_date <= today && today <= max_date).then(|| timeline.date_to_x(today) + chart_y)
+    } else {
+        None
+    };
+
+    let mut height = chart_end_y + margins.bottom;
+    let legend = if config.legend {
+        let legend = layout_legend(gantt_chart, label_x, height, width - margins.right);
+        if let Some(last_entry) = legend.last() {
+            height = last_entry.y + 1;
+        }
+        legend
+    } else {
+        Vec::new()
+    };
+
+    GanttLayout {
+        task_layouts: Vec::new(),
+        milestone_layouts: Vec::new(),
+        dependency_layouts: Vec::new(),
+        tick_layouts: Vec::new(),
+        tick_interval,
+        section_layouts: Vec::new(),
+        lane_layouts: Vec::new(),
+        summary_layouts: Vec::new(),
+        section_rules: Vec::new(),
+        guides: Vec::new(),
+        guide_char: config
+            .guide_char
+            .unwrap_or_else(|| config.row_guides.default_char()),
+        stripes: Vec::new(),
+        axis_breaks: Vec::new(),
+        months: Vec::new(),
+        month_y: None,
+        weekday_y: None,
+        weekend_columns: Vec::new(),
+        legend,
+        title_layouts,
+        today_marker: None,
+        tick_start_y: 0,
+        tick_end_y: 0,
+        axis_y: 0,
+        vertical: Some(VerticalLayout {
+            columns,
+            ticks,
+            label_x,
+            axis_x,
+            chart_x,
+            chart_end_x,
+            header_y,
+            axis_start_y: chart_y,
+            axis_end_y: chart_end_y,
+            today_y,
+        }),
+        width,
+        height,
+    }
+}
+
+/// Lays out the title and subtitle lines from row `y`, each wrapped to the canvas `width` and
+/// centered on `center_x` as far as the canvas leaves room
+fn layout_title(
+    gantt_chart: &GanttChart,
+    center_x: usize,
+    width: usize,
+    mut y: usize,
+) -> Vec<TitleLayout> {
+    let mut layouts = Vec::new();
+    for (text, subtitle) in [(&gantt_chart.title, false), (&gantt_chart.subtitle, true)] {
+        let Some(text) = text else {
+            continue;
+        };
+        for line in wrap_text(text, width) {
+            let line_width = line.width();
+            let x = center_x
+                .saturating_sub(line_width / 2)
+                .min(width.saturating_sub(line_width));
+            layouts.push(TitleLayout {
+                x,
+                y,
+                text: line,
+                subtitle,
+            });
+            y += 1;
+        }
+    }
+    layouts
+}
+
+/// First row of the chart below the title. `None` without a title.
+fn title_end_y(title_layouts: &[TitleLayout]) -> Option<usize> {
+    title_layouts.last().map(|line| line.y + 1 + TITLE_SPACING)
+}
+
+/// Splits `text` at spaces into lines of at most `max_width` columns. Words longer than a line
+/// are split too.
+fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max(max_width, 1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut pieces = Vec::new();
+        let mut piece = String::new();
+        for ch in word.chars() {
+            if !piece.is_empty() && piece.width() + ch.width().unwrap_or(0) > max_width {
+                pieces.push(std::mem::take(&mut piece));
+            }
+            piece.push(ch);
+        }
+        pieces.push(piece);
+
+        for piece in pieces {
+            if !line.is_empty() {
+                if line.width() + 1 + piece.width() > max_width {
+                    lines.push(std::mem::take(&mut line));
+                } else {
+                    line.push(' ');
+                }
+            }
+            line.push_str(&piece);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lays out the legend entries from left to right starting at `(x, y)`, wrapping to a new row
+/// before `max_x`: the statuses and markers, then the section palette on a row of its own. Only
+/// what the chart uses is listed.
+fn layout_legend(
+    gantt_chart: &GanttChart,
+    x: usize,
+    y: usize,
+    max_x: usize,
+) -> Vec<LegendEntryLayout> {
+    let tasks = &gantt_chart.tasks;
+    let mut markers_used = Vec::new();
+    // Statuses and `crit` only change the color of a bar, so like sections they get a swatch
+    // rather than a glyph of their own
+    let markers = [
+        ('■', "done", LegendKind::Status(TaskStatus::Done)),
+        ('■', "active", LegendKind::Status(TaskStatus::Active)),
+        ('■', "critical", LegendKind::Critical),
+        ('◆', "milestone", LegendKind::Milestone),
+    ];
+    for (symbol, label, kind) in markers {
+        let used = match kind {
+            LegendKind::Status(status) => tasks.iter().any(|task| task.status == Some(status)),
+            LegendKind::Critical => tasks.iter().any(|task| task.critical),
+            LegendKind::Milestone => tasks.iter().any(|task| task.is_milestone()),
+            LegendKind::Section(_) => false,
+        };
+        if used {
+            markers_used.push((symbol, label.to_string(), kind));
+        }
+    }
+    // Sections without tasks have no bars to explain
+    let sections = gantt_chart
+        .sections
+        .iter()
+        .enumerate()
+        .filter(|(_, section)| !section.tasks.is_empty())
+        .map(|(index, section)| ('■', section.name.clone(), LegendKind::Section(index)))
+        .collect();
+
+    // The markers on the first row, the section palette from the next one
+    let mut layouts: Vec<LegendEntryLayout> = Vec::new();
+    for entries in [markers_used, sections] {
+        let (mut entry_x, mut entry_y) = (x, layouts.last().map_or(y, |entry| entry.y + 1));
+        for (symbol, label, kind) in entries {
+            // The symbol and a space before the label
+            let entry_width = 2 + label.width();
+            if entry_x > x && entry_x + entry_width > max_x {
+                entry_x = x;
+                entry_y += 1;
+            }
+            layouts.push(LegendEntryLayout {
+                x: entry_x,
+                y: entry_y,
+                symbol,
+                label,
+                kind,
+            });
+            entry_x += entry_width + LEGEND_ENTRY_GAP;
+        }
+    }
+
+    layouts
+}
+
+/// Lays out the months overlapping the axis. Empty when the axis covers a single month.
+fn layout_months(timeline: &Timeline, chart_x: usize) -> Vec<MonthLayout> {
+    let (axis_start, _) = timeline.anchors[0];
+    let (axis_end, _) = timeline.anchors[timeline.anchors.len() - 1];
+
+    let mut months = Vec::new();
+    let mut month_start = axis_start.with_day(1).unwrap_or(axis_start);
+    while month_start < axis_end {
+        let Some(next_month) = month_start.checked_add_months(Months::new(1)) else {
+            break;
+        };
+        let start = max(month_start, axis_start);
+        let end = next_month.min(axis_end);
+        let x_start = timeline.date_to_x(start) + chart_x;
+        let x_end = timeline.date_to_x(end) + chart_x;
+
+        // Leave the columns of the separators at both ends
+        let room = x_end.saturating_sub(x_start + 1);
+        months.push(MonthLayout {
+            x_start,
+            x_end,
+            label: month_label(month_start, room),
+            starts_on_chart: start == month_start,
+        });
+        month_start = next_month;
+    }
+
+    if months.len() < 2 {
+        months.clear();
+    }
+    months
+}
+
+/// The longest name of the month that fits in `room` columns, or the short name clipped to it
+fn month_label(month_start: NaiveDate, room: usize) -> String {
+    let formats = ["%B %Y", "%b %Y", "%b"];
+    for format in formats {
+        let label = month_start.format(format).to_string();
+        if label.width() <= room {
+            return label;
+        }
+    }
+    month_start
+        .format("%b")
+        .to_string()
+        .chars()
+        .take(room)
+        .collect()
+}
+
+/// Width of the label gutter: the left margin followed by the longest task name
+fn gutter_width(gantt_chart: &GanttChart, config: &GanttLayoutConfig) -> usize {
+    let longest_name = gantt_chart
+        .tasks
+        .iter()
+        .map(|task| task_label(task, config).width())
+        .max()
+        .unwrap_or(0);
+
+    GUTTER_MARGIN_LEFT + longest_name
+}
+
+/// Duration of a task as `10d`, or `10wd` in working days
+fn duration_text(task: &Task, config: &GanttLayoutConfig) -> String {
+    if config.working_days {
+        format!("{}wd", task.working_days())
+    } else {
+        format!("{}d", task.duration_days())
+    }
+}
+
+/// Name of a task with the duration appended by `DurationLabels::Append`. Milestones keep their
+/// name.
+fn task_label(task: &Task, config: &GanttLayoutConfig) -> String {
+    if config.duration_labels == DurationLabels::Append && !task.is_milestone() {
+        format!("{} ({})", task.name, duration_text(task, config))
+    } else {
+        task.name.clone()
+    }
+}
+
+/// Where a task name that does not fit in its bar is drawn on a canvas `width` columns wide:
+/// right of the bar, or left of it when more of the name fits there. Returns the first column
+/// and the name, truncated with `…` to the room it gets.
+pub(crate) fn outside_name_placement(
+    name: &str,
+    x_start: usize,
+    x_end: usize,
+    width: usize,
+) -> (usize, String) {
+    let right_x = x_end + 1;
+    let right_room = width.saturating_sub(right_x);
+    // A blank column separates the name from the left border
+    let left_room = x_start.saturating_sub(1);
+    let fit = |room: usize| {
+        if room == 0 {
+            String::new()
+        } else {
+            truncate_name(name, room)
+        }
+    };
+
+    if name.width() <= right_room || right_room >= left_room {
+        (right_x, fit(right_room))
+    } else {
+        let name = fit(left_room);
+        (x_start - 1 - name.width(), name)
+    }
+}
+
+/// Everything laid out row by row below the top margin
+struct TaskRows {
+    task_layouts: Vec<TaskLayout>,
+    milestone_layouts: Vec<MilestoneLayout>,
+    dependency_layouts: Vec<DependencyLayout>,
+    section_layouts: Vec<SectionLayout>,
+    lane_layouts: Vec<LaneLayout>,
+    summary_layouts: Vec<SummaryLayout>,
+    /// First row after the last task
+    end_y: usize,
+}
+
+/// The tasks of an assignee, each on the first row of the lane where it overlaps no other task
+struct Lane<'a> {
+    name: &'a str,
+    /// Indices into `GanttChart::tasks` with the row of the lane they are on
+    tasks: Vec<(usize, usize)>,
+    rows: usize,
+}
+
+/// Where a task or milestone was placed, for routing dependency connectors
+struct BarBounds {
+    x_start: usize,
+    mid_y: usize,
+    bottom_y: usize,
+    /// Columns a connector can leave the bar through its bottom border
+    exit_x: (usize, usize),
+}
+
+/// Lays out the tasks row by row from `top_y`
 fn layout_tasks(
     gantt_chart: &GanttChart,
-    min_date: NaiveDate,
-    pixels_per_day: Ratio<usize>,
-) -> Vec<TaskLayout> {
+    timeline: &Timeline,
+    chart_x: usize,
+    top_y: usize,
+    config: &GanttLayoutConfig,
+) -> TaskRows {
+    let name_x = match config.label_placement {
+        LabelPlacement::Inline => None,
+        LabelPlacement::Gutter => Some(GUTTER_MARGIN_LEFT),
+    };
+    // Section headers line up with the task names in the gutter, or with the chart
+    let section_x = name_x.unwrap_or(chart_x);
     let mut task_layouts = Vec::new();
-    let mut y = MARGIN_TOP;
+    let mut milestone_layouts = Vec::new();
+    let mut section_layouts = Vec::new();
+    let mut summary_layouts = Vec::new();
+    let mut bounds = Vec::new();
+    let mut y = top_y;
+    let critical_path = gantt_chart.critical_path_indices();
 
-    for task in &gantt_chart.tasks {
-        let x_start = date_to_x(task.start_date, min_date, pixels_per_day) + MARGIN_LEFT;
-        let x_end = date_to_x(task.end_date, min_date, pixels_per_day) + MARGIN_LEFT;
+    let chart_end_x = chart_x + timeline.width;
+    let width = chart_end_x + config.margins.right;
+    let summary = |name: &str, tasks: &[Task], y: usize| {
+        layout_summary(name, tasks, timeline, chart_x, y, width, config)
+    };
+
+    if config.summary_rows == SummaryRows::Project && !gantt_chart.tasks.is_empty() {
+        summary_layouts.push(summary(PROJECT_SUMMARY_NAME, &gantt_chart.tasks, y));
+        y += SUMMARY_HEIGHT;
+    }
+
+    for (index, task) in gantt_chart.tasks.iter().enumerate() {
+        y = layout_section_headers(
+            gantt_chart,
+            index,
+            section_x,
+            y,
+            top_y,
+            &mut section_layouts,
+        );
+        // Of the sections starting here, only the last one can have tasks
+        if config.summary_rows == SummaryRows::Sections
+            && let Some(section) = gantt_chart
+                .sections
+                .iter()
+                .find(|section| section.tasks.start == index && !section.tasks.is_empty())
+        {
+            let tasks = &gantt_chart.tasks[section.tasks.clone()];
+            summary_layouts.push(summary(&section.name, tasks, y));
+            y += SUMMARY_HEIGHT;
+        }
+
+        let section = gantt_chart
+            .sections
+            .iter()
+            .position(|section| section.tasks.contains(&index));
+        let actual = |y: usize| {
+            task.actual.as_ref().map(|actual| ActualLayout {
+                x_start: timeline.date_to_x(actual.start_date) + chart_x,
+                x_end: timeline.date_to_x(actual.end_date) + chart_x,
+                y,
+            })
+        };
+
+        // Milestones take a row of their own so they never overwrite a bar
+        if task.is_milestone() {
+            let x = timeline.date_to_x(task.start_date) + chart_x;
+            let milestone_name_x = name_x.unwrap_or_else(|| {
+                let right_x = x + MILESTONE_LABEL_GAP;
+                if right_x + task.name.width() <= width {
+                    right_x
+                } else {
+                    x.saturating_sub(MILESTONE_LABEL_GAP - 1 + task.name.width())
+                }
+            });
+
+            bounds.push(BarBounds {
+                x_start: x,
+                mid_y: y,
+                bottom_y: y,
+                exit_x: (x, x),
+            });
+            milestone_layouts.push(MilestoneLayout {
+                x,
+                y,
+                name: task.name.clone(),
+                name_x: milestone_name_x,
+                section,
+                status: task.status,
+                critical: task.critical,
+                on_critical_path: critical_path.contains(&index),
+                actual: actual(y + MILESTONE_HEIGHT),
+            });
+
+            y += MILESTONE_HEIGHT;
+            if task.actual.is_some() {
+                y += ACTUAL_BAR_HEIGHT;
+            }
+            continue;
+        }
+
+        let (x_start, x_end) = bar_columns(task, timeline, chart_x);
+        let fill_until_x = progress_fill_x(task, x_start, x_end);
 
+        let name = task_label(task, config);
+        let interior_width = x_end.saturating_sub(x_start + 1);
+        let duration = Some(duration_text(task, config)).filter(|duration| {
+            config.duration_labels == DurationLabels::NarrowBars
+                && (name_x.is_some() || name.width() > interior_width)
+                && duration.width() <= interior_width
+        });
+
+        bounds.push(BarBounds {
+            x_start,
+            mid_y: y + 1,
+            bottom_y: y + 2,
+            exit_x: (x_start + 1, x_end.saturating_sub(1)),
+        });
         task_layouts.push(TaskLayout {
             x_start,
             x_end,
             y,
-            name: task.name.clone(),
+            name,
+            name_x,
+            duration,
+            fill_until_x,
+            section,
+            status: task.status,
+            critical: task.critical,
+            on_critical_path: critical_path.contains(&index),
+            actual: actual(y + config.task_height),
         });
 
-        y += TASK_HEIGHT;
+        y += config.task_height;
+        if task.actual.is_some() {
+            y += ACTUAL_BAR_HEIGHT;
+        }
+    }
+
+    // Sections without tasks at the end of the chart
+    y = layout_section_headers(
+        gantt_chart,
+        gantt_chart.tasks.len(),
+        section_x,
+        y,
+        top_y,
+        &mut section_layouts,
+    );
+
+    let dependency_layouts = gantt_chart
+        .tasks
+        .iter()
+        .enumerate()
+        .flat_map(|(index, task)| {
+            task.depends_on
+                .iter()
+                .filter_map(|&from| bounds.get(from))
+                .map(move |from| (from, index))
+        })
+        .map(|(from, index)| layout_dependency(from, &bounds[index]))
+        .collect();
+
+    TaskRows {
+        task_layouts,
+        milestone_layouts,
+        dependency_layouts,
+        section_layouts,
+        lane_layouts: Vec::new(),
+        summary_layouts,
+        end_y: y,
     }
+}
+
+/// Lays out a summary row spanning the planned dates of `tasks`, with its label centered on the
+/// bar, or placed like the name of a task when the bar is too narrow
+fn layout_summary(
+    name: &str,
+    tasks: &[Task],
+    timeline: &Timeline,
+    chart_x: usize,
+    y: usize,
+    width: usize,
+    config: &GanttLayoutConfig,
+) -> SummaryLayout {
+    let start_date = tasks.iter().map(|task| task.start_date).min().unwrap();
+    let end_date = tasks.iter().map(|task| task.end_date).max().unwrap();
+    let duration = if config.working_days {
+        format!("{}wd", working_days_between(start_date, end_date))
+    } else {
+        format!("{}d", (end_date - start_date).num_days())
+    };
+    let label = format!(
+        "{}: {} → {}, {}",
+        name,
+        start_date.format(TICK_LABEL_FORMAT),
+        end_date.format(TICK_LABEL_FORMAT),
+        duration
+    );
 
-    task_layouts
+    let x_start = timeline.date_to_x(start_date) + chart_x;
+    let x_end = timeline.date_to_x(end_date) + chart_x;
+    let span = x_end - x_start + 1;
+    let (label_x, label) = if label.width() + 2 <= span {
+        (
+            x_start + (span - label.width() - 2) / 2,
+            format!(" {} ", label),
+        )
+    } else {
+        outside_name_placement(&label, x_start, x_end, width)
+    };
+
+    SummaryLayout {
+        x_start,
+        x_end,
+        y,
+        label_x,
+        label,
+    }
 }
 
-fn layout_ticks(min_date: NaiveDate, total_days: usize) -> Vec<TickLayout> {
-    // TODO: Maybe need to improve this. Calculate ticks base on date range instead of fixed it.
-    let ticks_count = (CHART_WIDTH / MIN_TICK_SPACING).max(2);
+/// First and last column of the bar of a task. Short tasks are widened so their box can be
+/// drawn, which overstates their duration. Bars are widened to the right, or to the left at the
+/// end of the chart.
+fn bar_columns(task: &Task, timeline: &Timeline, chart_x: usize) -> (usize, usize) {
+    let chart_end_x = chart_x + timeline.width;
+    let mut x_start = timeline.date_to_x(task.start_date) + chart_x;
+    let mut x_end = timeline.date_to_x(task.end_date) + chart_x;
 
-    let mut ticks_layout = Vec::new();
-    let days_per_tick = total_days / (ticks_count - 1);
-    let pixels_per_tick = CHART_WIDTH / (ticks_count - 1);
+    if x_end < x_start + MIN_BAR_SPAN {
+        x_end = x_start + MIN_BAR_SPAN;
+        if x_end > chart_end_x {
+            x_end = chart_end_x;
+            x_start = chart_end_x.saturating_sub(MIN_BAR_SPAN);
+        }
+    }
+    (x_start, x_end)
+}
+
+/// Column the progress fill of a bar stops at
+fn progress_fill_x(task: &Task, x_start: usize, x_end: usize) -> Option<usize> {
+    task.progress.map(|progress| {
+        let interior_width = x_end.saturating_sub(x_start + 1);
+        x_start + 1 + interior_width * progress as usize / 100
+    })
+}
+
+/// Groups the tasks by assignee, in order of their first task, with the tasks without an
+/// assignee last. Bars that only share a border column do not overlap, so back-to-back tasks
+/// stay on one row.
+fn assign_lanes<'a>(gantt_chart: &'a GanttChart, timeline: &Timeline) -> Vec<Lane<'a>> {
+    let mut assignees: Vec<Option<&str>> = Vec::new();
+    for task in &gantt_chart.tasks {
+        if !assignees.contains(&task.assignee.as_deref()) {
+            assignees.push(task.assignee.as_deref());
+        }
+    }
+    if let Some(index) = assignees.iter().position(Option::is_none) {
+        let unassigned = assignees.remove(index);
+        assignees.push(unassigned);
+    }
 
-    for i in 0..ticks_count {
-        ticks_layout.push(TickLayout {
-            x: i * pixels_per_tick + MARGIN_LEFT,
-            date: min_date + TimeDelta::days((days_per_tick * i) as i64),
+    let mut lanes = Vec::new();
+    for assignee in assignees {
+        // Columns taken on each row of the lane. Overlaps do not depend on where the chart
+        // starts, so columns are counted from the first one.
+        let mut taken: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut tasks = Vec::new();
+        for (index, task) in gantt_chart.tasks.iter().enumerate() {
+            if task.assignee.as_deref() != assignee {
+                continue;
+            }
+            let (x_start, x_end) = if task.is_milestone() {
+                let x = timeline.date_to_x(task.start_date);
+                (x, x)
+            } else {
+                bar_columns(task, timeline, 0)
+            };
+            let free_row = taken.iter().position(|row| {
+                row.iter()
+                    .all(|&(start, end)| x_end <= start || end <= x_start)
+            });
+            let row = free_row.unwrap_or_else(|| {
+                taken.push(Vec::new());
+                taken.len() - 1
+            });
+            taken[row].push((x_start, x_end));
+            tasks.push((index, row));
+        }
+        lanes.push(Lane {
+            name: assignee.unwrap_or(UNASSIGNED_LANE),
+            tasks,
+            rows: taken.len(),
         });
     }
+    lanes
+}
+
+/// Width of the label gutter of resource lanes: the left margin followed by the longest lane
+/// label
+fn lane_gutter_width(lanes: &[Lane]) -> usize {
+    let longest_label = lanes
+        .iter()
+        .map(|lane| {
+            if lane.rows > 1 {
+                max(lane.name.width(), LANE_OVERLAP_LABEL.width())
+            } else {
+                lane.name.width()
+            }
+        })
+        .max()
+        .unwrap_or(0);
+
+    GUTTER_MARGIN_LEFT + longest_label
+}
+
+/// Lays out the lanes one below the other from `top_y`, with their bars on shared rows.
+/// Milestones are drawn on the middle row of their lane.
+fn layout_lanes(
+    gantt_chart: &GanttChart,
+    lanes: &[Lane],
+    timeline: &Timeline,
+    chart_x: usize,
+    top_y: usize,
+    config: &GanttLayoutConfig,
+) -> TaskRows {
+    let mut task_layouts = Vec::new();
+    let mut milestone_layouts = Vec::new();
+    let mut lane_layouts = Vec::new();
+    let mut y = top_y;
+    let critical_path = gantt_chart.critical_path_indices();
+
+    for lane in lanes {
+        for &(index, row) in &lane.tasks {
+            let task = &gantt_chart.tasks[index];
+            let row_y = y + row * config.task_height;
+            let section = gantt_chart
+                .sections
+                .iter()
+                .position(|section| section.tasks.contains(&index));
+
+            if task.is_milestone() {
+                let x = timeline.date_to_x(task.start_date) + chart_x;
+                milestone_layouts.push(MilestoneLayout {
+                    x,
+                    y: row_y + 1,
+                    name: String::new(),
+                    name_x: x,
+                    section,
+                    status: task.status,
+                    critical: task.critical,
+                    on_critical_path: critical_path.contains(&index),
+                    actual: None,
+                });
+                continue;
+            }
+
+            let (x_start, x_end) = bar_columns(task, timeline, chart_x);
+            let interior_width = x_end.saturating_sub(x_start + 1);
+            // Names would run into the next bar on the row
+            let name = Some(task_label(task, config))
+                .filter(|name| name.width() <= interior_width)
+                .unwrap_or_default();
+            let duration = Some(duration_text(task, config)).filter(|duration| {
+                config.duration_labels == DurationLabels::NarrowBars
+                    && name.is_empty()
+                    && duration.width() <= interior_width
+            });
+
+            task_layouts.push(TaskLayout {
+                x_start,
+                x_end,
+                y: row_y,
+                name,
+                name_x: None,
+                duration,
+                fill_until_x: progress_fill_x(task, x_start, x_end),
+                section,
+                status: task.status,
+                critical: task.critical,
+                on_critical_path: critical_path.contains(&index),
+                actual: None,
+            });
+        }
+
+        // Labels go on the name rows of the bars
+        lane_layouts.push(LaneLayout {
+            x: GUTTER_MARGIN_LEFT,
+            y: y + 1,
+            name: lane.name.to_string(),
+            overlap_ys: (1..lane.rows)
+                .map(|row| y + row * config.task_height + 1)
+                .collect(),
+        });
+        y += lane.rows * config.task_height;
+    }
+
+    TaskRows {
+        task_layouts,
+        milestone_layouts,
+        dependency_layouts: Vec::new(),
+        section_layouts: Vec::new(),
+        lane_layouts,
+        summary_layouts: Vec::new(),
+        end_y: y,
+    }
+}
+
+/// Routes a connector from the bottom border of `from` down to the left edge of `to`. When `to`
+/// starts too far left for that, the connector leaves `from` through its left edge instead and
+/// goes around it.
+fn layout_dependency(from: &BarBounds, to: &BarBounds) -> DependencyLayout {
+    let to_x = to.x_start.saturating_sub(1);
+    let (exit_start_x, exit_end_x) = from.exit_x;
+    // Leave a column for the horizontal segment before the arrowhead
+    let turn_x = exit_end_x.min(to_x.saturating_sub(1));
+
+    if exit_start_x <= turn_x {
+        DependencyLayout {
+            from_x: turn_x,
+            from_y: from.bottom_y + 1,
+            turn_x,
+            to_x,
+            to_y: to.mid_y,
+        }
+    } else {
+        DependencyLayout {
+            from_x: from.x_start.saturating_sub(1),
+            from_y: from.mid_y,
+            turn_x: to_x.saturating_sub(1).min(from.x_start.saturating_sub(1)),
+            to_x,
+            to_y: to.mid_y,
+        }
+    }
+}
+
+/// Lays out the headers of the sections starting at `task_index` and returns the next free row
+fn layout_section_headers(
+    gantt_chart: &GanttChart,
+    task_index: usize,
+    x: usize,
+    mut y: usize,
+    top_y: usize,
+    section_layouts: &mut Vec<SectionLayout>,
+) -> usize {
+    for section in &gantt_chart.sections {
+        if section.tasks.start != task_index {
+            continue;
+        }
+
+        if y > top_y {
+            y += SECTION_SPACING;
+        }
+        section_layouts.push(SectionLayout {
+            x,
+            y,
+            name: section.name.clone(),
+        });
+        y += SECTION_HEADER_HEIGHT;
+    }
+
+    y
+}
+
+/// Stripes behind every other task row, counted from the top. Boxes are three rows high and
+/// milestones one. Bars in resource lanes share rows, which are striped once.
+fn layout_stripes(
+    task_layouts: &[TaskLayout],
+    milestone_layouts: &[MilestoneLayout],
+    x_start: usize,
+    x_end: usize,
+) -> Vec<StripeLayout> {
+    let task_rows = task_layouts.iter().map(|task| (task.y, task.y + 2));
+    let milestone_rows = milestone_layouts
+        .iter()
+        .map(|milestone| (milestone.y, milestone.y));
+    let mut rows: Vec<(usize, usize)> = task_rows.chain(milestone_rows).collect();
+    rows.sort();
+    rows.dedup();
+
+    rows.into_iter()
+        .step_by(2)
+        .map(|(y_start, y_end)| StripeLayout {
+            x_start,
+            x_end,
+            y_start,
+            y_end,
+        })
+        .collect()
+}
+
+/// Columns between the ticks of each Saturday and Sunday on the axis. Weekend days in a
+/// compressed gap are left out.
+fn layout_weekends(timeline: &Timeline, chart_x: usize) -> Vec<(usize, usize)> {
+    let mut columns = Vec::new();
+    for (start, end) in timeline.stretches() {
+        for day in start.iter_days().take_while(|&day| day < end) {
+            if day.weekday().num_days_from_monday() < 5 {
+                continue;
+            }
+            let x_start = timeline.date_to_x(day) + 1;
+            let x_end = timeline.date_to_x(day.succ_opt().unwrap_or(day));
+            if x_start < x_end {
+                columns.push((x_start + chart_x, x_end - 1 + chart_x));
+            }
+        }
+    }
+    columns
+}
+
+/// Lays out the ticks of each stretch of the axis between compressed gaps. Both ends of a
+/// stretch are tick dates.
+fn layout_ticks(
+    timeline: &Timeline,
+    tick_interval: TickInterval,
+    chart_x: usize,
+) -> Vec<TickLayout> {
+    let mut ticks_layout = Vec::new();
+
+    for (start, end) in timeline.stretches() {
+        let mut date = start;
+        while date <= end {
+            ticks_layout.push(TickLayout {
+                x: timeline.date_to_x(date) + chart_x,
+                date,
+                label: None,
+            });
+            let next_date = tick_interval.next(date);
+            if next_date == date {
+                // Reached the last representable date
+                break;
+            }
+            date = next_date;
+        }
+    }
+
+    label_ticks(&mut ticks_layout, tick_interval);
 
     ticks_layout
 }
 
-fn find_date_range(chart: &GanttChart) -> (NaiveDate, NaiveDate) {
-    let mut min_date = NaiveDate::MAX;
-    let mut max_date = NaiveDate::MIN;
+/// Labels every tick with the longest date format that fits between neighbouring ticks.
+/// When even the short format does not fit, labels that would collide are dropped.
+fn label_ticks(ticks: &mut [TickLayout], tick_interval: TickInterval) {
+    let mut labels = Vec::new();
+
+    for format in [TICK_LABEL_FORMAT, tick_interval.short_label_format()] {
+        labels = place_tick_labels(ticks, format);
+        if labels.iter().all(Option::is_some) {
+            break;
+        }
+    }
+
+    for (tick, label) in ticks.iter_mut().zip(labels) {
+        tick.label = label;
+    }
+}
+
+fn place_tick_labels(ticks: &[TickLayout], format: &str) -> Vec<Option<String>> {
+    let mut labels = Vec::new();
+    // First column a label may start at, leaving a blank column after the previous label
+    let mut next_free_x = 0;
+
+    for tick in ticks {
+        let label = tick.date.format(format).to_string();
+        let label_start_x = tick.x.saturating_sub(label.width() / 2);
+
+        if label_start_x >= next_free_x {
+            next_free_x = label_start_x + label.width() + 1;
+            labels.push(Some(label));
+        } else {
+            labels.push(None);
+        }
+    }
+
+    labels
+}
+
+/// Spacing of the ticks, picked from the length of the chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickInterval {
+    /// A tick every day
+    Day,
+    /// A tick every Monday
+    Week,
+    /// A tick on the first of every month
+    Month,
+}
+
+impl TickInterval {
+    /// The interval for the length of the chart, made finer while its ticks stay
+    /// `min_tick_spacing` columns apart, and coarser while tick lines would touch
+    fn for_scale(
+        min_date: NaiveDate,
+        max_date: NaiveDate,
+        pixels_per_day: Ratio<usize>,
+        min_tick_spacing: usize,
+    ) -> Self {
+        let spacing = |interval: TickInterval| {
+            (pixels_per_day * Ratio::from_integer(interval.approximate_days())).to_integer()
+        };
+
+        let mut interval = Self::for_range(min_date, max_date);
+        while let Some(finer) = interval.finer()
+            && spacing(finer) >= min_tick_spacing
+        {
+            interval = finer;
+        }
+        while let Some(coarser) = interval.coarser()
+            && spacing(interval) < MIN_TICK_LINE_SPACING
+        {
+            interval = coarser;
+        }
+        interval
+    }
+
+    fn approximate_days(self) -> usize {
+        match self {
+            TickInterval::Day => 1,
+            TickInterval::Week => 7,
+            TickInterval::Month => 30,
+        }
+    }
+
+    fn finer(self) -> Option<Self> {
+        match self {
+            TickInterval::Day => None,
+            TickInterval::Week => Some(TickInterval::Day),
+            TickInterval::Month => Some(TickInterval::Week),
+        }
+    }
+
+    fn coarser(self) -> Option<Self> {
+        match self {
+            TickInterval::Day => Some(TickInterval::Week),
+            TickInterval::Week => Some(TickInterval::Month),
+            TickInterval::Month => None,
+        }
+    }
+
+    fn for_range(min_date: NaiveDate, max_date: NaiveDate) -> Self {
+        if (max_date - min_date).num_days() < DAILY_TICKS_MAX_DAYS {
+            TickInterval::Day
+        } else if min_date
+            .checked_add_months(Months::new(WEEKLY_TICKS_MAX_MONTHS))
+            .is_none_or(|limit| max_date < limit)
+        {
+            TickInterval::Week
+        } else {
+            TickInterval::Month
+        }
+    }
+
+    /// The latest tick date at or before `date`
+    fn floor(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TickInterval::Day => date,
+            TickInterval::Week => date
+                .checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))
+                .unwrap_or(date),
+            TickInterval::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// The earliest tick date at or after `date`
+    fn ceil(self, date: NaiveDate) -> NaiveDate {
+        let floor = self.floor(date);
+        if floor == date {
+            date
+        } else {
+            self.next(floor)
+        }
+    }
+
+    fn short_label_format(self) -> &'static str {
+        match self {
+            TickInterval::Day | TickInterval::Week => "%d-%m",
+            TickInterval::Month => "%m-%Y",
+        }
+    }
+
+    fn next(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TickInterval::Day => date
+                .checked_add_days(Days::new(1))
+                .unwrap_or(NaiveDate::MAX),
+            TickInterval::Week => date
+                .checked_add_days(Days::new(7))
+                .unwrap_or(NaiveDate::MAX),
+            TickInterval::Month => date
+                .checked_add_months(Months::new(1))
+                .unwrap_or(NaiveDate::MAX),
+        }
+    }
+}
+
+/// Stretches of more than `min_days` days in which no task starts or ends, as
+/// `(last date before, first date after)` pairs in date order
+fn quiet_periods(gantt_chart: &GanttChart, min_days: usize) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut dates: Vec<NaiveDate> = gantt_chart
+        .tasks
+        .iter()
+        .flat_map(|task| {
+            let actual = task
+                .actual
+                .iter()
+                .flat_map(|actual| [actual.start_date, actual.end_date]);
+            [task.start_date, task.end_date].into_iter().chain(actual)
+        })
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    dates
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|&(start, end)| (end - start).num_days() > min_days as i64)
+        .collect()
+}
+
+/// The quiet periods that are still longer than `min_days` days once shrunk to whole tick
+/// intervals, to be compressed
+fn compressed_gaps(
+    quiet_periods: &[(NaiveDate, NaiveDate)],
+    tick_interval: TickInterval,
+    min_days: usize,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    quiet_periods
+        .iter()
+        .map(|&(start, end)| (tick_interval.ceil(start), tick_interval.floor(end)))
+        .filter(|&(start, end)| (end - start).num_days() > min_days as i64)
+        .collect()
+}
+
+/// Columns left of the timeline, which `WidthMode::Max` leaves out of the chart width. Resource
+/// lanes keep room for the overlap label, which a narrower chart may need.
+fn reserved_width(gantt_chart: &GanttChart, config: &GanttLayoutConfig) -> usize {
+    if config.resource_lanes {
+        let longest_label = gantt_chart
+            .tasks
+            .iter()
+            .map(|task| task.assignee.as_deref().unwrap_or(UNASSIGNED_LANE).width())
+            .chain([LANE_OVERLAP_LABEL.width()])
+            .max()
+            .unwrap_or(0);
+        return GUTTER_MARGIN_LEFT + longest_label + GUTTER_PADDING;
+    }
+    match config.label_placement {
+        LabelPlacement::Inline => config.margins.left,
+        LabelPlacement::Gutter => gutter_width(gantt_chart, config) + GUTTER_PADDING,
+    }
+}
+
+/// Maps dates to columns relative to the first column of the chart. Compressed gaps take
+/// `COMPRESSED_GAP_WIDTH` columns and the remaining columns are spread over the other days.
+/// Below one column per day, several days share a column and dates are rounded down to it.
+struct Timeline {
+    /// `(date, x)` pairs sorted by date, from the axis start to the axis end. Dates between two
+    /// anchors are interpolated.
+    anchors: Vec<(NaiveDate, usize)>,
+    /// Compressed gaps as `(start, end)` tick dates
+    gaps: Vec<(NaiveDate, NaiveDate)>,
+    /// Columns of the chart, the x of the axis end
+    width: usize,
+}
+
+impl Timeline {
+    /// Spreads the days outside the gaps over `chart_width` columns. Gaps are dropped when they
+    /// would leave no room for the other days.
+    fn new(
+        axis_start: NaiveDate,
+        axis_end: NaiveDate,
+        mut gaps: Vec<(NaiveDate, NaiveDate)>,
+        chart_width: usize,
+    ) -> Self {
+        if gaps.len() * COMPRESSED_GAP_WIDTH >= chart_width {
+            gaps.clear();
+        }
+
+        let shown_days = Self::shown_days(axis_start, axis_end, &gaps);
+        // How many pixels (char columns) represent one day outside the gaps
+        let pixels_per_day =
+            Ratio::new(chart_width - gaps.len() * COMPRESSED_GAP_WIDTH, shown_days);
+        Self::with_pixels_per_day(axis_start, axis_end, gaps, pixels_per_day, chart_width)
+    }
+
+    /// Gives each day outside the gaps `columns` columns, making the chart as wide as needed
+    fn with_day_width(
+        axis_start: NaiveDate,
+        axis_end: NaiveDate,
+        gaps: Vec<(NaiveDate, NaiveDate)>,
+        columns: usize,
+    ) -> Self {
+        let shown_days = Self::shown_days(axis_start, axis_end, &gaps);
+        let chart_width = columns
+            .saturating_mul(shown_days)
+            .saturating_add(gaps.len() * COMPRESSED_GAP_WIDTH);
+        let pixels_per_day = Ratio::from_integer(columns);
+        Self::with_pixels_per_day(axis_start, axis_end, gaps, pixels_per_day, chart_width)
+    }
+
+    fn shown_days(
+        axis_start: NaiveDate,
+        axis_end: NaiveDate,
+        gaps: &[(NaiveDate, NaiveDate)],
+    ) -> usize {
+        let gap_days: i64 = gaps
+            .iter()
+            .map(|&(start, end)| (end - start).num_days())
+            .sum();
+        max((axis_end - axis_start).num_days() - gap_days, 1) as usize
+    }
+
+    fn with_pixels_per_day(
+        axis_start: NaiveDate,
+        axis_end: NaiveDate,
+        gaps: Vec<(NaiveDate, NaiveDate)>,
+        pixels_per_day: Ratio<usize>,
+        chart_width: usize,
+    ) -> Self {
+        let mut anchors = vec![(axis_start, 0)];
+        // Shown days before the current date, and the columns of the gaps before it
+        let mut days_before = axis_start;
+        let mut gap_columns = 0;
+        for &(start, end) in &gaps {
+            let x = date_to_x(start, days_before, pixels_per_day) + gap_columns;
+            anchors.push((start, x));
+            gap_columns += COMPRESSED_GAP_WIDTH;
+            anchors.push((end, x + COMPRESSED_GAP_WIDTH));
+            days_before = days_before
+                .checked_add_days(Days::new((end - start).num_days() as u64))
+                .unwrap_or(days_before);
+        }
+        anchors.push((axis_end, chart_width));
+
+        Timeline {
+            anchors,
+            gaps,
+            width: chart_width,
+        }
+    }
+
+    fn date_to_x(&self, date: NaiveDate) -> usize {
+        // The pair of anchors around the date
+        let index = self
+            .anchors
+            .partition_point(|&(anchor_date, _)| anchor_date <= date)
+            .clamp(1, self.anchors.len() - 1);
+        let (start_date, start_x) = self.anchors[index - 1];
+        let (end_date, end_x) = self.anchors[index];
+
+        let days = max((end_date - start_date).num_days() as usize, 1);
+        start_x + date_to_x(date, start_date, Ratio::new(end_x - start_x, days))
+    }
+
+    /// The stretches of the axis outside the gaps, as `(start, end)` dates
+    fn stretches(&self) -> Vec<(NaiveDate, NaiveDate)> {
+        let first = self.anchors[0].0;
+        let last = self.anchors[self.anchors.len() - 1].0;
+
+        let starts = std::iter::once(first).chain(self.gaps.iter().map(|&(_, end)| end));
+        let ends = self.gaps.iter().map(|&(start, _)| start).chain([last]);
+        starts.zip(ends).collect()
+    }
+}
+
+fn date_to_x(date: NaiveDate, min_date: NaiveDate, pixels_per_day: Ratio<usize>) -> usize {
+    let days = (date - min_date).num_days() as usize;
+    let days = Ratio::from_integer(days);
+
+    let pixels = days * pixels_per_day;
+    pixels.to_integer()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gantt::parser::{ActualDates, Task};
+
+    use super::*;
+
+    #[test]
+    fn test_compressed_gaps() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        // Nothing starts or ends between the end of January and June
+        let input = "
+Planning: 05-01-2026, 10d
+Support: 12-01-2026, 19-06-2026
+Launch: 08-06-2026, 10d";
+        let gantt_chart = crate::gantt::parser::parse(input).unwrap();
+
+        let plain = layout(&gantt_chart);
+        assert!(plain.axis_breaks.is_empty());
+
+        let config = GanttLayoutConfig {
+            compress_gaps: Some(14),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        assert_eq!(gantt_layout.width, plain.width);
+
+        // The gap runs between the weekly ticks around it
+        let tick_dates: Vec<NaiveDate> = gantt_layout
+            .tick_layouts
+            .iter()
+            .map(|tick| tick.date)
+            .collect();
+        assert_eq!(
+            tick_dates,
+            [
+                date(1, 5),
+                date(1, 12),
+                date(1, 19),
+                date(6, 8),
+                date(6, 15),
+                date(6, 22)
+            ]
+        );
+        let gap_start_x = gantt_layout.tick_layouts[2].x;
+        assert_eq!(
+            gantt_layout.tick_layouts[3].x,
+            gap_start_x + COMPRESSED_GAP_WIDTH
+        );
+        assert_eq!(gantt_layout.axis_breaks, [gap_start_x + 2]);
+
+        // The days outside the gap share the rest of the chart
+        let planning = &gantt_layout.task_layouts[0];
+        let launch = &gantt_layout.task_layouts[2];
+        assert!(planning.x_end - planning.x_start > 30);
+        assert_eq!(
+            launch.x_end - launch.x_start,
+            planning.x_end - planning.x_start
+        );
+        // A task across the gap stays one bar
+        let support = &gantt_layout.task_layouts[1];
+        assert!(
+            support.x_start < gap_start_x && gap_start_x + COMPRESSED_GAP_WIDTH < support.x_end
+        );
+
+        // Gaps not longer than the limit stay
+        let config = GanttLayoutConfig {
+            compress_gaps: Some(200),
+            ..GanttLayoutConfig::default()
+        };
+        assert_eq!(
+            layout_with_config(&gantt_chart, &config).tick_layouts,
+            plain.tick_layouts
+        );
+    }
+
+    #[test]
+    fn test_width_max_two_years() {
+        let input = "
+Discovery: 01-01-2025, 3d
+Build: continue, 300d
+Beta: continue, 10d
+Launch: 01-07-2026, 01-07-2026
+Support: continue, 180d";
+        let gantt_chart = crate::gantt::parser::parse(input).unwrap();
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Max(100),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
 
-    for task in &chart.tasks {
-        min_date = min(min_date, task.start_date);
-        max_date = max(max_date, task.end_date);
-    }
+        // Several days share each column of the 88 left by the margins
+        assert_eq!(gantt_layout.width, 100);
+        let chart_end_x = 100 - MARGIN_RIGHT;
+        assert_eq!(gantt_layout.tick_interval, TickInterval::Month);
+        assert_eq!(gantt_layout.tick_layouts.last().unwrap().x, chart_end_x);
 
-    (min_date, max_date)
-}
+        let bars: Vec<(usize, usize)> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| (task.x_start, task.x_end))
+            .collect();
+        // Discovery is shorter than a column and is widened. Bars that follow each other
+        // share the rounded column of the date between them.
+        assert_eq!(bars[0], (MARGIN_LEFT, MARGIN_LEFT + MIN_BAR_SPAN));
+        assert_eq!(bars[1].1, bars[2].0);
+        assert!(bars[2].1 - bars[2].0 >= MIN_BAR_SPAN);
+        assert!(bars.iter().all(|&(_, x_end)| x_end <= chart_end_x));
+        assert!(gantt_layout.milestone_layouts[0].x <= bars[3].0);
 
-fn date_to_x(date: NaiveDate, min_date: NaiveDate, pixels_per_day: Ratio<usize>) -> usize {
-    let days = (date - min_date).num_days() as usize;
-    let days = Ratio::from_integer(days);
+        // Charts that fit keep the default width
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Max(200),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        assert_eq!(gantt_layout.width, MARGIN_LEFT + CHART_WIDTH + MARGIN_RIGHT);
+    }
 
-    let pixels = days * pixels_per_day;
-    pixels.to_integer()
-}
+    #[test]
+    fn test_width_fit_days() {
+        // Ticks every Monday from the length of the chart, every day at 20 columns a day
+        let input = "Design: 05-01-2026, 19-01-2026";
+        let gantt_chart = crate::gantt::parser::parse(input).unwrap();
+        assert_eq!(layout(&gantt_chart).tick_interval, TickInterval::Week);
 
-#[cfg(test)]
-mod test {
-    use crate::gantt::parser::Task;
+        for (columns, tick_interval) in [(2, TickInterval::Week), (20, TickInterval::Day)] {
+            let config = GanttLayoutConfig {
+                chart_width: WidthMode::FitDays(columns),
+                ..GanttLayoutConfig::default()
+            };
+            let gantt_layout = layout_with_config(&gantt_chart, &config);
 
-    use super::*;
+            assert_eq!(gantt_layout.tick_interval, tick_interval);
+            let design = &gantt_layout.task_layouts[0];
+            assert_eq!(design.x_end - design.x_start, 14 * columns);
+            assert_eq!(
+                gantt_layout.width,
+                MARGIN_LEFT + 14 * columns + MARGIN_RIGHT
+            );
+        }
+    }
 
     #[test]
     fn test_date_to_x() {
         let start_date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
-        let dates = vec![
+        let dates = [
             NaiveDate::from_ymd_opt(2026, 1, 2).unwrap(),
             NaiveDate::from_ymd_opt(2026, 1, 7).unwrap(),
             NaiveDate::from_ymd_opt(2026, 1, 10).unwrap(),
             NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
             NaiveDate::from_ymd_opt(2026, 1, 16).unwrap(),
         ];
-        let pixels_per_day = Ratio::from_integer(4 as usize);
+        let pixels_per_day = Ratio::from_integer(4_usize);
         let expected_result = [4, 24, 36, 56, 60];
 
         for (index, date) in dates.iter().enumerate() {
-            let x = date_to_x(date.clone(), start_date, pixels_per_day);
+            let x = date_to_x(*date, start_date, pixels_per_day);
             assert_eq!(x, expected_result[index]);
         }
     }
@@ -151,67 +2544,147 @@ mod test {
                     start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
                     end_date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
                     name: "Design".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
                 Task {
                     start_date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
                     end_date: NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
                     name: "Implementation".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
                 Task {
                     start_date: NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
                     end_date: NaiveDate::from_ymd_opt(2026, 1, 20).unwrap(),
                     name: "Testing".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
                 Task {
                     start_date: NaiveDate::from_ymd_opt(2026, 1, 20).unwrap(),
                     end_date: NaiveDate::from_ymd_opt(2026, 2, 3).unwrap(),
                     name: "Bugfix".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
                 Task {
                     start_date: NaiveDate::from_ymd_opt(2026, 2, 3).unwrap(),
                     end_date: NaiveDate::from_ymd_opt(2026, 2, 6).unwrap(),
                     name: "Release".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
             ],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
         };
 
         let gantt_layout = layout(&gantt_chart);
 
+        // 01-01-2026 is a Thursday, so the axis starts on the Monday before it
         assert_eq!(gantt_layout.task_layouts.len(), 5);
-        assert_eq!(gantt_layout.tick_layouts.len(), 10);
+        assert_eq!(gantt_layout.tick_layouts.len(), 7);
 
         assert_eq!(
             gantt_layout.task_layouts,
             vec![
                 TaskLayout {
-                    x_start: 6,
-                    x_end: 19,
+                    x_start: 14,
+                    x_end: 26,
                     y: 2,
-                    name: "Design".to_string()
+                    name: "Design".to_string(),
+                    name_x: None,
+                    duration: None,
+                    fill_until_x: None,
+                    section: None,
+                    status: None,
+                    critical: false,
+                    on_critical_path: false,
+                    actual: None,
                 },
                 TaskLayout {
-                    x_start: 19,
-                    x_end: 52,
+                    x_start: 26,
+                    x_end: 54,
                     y: 5,
-                    name: "Implementation".to_string()
+                    name: "Implementation".to_string(),
+                    name_x: None,
+                    duration: None,
+                    fill_until_x: None,
+                    section: None,
+                    status: None,
+                    critical: false,
+                    on_critical_path: false,
+                    actual: None,
                 },
                 TaskLayout {
-                    x_start: 52,
-                    x_end: 69,
+                    x_start: 54,
+                    x_end: 68,
                     y: 8,
-                    name: "Testing".to_string()
+                    name: "Testing".to_string(),
+                    name_x: None,
+                    duration: None,
+                    fill_until_x: None,
+                    section: None,
+                    status: None,
+                    critical: false,
+                    on_critical_path: false,
+                    actual: None,
                 },
                 TaskLayout {
-                    x_start: 69,
-                    x_end: 116,
+                    x_start: 68,
+                    x_end: 108,
                     y: 11,
-                    name: "Bugfix".to_string()
+                    name: "Bugfix".to_string(),
+                    name_x: None,
+                    duration: None,
+                    fill_until_x: None,
+                    section: None,
+                    status: None,
+                    critical: false,
+                    on_critical_path: true,
+                    actual: None,
                 },
                 TaskLayout {
-                    x_start: 116,
-                    x_end: 126,
+                    x_start: 108,
+                    x_end: 117,
                     y: 14,
-                    name: "Release".to_string()
+                    name: "Release".to_string(),
+                    name_x: None,
+                    duration: None,
+                    fill_until_x: None,
+                    section: None,
+                    status: None,
+                    critical: false,
+                    on_critical_path: false,
+                    actual: None,
                 },
             ]
         );
@@ -221,45 +2694,814 @@ mod test {
             vec![
                 TickLayout {
                     x: 6,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+                    date: NaiveDate::from_ymd_opt(2025, 12, 29).unwrap(),
+                    label: Some("29-12-2025".to_string()),
                 },
                 TickLayout {
-                    x: 19,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()
+                    x: 26,
+                    date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+                    label: Some("05-01-2026".to_string()),
                 },
                 TickLayout {
-                    x: 32,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 9).unwrap()
+                    x: 46,
+                    date: NaiveDate::from_ymd_opt(2026, 1, 12).unwrap(),
+                    label: Some("12-01-2026".to_string()),
                 },
                 TickLayout {
-                    x: 45,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 13).unwrap()
+                    x: 66,
+                    date: NaiveDate::from_ymd_opt(2026, 1, 19).unwrap(),
+                    label: Some("19-01-2026".to_string()),
                 },
                 TickLayout {
-                    x: 58,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 17).unwrap()
+                    x: 86,
+                    date: NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(),
+                    label: Some("26-01-2026".to_string()),
                 },
                 TickLayout {
-                    x: 71,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 21).unwrap()
+                    x: 106,
+                    date: NaiveDate::from_ymd_opt(2026, 2, 2).unwrap(),
+                    label: Some("02-02-2026".to_string()),
                 },
                 TickLayout {
-                    x: 84,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 25).unwrap()
+                    x: 126,
+                    date: NaiveDate::from_ymd_opt(2026, 2, 9).unwrap(),
+                    label: Some("09-02-2026".to_string()),
                 },
-                TickLayout {
-                    x: 97,
-                    date: NaiveDate::from_ymd_opt(2026, 1, 29).unwrap()
+            ]
+        )
+    }
+
+    #[test]
+    fn test_try_layout() {
+        let gantt_chart = parse("section Empty").unwrap();
+        assert_eq!(try_layout(&gantt_chart).unwrap_err(), LayoutError::NoTasks);
+
+        let gantt_chart =
+            parse("Launch: 06-02-2026, 06-02-2026\nRelease: 06-02-2026, 06-02-2026").unwrap();
+        let err = try_layout(&gantt_chart).unwrap_err();
+        let date = NaiveDate::from_ymd_opt(2026, 2, 6).unwrap();
+        assert_eq!(err, LayoutError::ZeroSpan(date));
+        assert_eq!(
+            err.to_string(),
+            "Layout error: every task falls on 06-02-2026"
+        );
+        // The infallible layout spreads the date over a tick interval instead
+        assert!(layout(&gantt_chart).width > 0);
+
+        let gantt_chart = parse("Launch: 06-02-2026, 06-02-2026 | actual 06-02-2026, 1d").unwrap();
+        assert!(try_layout(&gantt_chart).is_ok());
+    }
+
+    #[test]
+    fn test_layout_with_actual() {
+        let gantt_chart = GanttChart {
+            tasks: vec![
+                Task {
+                    start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+                    name: "Design".to_string(),
+                    actual: Some(ActualDates {
+                        start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                        end_date: NaiveDate::from_ymd_opt(2026, 1, 7).unwrap(),
+                    }),
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
-                TickLayout {
-                    x: 110,
-                    date: NaiveDate::from_ymd_opt(2026, 2, 2).unwrap()
+                Task {
+                    start_date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 1, 13).unwrap(),
+                    name: "Implementation".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
                 },
-                TickLayout {
-                    x: 123,
-                    date: NaiveDate::from_ymd_opt(2026, 2, 6).unwrap()
+            ],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+
+        let gantt_layout = layout(&gantt_chart);
+
+        // The task with an actual takes its box rows plus the actual bar row
+        assert_eq!(
+            gantt_layout.height,
+            TASK_HEIGHT * 2 + ACTUAL_BAR_HEIGHT + MARGIN_TOP + MARGIN_BOTTOM
+        );
+        assert_eq!(
+            gantt_layout.task_layouts[0].actual,
+            Some(ActualLayout {
+                x_start: 6,
+                x_end: 66,
+                y: 5,
+            })
+        );
+        assert_eq!(gantt_layout.task_layouts[1].y, 6);
+        assert_eq!(gantt_layout.task_layouts[1].actual, None);
+    }
+
+    fn chart_between(start_date: NaiveDate, end_date: NaiveDate) -> GanttChart {
+        GanttChart {
+            tasks: vec![Task {
+                start_date,
+                end_date,
+                name: "Task".to_string(),
+                actual: None,
+                progress: None,
+                status: None,
+                critical: false,
+                id: None,
+                depends_on: Vec::new(),
+                assignee: None,
+            }],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        }
+    }
+
+    #[test]
+    fn test_month_header() {
+        // Weekly ticks, so the axis starts before January 7 and ends after March 27
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 7).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 27).unwrap(),
+        );
+        let plain = layout(&gantt_chart);
+        assert!(plain.months.is_empty());
+        assert_eq!(plain.month_y, None);
+
+        let config = GanttLayoutConfig {
+            month_header: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let labels: Vec<&str> = gantt_layout
+            .months
+            .iter()
+            .map(|month| month.label.as_str())
+            .collect();
+        assert_eq!(labels, ["January 2026", "February 2026", "March 2026"]);
+
+        // January is cut off by the axis start, February starts at the first of the month
+        let (january, february) = (&gantt_layout.months[0], &gantt_layout.months[1]);
+        assert!(!january.starts_on_chart);
+        assert_eq!(january.x_start, MARGIN_LEFT);
+        assert!(february.starts_on_chart);
+        assert_eq!(january.x_end, february.x_start);
+        let label_end = february.label_x() + february.label.width();
+        assert!(february.label_x() > february.x_start && label_end < february.x_end);
+
+        // The month row goes between the tick lines and the tick labels
+        assert_eq!(gantt_layout.month_y, Some(plain.tick_end_y + 1));
+        assert_eq!(gantt_layout.axis_y, plain.axis_y + 1);
+        assert_eq!(gantt_layout.height, plain.height + 1);
+
+        // Charts within a single month get no header
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 11).unwrap(),
+        );
+        assert!(layout_with_config(&gantt_chart, &config).months.is_empty());
+    }
+
+    #[test]
+    fn test_month_labels_shortened_to_fit() {
+        let date = |month| NaiveDate::from_ymd_opt(2026, month, 1).unwrap();
+        assert_eq!(month_label(date(3), 20), "March 2026");
+        assert_eq!(month_label(date(3), 9), "Mar 2026");
+        assert_eq!(month_label(date(3), 4), "Mar");
+        assert_eq!(month_label(date(3), 2), "Ma");
+        assert_eq!(month_label(date(3), 0), "");
+    }
+
+    #[test]
+    fn test_daily_ticks() {
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 11).unwrap(),
+        );
+
+        let ticks = layout(&gantt_chart).tick_layouts;
+
+        assert_eq!(ticks.len(), 11);
+        for (i, tick) in ticks.iter().enumerate() {
+            assert_eq!(
+                tick.date,
+                NaiveDate::from_ymd_opt(2026, 1, 1 + i as u32).unwrap()
+            );
+            assert_eq!(tick.x, MARGIN_LEFT + i * 12);
+        }
+    }
+
+    #[test]
+    fn test_weekly_ticks() {
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 7).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 27).unwrap(),
+        );
+
+        let ticks = layout(&gantt_chart).tick_layouts;
+
+        assert_eq!(ticks.len(), 13);
+        assert_eq!(ticks[0].date, NaiveDate::from_ymd_opt(2026, 1, 5).unwrap());
+        assert_eq!(
+            ticks[12].date,
+            NaiveDate::from_ymd_opt(2026, 3, 30).unwrap()
+        );
+        for tick in &ticks {
+            assert_eq!(tick.date.weekday(), chrono::Weekday::Mon);
+        }
+        assert_eq!(ticks[0].x, MARGIN_LEFT);
+        assert_eq!(ticks[12].x, MARGIN_LEFT + CHART_WIDTH);
+    }
+
+    #[test]
+    fn test_monthly_ticks() {
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2027, 7, 15).unwrap(),
+        );
+
+        let ticks = layout(&gantt_chart).tick_layouts;
+
+        assert_eq!(ticks.len(), 20);
+        assert_eq!(ticks[0].date, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap());
+        assert_eq!(ticks[19].date, NaiveDate::from_ymd_opt(2027, 8, 1).unwrap());
+        for tick in &ticks {
+            assert_eq!(tick.date.day(), 1);
+        }
+        assert!(ticks.windows(2).all(|pair| pair[0].x < pair[1].x));
+        assert_eq!(ticks[19].x, MARGIN_LEFT + CHART_WIDTH);
+    }
+
+    #[test]
+    fn test_tick_labels_shortened_when_crowded() {
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 11).unwrap(),
+        );
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(60),
+            ..GanttLayoutConfig::default()
+        };
+
+        let ticks = layout_with_config(&gantt_chart, &config).tick_layouts;
+
+        assert_eq!(ticks[0].label, Some("01-01".to_string()));
+        assert_eq!(ticks[10].label, Some("11-01".to_string()));
+        assert!(ticks.iter().all(|tick| tick.label.is_some()));
+    }
+
+    #[test]
+    fn test_tick_labels_dropped_when_short_format_collides() {
+        let gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2027, 7, 15).unwrap(),
+        );
+
+        let ticks = layout(&gantt_chart).tick_layouts;
+
+        assert_eq!(ticks[0].label, Some("01-2026".to_string()));
+        assert_eq!(ticks[1].label, None);
+        assert!(ticks.iter().any(|tick| tick.label.is_some()));
+        assert!(ticks.iter().any(|tick| tick.label.is_none()));
+    }
+
+    fn fixed_today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, 6).unwrap()
+    }
+
+    #[test]
+    fn test_today_marker() {
+        let mut gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 11).unwrap(),
+        );
+        let config = GanttLayoutConfig {
+            clock: fixed_today,
+            ..GanttLayoutConfig::default()
+        };
+
+        // Disabled by default
+        assert_eq!(layout_with_config(&gantt_chart, &config).today_marker, None);
+
+        gantt_chart.today_marker = true;
+        assert_eq!(
+            layout_with_config(&gantt_chart, &config).today_marker,
+            Some(TodayMarkerLayout {
+                x: MARGIN_LEFT + 5 * 12,
+                date: fixed_today(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_today_marker_outside_range() {
+        let mut gantt_chart = chart_between(
+            NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 2, 11).unwrap(),
+        );
+        gantt_chart.today_marker = true;
+        let config = GanttLayoutConfig {
+            clock: fixed_today,
+            ..GanttLayoutConfig::default()
+        };
+
+        assert_eq!(layout_with_config(&gantt_chart, &config).today_marker, None);
+    }
+
+    #[test]
+    fn test_consecutive_milestones_on_one_date() {
+        let milestone_date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let milestone = |name: &str| Task {
+            start_date: milestone_date,
+            end_date: milestone_date,
+            name: name.to_string(),
+            actual: None,
+            progress: None,
+            status: None,
+            critical: false,
+            id: None,
+            depends_on: Vec::new(),
+            assignee: None,
+        };
+        let gantt_chart = GanttChart {
+            tasks: vec![milestone("Review"), milestone("Sign-off")],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+        assert_eq!(gantt_chart.duration_days(), 0);
+
+        let gantt_layout = layout(&gantt_chart);
+
+        // The axis spans one day, starting at the milestones
+        let tick_dates: Vec<NaiveDate> = gantt_layout
+            .tick_layouts
+            .iter()
+            .map(|tick| tick.date)
+            .collect();
+        assert_eq!(
+            tick_dates,
+            [milestone_date, NaiveDate::from_ymd_opt(2026, 1, 6).unwrap()]
+        );
+        // Each milestone gets a row of its own at the start of the axis
+        assert!(gantt_layout.task_layouts.is_empty());
+        let milestones: Vec<(usize, usize)> = gantt_layout
+            .milestone_layouts
+            .iter()
+            .map(|milestone| (milestone.x, milestone.y))
+            .collect();
+        assert_eq!(
+            milestones,
+            [(MARGIN_LEFT, MARGIN_TOP), (MARGIN_LEFT, MARGIN_TOP + 1)]
+        );
+        for milestone in &gantt_layout.milestone_layouts {
+            assert_eq!(milestone.name_x, MARGIN_LEFT + MILESTONE_LABEL_GAP);
+        }
+    }
+
+    #[test]
+    fn test_minimum_bar_width() {
+        let gantt_chart = GanttChart {
+            tasks: vec![
+                Task {
+                    start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+                    name: "Project".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
+                },
+                Task {
+                    start_date: NaiveDate::from_ymd_opt(2026, 3, 10).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 3, 11).unwrap(),
+                    name: "Review".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
+                },
+                Task {
+                    start_date: NaiveDate::from_ymd_opt(2026, 6, 30).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+                    name: "Release".to_string(),
+                    actual: None,
+                    progress: None,
+                    status: None,
+                    critical: false,
+                    id: None,
+                    depends_on: Vec::new(),
+                    assignee: None,
+                },
+            ],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+
+        let gantt_layout = layout(&gantt_chart);
+
+        for task_layout in &gantt_layout.task_layouts {
+            assert!(task_layout.x_end >= task_layout.x_start + MIN_BAR_SPAN);
+        }
+        // The release at the end of the chart is widened to the left
+        let release = &gantt_layout.task_layouts[2];
+        assert_eq!(release.x_end, MARGIN_LEFT + CHART_WIDTH);
+        assert_eq!(release.x_start, MARGIN_LEFT + CHART_WIDTH - MIN_BAR_SPAN);
+    }
+
+    #[test]
+    fn test_empty_chart() {
+        let gantt_chart = GanttChart {
+            tasks: Vec::new(),
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+        let config = GanttLayoutConfig {
+            clock: fixed_today,
+            ..GanttLayoutConfig::default()
+        };
+
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+
+        assert!(gantt_layout.task_layouts.is_empty());
+        assert_eq!(gantt_layout.tick_layouts[0].date, fixed_today());
+        assert_eq!(gantt_layout.height, MARGIN_TOP + MARGIN_BOTTOM);
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = "
+Kickoff: 01-01-2026, 02-01-2026
+section Design
+Wireframes: continue, 5d | actual 03-01-2026, 9d
+section Build
+Implementation: continue, 10d";
+        let gantt_chart = crate::gantt::parser::parse(input).unwrap();
+
+        let gantt_layout = layout(&gantt_chart);
+
+        let task_ys: Vec<usize> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| task.y)
+            .collect();
+        // Kickoff, spacer, header, Wireframes with its actual bar, spacer, header, Implementation
+        assert_eq!(task_ys, [2, 7, 13]);
+        assert_eq!(
+            gantt_layout.section_layouts,
+            [
+                SectionLayout {
+                    x: MARGIN_LEFT,
+                    y: 6,
+                    name: "Design".to_string(),
+                },
+                SectionLayout {
+                    x: MARGIN_LEFT,
+                    y: 12,
+                    name: "Build".to_string(),
                 },
             ]
-        )
+        );
+        assert_eq!(gantt_layout.height, 16 + MARGIN_BOTTOM);
+    }
+
+    #[test]
+    fn test_progress_fill() {
+        let input = "
+Design: 01-01-2026, 11-01-2026 | progress 0%
+Build: 01-01-2026, 11-01-2026 | progress 50%
+Test: 01-01-2026, 11-01-2026 | progress 100%
+Release: 01-01-2026, 11-01-2026";
+        let gantt_layout = layout(&crate::gantt::parser::parse(input).unwrap());
+
+        // Ten days of 12 columns each: the interior is 119 columns wide
+        let interior_start = MARGIN_LEFT + 1;
+        let fills: Vec<Option<usize>> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| task.fill_until_x)
+            .collect();
+        assert_eq!(
+            fills,
+            [
+                Some(interior_start),
+                Some(interior_start + 59),
+                Some(MARGIN_LEFT + CHART_WIDTH),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duration_labels() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 12-01-2026
+Ship: 14-01-2026, 14-01-2026";
+        let gantt_chart = crate::gantt::parser::parse(input).unwrap();
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            duration_labels: DurationLabels::Append,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let names: Vec<&str> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, ["Plan (2d)", "Build (5d)"]);
+        assert_eq!(gantt_layout.milestone_layouts[0].name, "Ship");
+        // The longer label no longer fits the bar of `Plan` and moves right of it
+        let (_, _, max_x, _) = gantt_layout.bounding_box();
+        let plan = &gantt_layout.task_layouts[0];
+        assert!(plan.name.width() > plan.x_end - plan.x_start - 1);
+        assert!(max_x >= plan.x_end + plan.name.width());
+
+        // The gutter makes room for the longest label
+        let config = GanttLayoutConfig {
+            label_placement: LabelPlacement::Gutter,
+            working_days: true,
+            ..config
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        assert_eq!(gantt_layout.task_layouts[1].name, "Build (3wd)");
+        assert_eq!(
+            gantt_layout.task_layouts[0].x_start,
+            GUTTER_MARGIN_LEFT + "Build (3wd)".width() + GUTTER_PADDING
+        );
+
+        // Only bars without their name inside show the duration
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            duration_labels: DurationLabels::NarrowBars,
+            ..GanttLayoutConfig::default()
+        };
+        let input = "
+Planning and research: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 12-01-2026";
+        let gantt_layout =
+            layout_with_config(&crate::gantt::parser::parse(input).unwrap(), &config);
+        let durations: Vec<Option<&str>> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| task.duration.as_deref())
+            .collect();
+        assert_eq!(durations, [Some("2d"), None]);
+    }
+
+    #[test]
+    fn test_dependency_layouts() {
+        let task = |start: u32, end: u32, depends_on: Vec<usize>| Task {
+            start_date: NaiveDate::from_ymd_opt(2026, 1, start).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2026, 1, end).unwrap(),
+            name: "Task".to_string(),
+            actual: None,
+            progress: None,
+            status: None,
+            critical: false,
+            id: None,
+            depends_on,
+            assignee: None,
+        };
+        let gantt_chart = GanttChart {
+            tasks: vec![
+                task(5, 10, Vec::new()),
+                task(10, 20, vec![0]),
+                task(2, 8, vec![1]),
+            ],
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+
+        let gantt_layout = layout(&gantt_chart);
+        let [design, build, test] = &gantt_layout.task_layouts[..] else {
+            panic!("Expected three task layouts");
+        };
+        let [after_design, after_build] = &gantt_layout.dependency_layouts[..] else {
+            panic!("Expected two dependency layouts");
+        };
+
+        // Leaves the predecessor through its bottom border and ends left of the successor
+        assert_eq!(
+            after_design,
+            &DependencyLayout {
+                from_x: build.x_start - 2,
+                from_y: design.y + 3,
+                turn_x: build.x_start - 2,
+                to_x: build.x_start - 1,
+                to_y: build.y + 1,
+            }
+        );
+        assert!(after_design.turn_x > design.x_start);
+
+        // The successor starts before the predecessor, so the connector goes around its left edge
+        assert_eq!(after_build.from_x, build.x_start - 1);
+        assert_eq!(after_build.from_y, build.y + 1);
+        assert_eq!(after_build.to_x, test.x_start - 1);
+        assert!(after_build.turn_x < after_build.to_x);
+        assert_eq!(after_build.to_y, test.y + 1);
+    }
+
+    #[test]
+    fn test_resource_lanes() {
+        let input = "
+Design: 01-01-2026, 10-01-2026 | assignee Ada
+Build: 10-01-2026, 20-01-2026 | assignee Ada
+Review: 05-01-2026, 12-01-2026 | assignee Ada
+Docs: 01-01-2026, 1d | assignee Grace
+Ship: 20-01-2026, 20-01-2026
+Plan: 01-01-2026, 5d";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(40),
+            resource_lanes: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let top = config.margins.top;
+
+        // Review overlaps Design and Build, which are back to back, and gets a row of its own
+        let lanes: Vec<(&str, usize, &[usize])> = gantt_layout
+            .lane_layouts
+            .iter()
+            .map(|lane| (lane.name.as_str(), lane.y, lane.overlap_ys.as_slice()))
+            .collect();
+        assert_eq!(
+            lanes,
+            [
+                ("Ada", top + 1, &[top + 4][..]),
+                ("Grace", top + 7, &[][..]),
+                (UNASSIGNED_LANE, top + 10, &[][..]),
+            ]
+        );
+        let tasks: Vec<(&str, usize)> = gantt_layout
+            .task_layouts
+            .iter()
+            .map(|task| (task.name.as_str(), task.y))
+            .collect();
+        // Docs is too short for its name
+        assert_eq!(
+            tasks,
+            [
+                ("Design", top),
+                ("Build", top),
+                ("Review", top + 3),
+                ("", top + 6),
+                ("Plan", top + 9),
+            ]
+        );
+        assert_eq!(gantt_layout.milestone_layouts[0].y, top + 10);
+        assert_eq!(
+            gantt_layout.tick_layouts[0].x,
+            GUTTER_MARGIN_LEFT + UNASSIGNED_LANE.width() + GUTTER_PADDING
+        );
+    }
+
+    #[test]
+    fn test_summary_rows() {
+        let input = "
+section Build
+Design: 01-01-2026, 10-01-2026
+Code: 10-01-2026, 06-02-2026
+section Ship
+Release: 06-02-2026, 06-02-2026";
+        let gantt_chart = parse(input).unwrap();
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(80),
+            summary_rows: SummaryRows::Project,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let top = config.margins.top;
+
+        // Above the first section header, spanning every bar
+        let [project] = &gantt_layout.summary_layouts[..] else {
+            panic!("expected a project summary");
+        };
+        assert_eq!(project.y, top);
+        assert_eq!(project.label, " Project: 01-01-2026 → 06-02-2026, 36d ");
+        let design = &gantt_layout.task_layouts[0];
+        assert_eq!(project.x_start, design.x_start);
+        assert_eq!(project.x_end, gantt_layout.milestone_layouts[0].x);
+        assert_eq!(gantt_layout.section_layouts[0].y, top + 2);
+        // Not a task: the bars and milestone are the chart's own
+        assert_eq!(gantt_layout.task_layouts.len(), 2);
+
+        let config = GanttLayoutConfig {
+            summary_rows: SummaryRows::Sections,
+            ..config
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let rows: Vec<(usize, &str)> = gantt_layout
+            .summary_layouts
+            .iter()
+            .map(|summary| (summary.y, summary.label.trim()))
+            .collect();
+        // Below each section header. The milestone has no span for the label.
+        assert_eq!(
+            rows,
+            [
+                (top + 1, "Build: 01-01-2026 → 06-02-2026, 36d"),
+                (top + 10, "Ship: 06-02-2026 → 06-02-2026, 0d"),
+            ]
+        );
+        assert_eq!(gantt_layout.task_layouts[0].y, top + 2);
+        assert_eq!(gantt_layout.milestone_layouts[0].y, top + 11);
+    }
+
+    #[test]
+    fn test_title_layout() {
+        let input = "
+title Release plan
+subtitle Q1
+Design: 05-01-2026, 5d";
+        let config = GanttLayoutConfig {
+            label_placement: LabelPlacement::Gutter,
+            chart_width: WidthMode::Fixed(40),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let plain_layout = layout_with_config(&parse("Design: 05-01-2026, 5d").unwrap(), &config);
+
+        // Centered over the chart area, right of the gutter
+        let chart_x = plain_layout.tick_layouts[0].x;
+        let center_x = chart_x + 40 / 2;
+        let [title, subtitle] = &gantt_layout.title_layouts[..] else {
+            panic!("expected a title and a subtitle line");
+        };
+        assert_eq!((title.x, title.y), (center_x - 6, config.margins.top));
+        assert_eq!(
+            (subtitle.x, subtitle.y),
+            (center_x - 1, config.margins.top + 1)
+        );
+        assert!(subtitle.subtitle && !title.subtitle);
+
+        // The chart moves down by the title rows
+        let shift = 2 + TITLE_SPACING;
+        assert_eq!(gantt_layout.tick_start_y, plain_layout.tick_start_y + shift);
+        assert_eq!(
+            gantt_layout.task_layouts[0].y,
+            plain_layout.task_layouts[0].y + shift
+        );
+        assert_eq!(gantt_layout.height, plain_layout.height + shift);
+
+        // Titles wider than the canvas wrap instead of being clipped
+        let long_title = "word ".repeat(gantt_layout.width / 4);
+        let gantt_chart = parse(&format!("title {long_title}\nDesign: 05-01-2026, 5d")).unwrap();
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        assert_eq!(gantt_layout.title_layouts.len(), 2);
+        for line in &gantt_layout.title_layouts {
+            assert!(line.x + line.text.width() <= gantt_layout.width);
+        }
+        assert_eq!(wrap_text("abcdef gh", 4), ["abcd", "ef", "gh"]);
+    }
+
+    #[test]
+    fn test_measure_matches_render() {
+        let input = "
+section Build
+Planning: 05-01-2026, 10d
+Coding: 15-01-2026, 20d
+section Ship
+Launch: 08-02-2026, 3d";
+        let config = GanttLayoutConfig {
+            legend: true,
+            ..GanttLayoutConfig::default()
+        };
+        let (width, height) = measure_with_config(input, &config).unwrap();
+
+        let rendered =
+            crate::gantt::renderer::render(&layout_with_config(&parse(input).unwrap(), &config));
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(height, lines.len());
+        assert_eq!(width, lines.iter().map(|line| line.width()).max().unwrap());
+
+        assert_eq!(measure("Planning: 05-01-2026").unwrap_err().line, 1);
     }
 }
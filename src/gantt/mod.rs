@@ -1,3 +1,199 @@
 pub mod layout;
+pub mod options;
 pub mod parser;
 pub mod renderer;
+
+use crate::gantt::layout::{LayoutError, layout_with_config};
+use crate::gantt::options::GanttOptions;
+use crate::gantt::parser::{ParseError, parse};
+use crate::gantt::renderer::render_with_options;
+
+/// Why `render_str` could not draw a chart
+#[derive(Debug)]
+pub enum GanttError {
+    Parse(ParseError),
+    Layout(LayoutError),
+}
+
+impl std::fmt::Display for GanttError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GanttError::Parse(err) => err.fmt(f),
+            GanttError::Layout(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<ParseError> for GanttError {
+    fn from(err: ParseError) -> Self {
+        GanttError::Parse(err)
+    }
+}
+
+impl From<LayoutError> for GanttError {
+    fn from(err: LayoutError) -> Self {
+        GanttError::Layout(err)
+    }
+}
+
+/// Parses, lays out and renders a gantt chart with the default options
+pub fn render_str(input: &str) -> Result<String, GanttError> {
+    render_str_with_options(input, &GanttOptions::default())
+}
+
+/// Parses, lays out and renders a gantt chart. Charts without tasks have no timeline to draw
+/// and fail with `GanttError::Layout`. Tasks that all fall on one date are drawn on an axis of
+/// one tick interval, as `layout_with_config` does.
+pub fn render_str_with_options(input: &str, options: &GanttOptions) -> Result<String, GanttError> {
+    let gantt_chart = parse(input)?;
+    if gantt_chart.tasks.is_empty() {
+        return Err(LayoutError::NoTasks.into());
+    }
+    let gantt_layout = layout_with_config(&gantt_chart, &options.layout);
+    Ok(render_with_options(&gantt_layout, &options.render))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_str() {
+        // The same chart written with end dates, `continue` and durations, as in the parser tests
+        let inputs = [
+            "
+Design              :   01-01-2026,     05-01-2026 \t
+Implementation      :   05-01-2026,     15-01-2026 \t
+Testing             :   15-01-2026,     20-01-2026 \t
+Bugfix              :   20-01-2026,     03-02-2026 \t
+Release             :   03-02-2026,     06-02-2026 \t",
+            "
+Design: 01-01-2026, 05-01-2026
+Implementation: continue, 15-01-2026
+Testing: continue, 20-01-2026
+Bugfix: continue, 03-02-2026
+Release: continue, 06-02-2026",
+            "
+Design: 01-01-2026, 4d
+Implementation: 05-01-2026, 10d
+Testing: 15-01-2026, 5d
+Bugfix: 20-01-2026, 14d
+Release: 03-02-2026, 3d",
+        ];
+        let expected = [
+            "",
+            "      |     |      |      |     |      |      |",
+            "      | ┌───┐      |      |     |      |      |",
+            "      | |   |Design|      |     |      |      |",
+            "      | └───┘      |      |     |      |      |",
+            "      |     ┌─────────┐   |     |      |      |",
+            "      |     |         |Implementation  |      |",
+            "      |     └─────────┘   |     |      |      |",
+            "      |     |      |  ┌───┐     |      |      |",
+            "      |     |      |  |   |Testing     |      |",
+            "      |     |      |  └───┘     |      |      |",
+            "      |     |      |      ┌─────────────┐     |",
+            "      |     |      |      |    Bugfix   |     |",
+            "      |     |      |      └─────────────┘     |",
+            "      |     |      |      |     |      |┌──┐  |",
+            "      |     |      |      |     |      ||  |Release",
+            "      |     |      |      |     |      |└──┘  |",
+            "      |     |      |      |     |      |      |",
+            "    29-12 05-01  12-01  19-01 26-01  02-02  09-02",
+            "",
+        ];
+        let options = GanttOptions::new().chart_width(40);
+        for input in inputs {
+            let output = render_str_with_options(input, &options).unwrap();
+            let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+            assert_eq!(lines, expected, "input:\n{}", input);
+        }
+
+        // Default options match the separate parse, layout and render steps
+        let gantt_chart = parse(inputs[0]).unwrap();
+        assert_eq!(
+            render_str(inputs[0]).unwrap(),
+            renderer::render(&layout::layout(&gantt_chart))
+        );
+    }
+
+    #[test]
+    fn test_render_str_sections() {
+        let input = "
+Kickoff: 01-01-2026, 02-01-2026
+section Design
+Wireframes: continue, 5d
+Review: continue, 2d
+section Empty
+section Build
+Implementation: continue, 10d
+section review: 20-01-2026, 21-01-2026";
+        let output = render_str_with_options(input, &GanttOptions::new().chart_width(40)).unwrap();
+
+        let expected = [
+            "",
+            "      |         |         |         |         |",
+            "      |   ┌─┐   |         |         |         |",
+            "      |   | |Kickoff      |         |         |",
+            "      |   └─┘   |         |         |         |",
+            "      |         |         |         |         |",
+            "      ▸ Design  |         |         |         |",
+            "      |    ┌──────┐       |         |         |",
+            "      |    |      |Wireframes       |         |",
+            "      |    └──────┘       |         |         |",
+            "      |         | ┌──┐    |         |         |",
+            "      |         | |  |Review        |         |",
+            "      |         | └──┘    |         |         |",
+            "      |         |         |         |         |",
+            "      ▸ Empty   |         |         |         |",
+            "      |         |         |         |         |",
+            "      ▸ Build   |         |         |         |",
+            "      |         |    ┌──────────────┐         |",
+            "      |         |    |Implementation|         |",
+            "      |         |    └──────────────┘         |",
+            "      |         |         |         |┌─┐      |",
+            "      |         |     section review|| |      |",
+            "      |         |         |         |└─┘      |",
+            "      |         |         |         |         |",
+            "    29-12     05-01     12-01     19-01     26-01",
+            "",
+        ];
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_render_str_milestones_on_one_date() {
+        let input = "
+Review: 05-01-2026, 05-01-2026
+Sign-off: 05-01-2026, 05-01-2026";
+        let output = render_str_with_options(input, &GanttOptions::new().chart_width(20)).unwrap();
+
+        // The axis spans the one tick interval from the milestone date
+        let expected = [
+            "",
+            "      |                   |",
+            "      ◆ Review            |",
+            "      ◆ Sign-off          |",
+            "      |                   |",
+            " 05-01-2026          06-01-2026",
+            "",
+        ];
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+        assert_eq!(
+            render_str(input).unwrap(),
+            renderer::render(&layout::layout(&parse(input).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_render_str_errors() {
+        let err = render_str("Design: 01/01/2026, 05-01-2026").unwrap_err();
+        assert!(matches!(err, GanttError::Parse(ParseError { line: 1, .. })));
+
+        let err = render_str("title Empty").unwrap_err();
+        assert!(matches!(err, GanttError::Layout(LayoutError::NoTasks)));
+        assert_eq!(err.to_string(), "Layout error: the chart has no tasks");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::gantt::layout::{
+    DurationLabels, GanttLayoutConfig, LabelPlacement, Orientation, RowGuides, SummaryRows,
+    WidthMode,
+};
+use crate::gantt::renderer::{GanttTheme, NameFit, RenderOptions};
+
+/// Layout and render options of a gantt chart, set with chained calls like
+/// `GanttOptions::new().chart_width(60).legend(true)`
+#[derive(Debug, Clone, Default)]
+pub struct GanttOptions {
+    pub layout: GanttLayoutConfig,
+    pub render: RenderOptions,
+}
+
+impl GanttOptions {
+    pub fn new() -> Self {
+        GanttOptions::default()
+    }
+
+    /// Width of the timeline area, in char columns or as a `WidthMode`
+    pub fn chart_width(mut self, chart_width: impl Into<WidthMode>) -> Self {
+        self.layout.chart_width = chart_width.into();
+        self
+    }
+
+    /// Direction time flows in
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.layout.orientation = orientation;
+        self
+    }
+
+    /// Height of the timeline area in rows, for vertical charts
+    pub fn chart_height(mut self, chart_height: usize) -> Self {
+        self.layout.chart_height = chart_height;
+        self
+    }
+
+    pub fn label_placement(mut self, label_placement: LabelPlacement) -> Self {
+        self.layout.label_placement = label_placement;
+        self
+    }
+
+    pub fn today_marker(mut self, today_marker: bool) -> Self {
+        self.layout.today_marker = today_marker;
+        self
+    }
+
+    pub fn section_rules(mut self, section_rules: bool) -> Self {
+        self.layout.section_rules = section_rules;
+        self
+    }
+
+    pub fn legend(mut self, legend: bool) -> Self {
+        self.layout.legend = legend;
+        self
+    }
+
+    pub fn month_header(mut self, month_header: bool) -> Self {
+        self.layout.month_header = month_header;
+        self
+    }
+
+    pub fn weekday_letters(mut self, weekday_letters: bool) -> Self {
+        self.layout.weekday_letters = weekday_letters;
+        self
+    }
+
+    pub fn weekend_shading(mut self, weekend_shading: bool) -> Self {
+        self.layout.weekend_shading = weekend_shading;
+        self
+    }
+
+    pub fn row_guides(mut self, row_guides: RowGuides) -> Self {
+        self.layout.row_guides = row_guides;
+        self
+    }
+
+    /// Shade every other task row
+    pub fn row_stripes(mut self, row_stripes: bool) -> Self {
+        self.layout.row_stripes = row_stripes;
+        self
+    }
+
+    /// Collapse stretches of more than this many days in which no task starts or ends
+    pub fn compress_gaps(mut self, compress_gaps: Option<usize>) -> Self {
+        self.layout.compress_gaps = compress_gaps;
+        self
+    }
+
+    pub fn duration_labels(mut self, duration_labels: DurationLabels) -> Self {
+        self.layout.duration_labels = duration_labels;
+        self
+    }
+
+    /// Give each assignee a row of their own holding all of their tasks
+    pub fn resource_lanes(mut self, resource_lanes: bool) -> Self {
+        self.layout.resource_lanes = resource_lanes;
+        self
+    }
+
+    /// Add a bar summarizing the whole chart, or each section
+    pub fn summary_rows(mut self, summary_rows: SummaryRows) -> Self {
+        self.layout.summary_rows = summary_rows;
+        self
+    }
+
+    /// Count durations in working days
+    pub fn working_days(mut self, working_days: bool) -> Self {
+        self.layout.working_days = working_days;
+        self
+    }
+
+    /// Color the bars with ANSI escape codes
+    pub fn color(mut self, color: bool) -> Self {
+        self.render.output.color = color;
+        self
+    }
+
+    /// Highlight the bars on the critical path
+    pub fn critical_path(mut self, critical_path: bool) -> Self {
+        self.render.critical_path = critical_path;
+        self
+    }
+
+    pub fn theme(mut self, theme: GanttTheme) -> Self {
+        self.render.theme = theme;
+        self
+    }
+
+    /// Strip the spaces padding each line to the chart width
+    pub fn trim_trailing_whitespace(mut self, trim_trailing_whitespace: bool) -> Self {
+        self.render.output.trim_trailing_whitespace = trim_trailing_whitespace;
+        self
+    }
+
+    /// End the output with a newline
+    pub fn final_newline(mut self, final_newline: bool) -> Self {
+        self.render.output.final_newline = final_newline;
+        self
+    }
+
+    /// Shorten task names that do not fit in their bar
+    pub fn name_fit(mut self, name_fit: NameFit) -> Self {
+        self.render.name_fit = name_fit;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let options = GanttOptions::new();
+        let config = GanttLayoutConfig::default();
+        assert_eq!(options.layout.chart_width, config.chart_width);
+        assert_eq!(options.layout.label_placement, config.label_placement);
+        assert!(!options.layout.legend);
+        assert_eq!(options.render.theme, GanttTheme::boxes());
+        assert!(!options.render.critical_path);
+    }
+
+    #[test]
+    fn test_chained_setters() {
+        let options = GanttOptions::new()
+            .chart_width(60)
+            .legend(true)
+            .compress_gaps(Some(14))
+            .theme(GanttTheme::ascii());
+        assert_eq!(options.layout.chart_width, WidthMode::Fixed(60));
+        assert!(options.layout.legend);
+        assert_eq!(options.layout.compress_gaps, Some(14));
+        assert_eq!(options.render.theme, GanttTheme::ascii());
+    }
+}
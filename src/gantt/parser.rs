@@ -1,15 +1,285 @@
-use chrono::{NaiveDate, TimeDelta};
+use std::cmp::{max, min};
+use std::ops::Range;
 
-#[derive(Debug)]
+use chrono::{Datelike, NaiveDate, TimeDelta, Weekday};
+
+#[derive(Debug, PartialEq)]
 pub struct Task {
+    /// Planned (baseline) start date
     pub start_date: NaiveDate,
+    /// Planned (baseline) end date
     pub end_date: NaiveDate,
     pub name: String,
+    pub actual: Option<ActualDates>,
+    /// Completion in percent, 0 to 100
+    pub progress: Option<u8>,
+    pub status: Option<TaskStatus>,
+    /// Set by the `crit` overlay
+    pub critical: bool,
+    /// Set by the `id <id>` overlay, referenced by `after <id>` start dates
+    pub id: Option<String>,
+    /// Indices of the tasks this task starts after, from an `after <id>...` start date
+    pub depends_on: Vec<usize>,
+    /// Set by the `assignee <name>` overlay
+    pub assignee: Option<String>,
 }
 
-#[derive(Debug)]
+/// Set by the `done` and `active` overlays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskStatus {
+    Done,
+    Active,
+}
+
+/// Actual dates of a task, tracked against its planned dates.
+#[derive(Debug, PartialEq)]
+pub struct ActualDates {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+/// Named group of consecutive tasks, started by a `section <name>` line
+#[derive(Debug, PartialEq)]
+pub struct Section {
+    pub name: String,
+    /// Indices into `GanttChart::tasks`. Empty for a section without tasks.
+    pub tasks: Range<usize>,
+}
+
+#[derive(Debug, PartialEq)]
 pub struct GanttChart {
     pub tasks: Vec<Task>,
+    /// Sections in chart order. Tasks before the first section belong to no section.
+    pub sections: Vec<Section>,
+    /// Set by the `today marker: on` directive
+    pub today_marker: bool,
+    /// Set by the `title <text>` directive
+    pub title: Option<String>,
+    /// Set by the `subtitle <text>` directive
+    pub subtitle: Option<String>,
+}
+
+/// Days from Monday to Friday from `start_date` until the day before `end_date`
+pub fn working_days_between(start_date: NaiveDate, end_date: NaiveDate) -> i64 {
+    start_date
+        .iter_days()
+        .take_while(|date| *date < end_date)
+        .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        .count() as i64
+}
+
+impl Task {
+    /// Planned duration in days
+    pub fn duration_days(&self) -> i64 {
+        (self.end_date - self.start_date).num_days()
+    }
+
+    /// Planned duration in working days, Monday to Friday
+    pub fn working_days(&self) -> i64 {
+        working_days_between(self.start_date, self.end_date)
+    }
+
+    /// A milestone marks a single date: its planned start and end date are equal
+    pub fn is_milestone(&self) -> bool {
+        self.start_date == self.end_date
+    }
+}
+
+impl GanttChart {
+    /// Parses lines appended to the input of `existing` without parsing it again. The result is
+    /// the same as parsing the whole input at once. Line numbers in errors count from the start
+    /// of `additional_input`.
+    pub fn parse_append(
+        additional_input: &str,
+        existing: GanttChart,
+    ) -> Result<GanttChart, ParseError> {
+        parse_lines(additional_input, existing, &GanttParseConfig::default())
+    }
+
+    /// Earliest and latest date of the chart, including actual dates.
+    /// A chart without tasks returns `(NaiveDate::MAX, NaiveDate::MIN)`.
+    pub fn date_range(&self) -> (NaiveDate, NaiveDate) {
+        let mut min_date = NaiveDate::MAX;
+        let mut max_date = NaiveDate::MIN;
+
+        for task in &self.tasks {
+            min_date = min(min_date, task.start_date);
+            max_date = max(max_date, task.end_date);
+
+            if let Some(actual) = &task.actual {
+                min_date = min(min_date, actual.start_date);
+                max_date = max(max_date, actual.end_date);
+            }
+        }
+
+        (min_date, max_date)
+    }
+
+    /// Number of days between the earliest and latest date of the chart. 0 without tasks.
+    pub fn duration_days(&self) -> i64 {
+        if self.tasks.is_empty() {
+            return 0;
+        }
+
+        let (min_date, max_date) = self.date_range();
+        (max_date - min_date).num_days()
+    }
+
+    /// Idle periods between the first start and the last end, as `(start, end)` dates where no
+    /// task is planned to run. Milestones take no time and do not end a gap.
+    pub fn gaps(&self) -> Vec<(NaiveDate, NaiveDate)> {
+        let mut intervals: Vec<(NaiveDate, NaiveDate)> = self
+            .tasks
+            .iter()
+            .filter(|task| !task.is_milestone())
+            .map(|task| (task.start_date, task.end_date))
+            .collect();
+        intervals.sort();
+
+        let mut gaps = Vec::new();
+        let mut busy_until: Option<NaiveDate> = None;
+        for (start, end) in intervals {
+            if let Some(busy_until) = busy_until
+                && start > busy_until
+            {
+                gaps.push((busy_until, start));
+            }
+            busy_until = Some(busy_until.map_or(end, |busy_until| max(busy_until, end)));
+        }
+        gaps
+    }
+
+    /// Tasks planned to run on any day from `start` to `end` inclusive, in chart order
+    pub fn tasks_in_range(&self, start: NaiveDate, end: NaiveDate) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|task| task.start_date <= end && task.end_date >= start)
+            .collect()
+    }
+
+    /// The chain of dependent tasks with the longest total duration, in chain order. Tasks
+    /// without dependencies form chains of their own. Empty for a chart without tasks.
+    pub fn critical_path(&self) -> Vec<&Task> {
+        self.critical_path_indices()
+            .into_iter()
+            .map(|index| &self.tasks[index])
+            .collect()
+    }
+
+    /// Indices of the tasks on the critical path, in chain order
+    pub(crate) fn critical_path_indices(&self) -> Vec<usize> {
+        // Forward pass: the longest chain ending with each task. Dependencies always come
+        // earlier in the chart, so every predecessor is done before its successors.
+        let mut finish: Vec<i64> = Vec::with_capacity(self.tasks.len());
+        let mut predecessor: Vec<Option<usize>> = Vec::with_capacity(self.tasks.len());
+        for task in &self.tasks {
+            let longest = task
+                .depends_on
+                .iter()
+                .copied()
+                .filter(|&index| index < finish.len())
+                .fold(None, |longest: Option<usize>, index| match longest {
+                    Some(longest) if finish[longest] >= finish[index] => Some(longest),
+                    _ => Some(index),
+                });
+            let start = longest.map_or(0, |index| finish[index]);
+            finish.push(start.saturating_add(task.duration_days()));
+            predecessor.push(longest);
+        }
+
+        // Walk the longest chain back from the task that finishes last. Milestones right
+        // after that task finish at the same time and extend the chain.
+        let mut last = None;
+        for (index, &task_finish) in finish.iter().enumerate() {
+            if last.is_none_or(|last: usize| {
+                task_finish > finish[last]
+                    || (task_finish == finish[last] && predecessor[index] == Some(last))
+            }) {
+                last = Some(index);
+            }
+        }
+
+        let mut path = Vec::new();
+        while let Some(index) = last {
+            path.push(index);
+            last = predecessor[index];
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Writes the chart back as input that parses to an equal chart. Dates are written out, except
+/// for the start of tasks with dependencies, which is written as `after <id>...`.
+impl std::fmt::Display for GanttChart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "{}{}", TITLE_KEYWORD, title)?;
+        }
+        if let Some(subtitle) = &self.subtitle {
+            writeln!(f, "{}{}", SUBTITLE_KEYWORD, subtitle)?;
+        }
+        if self.today_marker {
+            writeln!(f, "{} on", TODAY_MARKER_DIRECTIVE)?;
+        }
+
+        let mut sections = self.sections.iter().peekable();
+        for (index, task) in self.tasks.iter().enumerate() {
+            while let Some(section) = sections.next_if(|section| section.tasks.start == index) {
+                writeln!(f, "{}{}", SECTION_KEYWORD, section.name)?;
+            }
+
+            let start = if task.depends_on.is_empty() {
+                task.start_date.format(DATE_FORMAT).to_string()
+            } else {
+                let ids: Vec<&str> = task
+                    .depends_on
+                    .iter()
+                    .filter_map(|&index| self.tasks.get(index)?.id.as_deref())
+                    .collect();
+                format!("after {}", ids.join(" "))
+            };
+            write!(
+                f,
+                "{}: {}, {}",
+                task.name,
+                start,
+                task.end_date.format(DATE_FORMAT)
+            )?;
+
+            if let Some(actual) = &task.actual {
+                write!(
+                    f,
+                    " | actual {}, {}",
+                    actual.start_date.format(DATE_FORMAT),
+                    actual.end_date.format(DATE_FORMAT)
+                )?;
+            }
+            if let Some(progress) = task.progress {
+                write!(f, " | progress {}%", progress)?;
+            }
+            match task.status {
+                Some(TaskStatus::Done) => write!(f, " | done")?,
+                Some(TaskStatus::Active) => write!(f, " | active")?,
+                None => {}
+            }
+            if task.critical {
+                write!(f, " | crit")?;
+            }
+            if let Some(id) = &task.id {
+                write!(f, " | id {}", id)?;
+            }
+            if let Some(assignee) = &task.assignee {
+                write!(f, " | assignee {}", assignee)?;
+            }
+            writeln!(f)?;
+        }
+        // Sections after the last task have no tasks
+        for section in sections {
+            writeln!(f, "{}{}", SECTION_KEYWORD, section.name)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -27,8 +297,66 @@ impl std::fmt::Display for ParseError {
 // TODO: Make date format configurable
 const DATE_FORMAT: &str = "%d-%m-%Y";
 
+const TODAY_MARKER_DIRECTIVE: &str = "today marker:";
+const SECTION_KEYWORD: &str = "section ";
+const TITLE_KEYWORD: &str = "title ";
+const SUBTITLE_KEYWORD: &str = "subtitle ";
+
+#[derive(Debug, Clone, Default)]
+pub struct GanttParseConfig {
+    /// Start of a `continue` task with no task before it. Such a task is an error without it.
+    pub default_start: Option<NaiveDate>,
+}
+
+/// Parses gantt charts with options set once, for reuse across inputs
+#[derive(Debug, Clone, Default)]
+pub struct GanttParser {
+    pub config: GanttParseConfig,
+}
+
+impl GanttParser {
+    pub fn new() -> Self {
+        GanttParser::default()
+    }
+
+    pub fn with_config(config: GanttParseConfig) -> Self {
+        GanttParser { config }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<GanttChart, ParseError> {
+        parse_with_config(input, &self.config)
+    }
+}
+
 pub fn parse(input: &str) -> Result<GanttChart, ParseError> {
-    let mut tasks: Vec<Task> = Vec::new();
+    parse_with_config(input, &GanttParseConfig::default())
+}
+
+pub fn parse_with_config(input: &str, config: &GanttParseConfig) -> Result<GanttChart, ParseError> {
+    let empty_chart = GanttChart {
+        tasks: Vec::new(),
+        sections: Vec::new(),
+        today_marker: false,
+        title: None,
+        subtitle: None,
+    };
+    parse_lines(input, empty_chart, config)
+}
+
+/// Parses `input` as a continuation of `existing`: tasks are added after its tasks, to its
+/// last section, and can start after its tasks with `continue` or `after <id>`
+fn parse_lines(
+    input: &str,
+    existing: GanttChart,
+    config: &GanttParseConfig,
+) -> Result<GanttChart, ParseError> {
+    let GanttChart {
+        mut tasks,
+        mut sections,
+        mut today_marker,
+        mut title,
+        mut subtitle,
+    } = existing;
 
     for (index, line) in input.lines().enumerate() {
         let line = line.trim();
@@ -38,6 +366,64 @@ pub fn parse(input: &str) -> Result<GanttChart, ParseError> {
 
         let line_number = index + 1;
 
+        if let Some(value) = line.strip_prefix(TODAY_MARKER_DIRECTIVE) {
+            today_marker = match value.trim() {
+                "on" => true,
+                "off" => false,
+                value => {
+                    return Err(ParseError {
+                        line: line_number,
+                        message: format!(
+                            "Invalid today marker value '{}'. Expected 'on' or 'off'",
+                            value
+                        ),
+                    });
+                }
+            };
+            continue;
+        }
+
+        // Like section names, titles cannot contain a colon, so tasks named "title ..." still
+        // parse
+        if let Some(text) = line.strip_prefix(TITLE_KEYWORD)
+            && !text.contains(':')
+        {
+            if title.is_some() {
+                return Err(ParseError {
+                    line: line_number,
+                    message: "Duplicate title. A chart has at most one title".to_string(),
+                });
+            }
+            title = Some(text.trim().to_string());
+            continue;
+        }
+        if let Some(text) = line.strip_prefix(SUBTITLE_KEYWORD)
+            && !text.contains(':')
+        {
+            if subtitle.is_some() {
+                return Err(ParseError {
+                    line: line_number,
+                    message: "Duplicate subtitle. A chart has at most one subtitle".to_string(),
+                });
+            }
+            subtitle = Some(text.trim().to_string());
+            continue;
+        }
+
+        // Section names cannot contain a colon, so tasks named "section ..." still parse
+        if let Some(name) = line.strip_prefix(SECTION_KEYWORD)
+            && !name.contains(':')
+        {
+            if let Some(section) = sections.last_mut() {
+                section.tasks.end = tasks.len();
+            }
+            sections.push(Section {
+                name: name.trim().to_string(),
+                tasks: tasks.len()..tasks.len(),
+            });
+            continue;
+        }
+
         let (task_name, date_str) = line.split_once(":").ok_or(ParseError {
             line: line_number,
             message: "Missing colon. Expects format: 'Task: start_date, end_date'".to_string(),
@@ -51,62 +437,218 @@ pub fn parse(input: &str) -> Result<GanttChart, ParseError> {
             });
         }
 
-        let (start_date_str, end_date_str) = date_str.split_once(",").ok_or(ParseError {
-            line: line_number,
-            message: "Missing delimiter. Expects format: 'Task: start_date, end_date'".to_string(),
-        })?;
+        let mut overlays = date_str.split('|');
+        let planned_str = overlays.next().unwrap_or_default();
 
-        let start_date_str = start_date_str.trim();
-        let end_date_str = end_date_str.trim();
+        let (start_date, end_date, depends_on) =
+            parse_date_range(line_number, planned_str, &tasks, config)?;
 
-        let start_date = match NaiveDate::parse_from_str(start_date_str, DATE_FORMAT) {
-            Ok(date) => date,
-            Err(_) => {
-                if start_date_str != "continue" {
-                    return Err(ParseError {
-                        line: line_number,
-                        message: format!(
-                            "Invalid start date '{}'. Expected format: 'DD-MM-YYYY' or 'continue'",
-                            start_date_str
-                        ),
-                    });
-                }
+        let mut actual = None;
+        let mut progress = None;
+        let mut status = None;
+        let mut critical = false;
+        let mut id = None;
+        let mut assignee = None;
+        for overlay in overlays {
+            let overlay = overlay.trim();
+            if let Some(actual_str) = overlay.strip_prefix("actual") {
+                let (start_date, end_date, _) =
+                    parse_date_range(line_number, actual_str, &tasks, config)?;
+                actual = Some(ActualDates {
+                    start_date,
+                    end_date,
+                });
+            } else if let Some(progress_str) = overlay.strip_prefix("progress") {
+                progress = Some(parse_progress(line_number, progress_str)?);
+            } else if overlay == "done" {
+                status = Some(TaskStatus::Done);
+            } else if overlay == "active" {
+                status = Some(TaskStatus::Active);
+            } else if overlay == "crit" {
+                critical = true;
+            } else if let Some(id_str) = overlay.strip_prefix("id ") {
+                id = Some(parse_task_id(line_number, id_str, &tasks)?);
+            } else if let Some(assignee_str) = overlay.strip_prefix("assignee ") {
+                assignee = Some(assignee_str.trim().to_string());
+            } else {
+                return Err(ParseError {
+                    line: line_number,
+                    message: "Invalid overlay. Expects format: '| actual start_date, end_date', \
+                              '| progress <percent>%', '| id <id>', '| assignee <name>', '| done', \
+                              '| active' or '| crit'"
+                        .to_string(),
+                });
+            }
+        }
 
-                if tasks.is_empty() {
-                    return Err(ParseError {
+        tasks.push(Task {
+            start_date,
+            end_date,
+            name: task_name.to_string(),
+            actual,
+            progress,
+            status,
+            critical,
+            id,
+            depends_on,
+            assignee,
+        });
+    }
+
+    if let Some(section) = sections.last_mut() {
+        section.tasks.end = tasks.len();
+    }
+
+    Ok(GanttChart {
+        tasks,
+        sections,
+        today_marker,
+        title,
+        subtitle,
+    })
+}
+
+/// Parses `start_date, end_date`. The start can also be `continue`, the end of the previous task
+/// or else `GanttParseConfig::default_start`, or `after <id>...`, the latest end of the given
+/// earlier tasks, whose indices are returned as the dependencies. The end can also be a
+/// duration, `until <date>`, or `until end of <name>`, the end of the closest earlier task with
+/// that name.
+fn parse_date_range(
+    line_number: usize,
+    date_str: &str,
+    tasks: &[Task],
+    config: &GanttParseConfig,
+) -> Result<(NaiveDate, NaiveDate, Vec<usize>), ParseError> {
+    let (start_date_str, end_date_str) = date_str.split_once(",").ok_or(ParseError {
+        line: line_number,
+        message: "Missing delimiter. Expects format: 'Task: start_date, end_date'".to_string(),
+    })?;
+
+    let start_date_str = start_date_str.trim();
+    let end_date_str = end_date_str.trim();
+
+    let mut depends_on = Vec::new();
+    let start_date = match NaiveDate::parse_from_str(start_date_str, DATE_FORMAT) {
+        Ok(date) => date,
+        Err(_) => {
+            if let Some(ids) = start_date_str.strip_prefix("after ") {
+                for id in ids.split_whitespace() {
+                    let index = tasks
+                        .iter()
+                        .position(|task| task.id.as_deref() == Some(id))
+                        .ok_or(ParseError {
+                            line: line_number,
+                            message: format!(
+                                "Unknown task id '{}'. Tasks can only start after tasks above them",
+                                id
+                            ),
+                        })?;
+                    if !depends_on.contains(&index) {
+                        depends_on.push(index);
+                    }
+                }
+                depends_on
+                    .iter()
+                    .map(|&index| tasks[index].end_date)
+                    .max()
+                    .ok_or(ParseError {
+                        line: line_number,
+                        message: "Missing task id. Expected 'after <id>'".to_string(),
+                    })?
+            } else if start_date_str == "continue" {
+                tasks
+                    .last()
+                    .map(|task| task.end_date)
+                    .or(config.default_start)
+                    .ok_or(ParseError {
                         line: line_number,
                         message: "No previous task exists".to_string(),
-                    });
-                }
-
-                let prev_task = tasks.last().unwrap();
-                prev_task.end_date
+                    })?
+            } else {
+                return Err(ParseError {
+                    line: line_number,
+                    message: format!(
+                        "Invalid start date '{}'. Expected format: 'DD-MM-YYYY', 'continue' \
+                         or 'after <id>'",
+                        start_date_str
+                    ),
+                });
             }
-        };
+        }
+    };
 
-        let end_date = match NaiveDate::parse_from_str(end_date_str, DATE_FORMAT) {
-            Ok(date) => date,
-            Err(_) => {
-                let duration = parse_duration(line_number, end_date_str)?;
-                start_date + duration
-            }
-        };
+    let end_date = match NaiveDate::parse_from_str(end_date_str, DATE_FORMAT) {
+        Ok(date) => date,
+        Err(_) if end_date_str.starts_with("until ") => {
+            parse_until(line_number, &end_date_str["until ".len()..], tasks)?
+        }
+        Err(_) => {
+            let duration = parse_duration(line_number, end_date_str)?;
+            start_date.checked_add_signed(duration).ok_or(ParseError {
+                line: line_number,
+                message: "End date is out of range".to_string(),
+            })?
+        }
+    };
 
-        if end_date < start_date {
-            return Err(ParseError {
+    if end_date < start_date {
+        return Err(ParseError {
+            line: line_number,
+            message: "End date cannot be earlier than start date".to_string(),
+        });
+    }
+
+    Ok((start_date, end_date, depends_on))
+}
+
+fn parse_until(
+    line_number: usize,
+    until_str: &str,
+    tasks: &[Task],
+) -> Result<NaiveDate, ParseError> {
+    let until_str = until_str.trim();
+    if let Some(name) = until_str.strip_prefix("end of ") {
+        let name = name.trim();
+        return tasks
+            .iter()
+            .rev()
+            .find(|task| task.name == name)
+            .map(|task| task.end_date)
+            .ok_or(ParseError {
                 line: line_number,
-                message: "End date cannot be earlier than start date".to_string(),
+                message: format!(
+                    "Unknown task '{}'. Tasks can only end with tasks above them",
+                    name
+                ),
             });
-        }
+    }
 
-        tasks.push(Task {
-            start_date,
-            end_date,
-            name: task_name.to_string(),
+    NaiveDate::parse_from_str(until_str, DATE_FORMAT).map_err(|_| ParseError {
+        line: line_number,
+        message: format!(
+            "Invalid end date 'until {}'. Expected format: 'until DD-MM-YYYY' or \
+             'until end of <task>'",
+            until_str
+        ),
+    })
+}
+
+fn parse_task_id(line_number: usize, id_str: &str, tasks: &[Task]) -> Result<String, ParseError> {
+    let id = id_str.trim();
+    if id.is_empty() || id.contains(char::is_whitespace) {
+        return Err(ParseError {
+            line: line_number,
+            message: format!("Invalid task id '{}'. Expected a single word", id),
+        });
+    }
+    if tasks.iter().any(|task| task.id.as_deref() == Some(id)) {
+        return Err(ParseError {
+            line: line_number,
+            message: format!("Duplicate task id '{}'", id),
         });
     }
 
-    Ok(GanttChart { tasks })
+    Ok(id.to_string())
 }
 
 fn parse_duration(line_number: usize, duration_str: &str) -> Result<TimeDelta, ParseError> {
@@ -114,7 +656,8 @@ fn parse_duration(line_number: usize, duration_str: &str) -> Result<TimeDelta, P
         return Err(ParseError {
             line: line_number,
             message: format!(
-                "Invalid end date '{}'. Expected format: 'DD-MM-YYYY' or '<number>d'",
+                "Invalid end date '{}'. Expected format: 'DD-MM-YYYY', '<number>d' or \
+                 'until <date>'",
                 duration_str
             ),
         });
@@ -133,7 +676,25 @@ fn parse_duration(line_number: usize, duration_str: &str) -> Result<TimeDelta, P
         });
     }
 
-    Ok(TimeDelta::days(days))
+    TimeDelta::try_days(days).ok_or(ParseError {
+        line: line_number,
+        message: "Duration is out of range".to_string(),
+    })
+}
+
+fn parse_progress(line_number: usize, progress_str: &str) -> Result<u8, ParseError> {
+    progress_str
+        .trim()
+        .strip_suffix('%')
+        .and_then(|percent| percent.trim().parse::<u8>().ok())
+        .filter(|percent| *percent <= 100)
+        .ok_or(ParseError {
+            line: line_number,
+            message: format!(
+                "Invalid progress '{}'. Expected a percentage from 0% to 100%",
+                progress_str.trim()
+            ),
+        })
 }
 
 #[cfg(test)]
@@ -299,6 +860,20 @@ Release: 03-02-2026, 3d";
         );
     }
 
+    #[test]
+    fn test_gantt_parser_reuse() {
+        let default_start = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let parser = GanttParser::with_config(GanttParseConfig {
+            default_start: Some(default_start),
+        });
+
+        for input in ["Design: continue, 3d", "Review: continue, 10-03-2026"] {
+            let gantt_chart = parser.parse(input).unwrap();
+            assert_eq!(gantt_chart.tasks[0].start_date, default_start);
+        }
+        assert!(GanttParser::new().parse("Design: continue, 3d").is_err());
+    }
+
     #[test]
     fn test_start_date_not_specifed() {
         let input = "
@@ -316,6 +891,19 @@ Release: continue, 06-02-2026";
                 .message
                 .contains("No previous task exists")
         );
+
+        let config = GanttParseConfig {
+            default_start: NaiveDate::from_ymd_opt(2026, 1, 1),
+        };
+        let gantt_chart = parse_with_config(input, &config).unwrap();
+        assert_eq!(
+            gantt_chart.tasks[0].start_date,
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        );
+        assert_eq!(
+            gantt_chart.tasks[1].start_date,
+            gantt_chart.tasks[0].end_date
+        );
     }
 
     #[test]
@@ -336,4 +924,484 @@ Release: 03-02-2026, 06-02-2026";
                 .contains("Invalid start date")
         );
     }
+
+    #[test]
+    fn test_actual_overlay() {
+        let input = "
+Design: 01-01-2026, 5d | actual 01-01-2026, 7d
+Implementation: continue, 15-01-2026 | actual 08-01-2026, 20-01-2026
+Testing: continue, 5d";
+
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(gantt_chart.tasks.len(), 3);
+        assert_eq!(
+            gantt_chart.tasks[0].actual,
+            Some(ActualDates {
+                start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                end_date: NaiveDate::from_ymd_opt(2026, 1, 8).unwrap(),
+            })
+        );
+        assert_eq!(
+            gantt_chart.tasks[1].start_date,
+            NaiveDate::from_ymd_opt(2026, 1, 6).unwrap()
+        );
+        assert_eq!(
+            gantt_chart.tasks[1].actual,
+            Some(ActualDates {
+                start_date: NaiveDate::from_ymd_opt(2026, 1, 8).unwrap(),
+                end_date: NaiveDate::from_ymd_opt(2026, 1, 20).unwrap(),
+            })
+        );
+        assert_eq!(gantt_chart.tasks[2].actual, None);
+    }
+
+    #[test]
+    fn test_invalid_actual_overlay() {
+        let input = "
+Design: 01-01-2026, 5d | planned 01-01-2026, 7d";
+
+        let gantt_chart = parse(input);
+        assert!(gantt_chart.is_err());
+        let err = gantt_chart.unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("Invalid overlay"));
+
+        let input = "
+Design: 01-01-2026, 5d | actual 01-01-2026";
+
+        let gantt_chart = parse(input);
+        assert!(gantt_chart.is_err());
+        assert!(
+            gantt_chart
+                .unwrap_err()
+                .message
+                .contains("Missing delimiter")
+        );
+    }
+
+    #[test]
+    fn test_today_marker_directive() {
+        let input = "
+today marker: on
+Design: 01-01-2026, 05-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        assert!(gantt_chart.today_marker);
+        assert_eq!(gantt_chart.tasks.len(), 1);
+
+        let gantt_chart = parse("Design: 01-01-2026, 05-01-2026").unwrap();
+        assert!(!gantt_chart.today_marker);
+
+        let err = parse("today marker: maybe").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("Invalid today marker value"));
+    }
+
+    #[test]
+    fn test_durations() {
+        let input = "
+Design              :   01-01-2026,     05-01-2026 \t
+Implementation      :   05-01-2026,     15-01-2026 \t
+Testing             :   15-01-2026,     20-01-2026 \t
+Bugfix              :   20-01-2026,     03-02-2026 \t
+Release             :   03-02-2026,     06-02-2026 \t";
+
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(
+            gantt_chart.date_range(),
+            (
+                NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 2, 6).unwrap()
+            )
+        );
+        assert_eq!(gantt_chart.duration_days(), 36);
+
+        let task_durations: Vec<i64> = gantt_chart
+            .tasks
+            .iter()
+            .map(|task| task.duration_days())
+            .collect();
+        assert_eq!(task_durations, vec![4, 10, 5, 14, 3]);
+        let working_days: Vec<i64> = gantt_chart
+            .tasks
+            .iter()
+            .map(|task| task.working_days())
+            .collect();
+        assert_eq!(working_days, vec![2, 8, 3, 10, 3]);
+
+        let gantt_chart = GanttChart {
+            tasks: Vec::new(),
+            today_marker: false,
+            sections: Vec::new(),
+            title: None,
+            subtitle: None,
+        };
+        assert_eq!(gantt_chart.duration_days(), 0);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        let gantt_chart =
+            parse("Design: 01-01-2026, 05-01-2026\nBuild: 08-01-2026, 12-01-2026").unwrap();
+        assert_eq!(gantt_chart.gaps(), [(date(1, 5), date(1, 8))]);
+
+        // Overlapping and adjacent tasks leave no gap, nor does a milestone inside one
+        let input = "
+Design: 01-01-2026, 10-01-2026
+Review: 03-01-2026, 05-01-2026
+Build: 10-01-2026, 15-01-2026
+Demo: 18-01-2026, 18-01-2026
+Ship: 20-01-2026, 22-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(gantt_chart.gaps(), [(date(1, 15), date(1, 20))]);
+    }
+
+    #[test]
+    fn test_continue_after_milestone() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Review: continue, 05-01-2026
+Sign-off: continue, 05-01-2026
+Build: continue, 3d";
+
+        let gantt_chart = parse(input).unwrap();
+        let milestone_date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        assert!(!gantt_chart.tasks[0].is_milestone());
+        assert!(gantt_chart.tasks[1].is_milestone());
+        assert!(gantt_chart.tasks[2].is_milestone());
+        assert_eq!(gantt_chart.tasks[2].start_date, milestone_date);
+
+        let build = &gantt_chart.tasks[3];
+        assert!(!build.is_milestone());
+        assert_eq!(build.start_date, milestone_date);
+        assert_eq!(build.end_date, NaiveDate::from_ymd_opt(2026, 1, 8).unwrap());
+    }
+
+    #[test]
+    fn test_duration_out_of_range() {
+        let err = parse("Design: 01-01-2026, 9999999999999d").unwrap_err();
+        assert_eq!(err.message, "Duration is out of range");
+
+        let err = parse("Design: 01-01-2026, 999999999d").unwrap_err();
+        assert_eq!(err.message, "End date is out of range");
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = "
+Kickoff: 01-01-2026, 02-01-2026
+section Design
+Wireframes: continue, 5d
+Review: continue, 2d
+section Empty
+section Build
+Implementation: continue, 10d
+section review: 20-01-2026, 21-01-2026";
+
+        let gantt_chart = parse(input).unwrap();
+
+        assert_eq!(gantt_chart.tasks.len(), 5);
+        assert_eq!(gantt_chart.tasks[4].name, "section review");
+        assert_eq!(
+            gantt_chart.sections,
+            [
+                Section {
+                    name: "Design".to_string(),
+                    tasks: 1..3,
+                },
+                Section {
+                    name: "Empty".to_string(),
+                    tasks: 3..3,
+                },
+                Section {
+                    name: "Build".to_string(),
+                    tasks: 3..5,
+                },
+            ]
+        );
+        // `continue` follows the previous task across sections
+        assert_eq!(
+            gantt_chart.tasks[3].start_date,
+            gantt_chart.tasks[2].end_date
+        );
+    }
+
+    #[test]
+    fn test_progress_overlay() {
+        let input = "
+Design: 01-01-2026, 5d | progress 40%
+Build: continue, 10d | actual 07-01-2026, 12d | progress 100%
+Test: continue, 3d";
+
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(gantt_chart.tasks[0].progress, Some(40));
+        assert_eq!(gantt_chart.tasks[1].progress, Some(100));
+        assert!(gantt_chart.tasks[1].actual.is_some());
+        assert_eq!(gantt_chart.tasks[2].progress, None);
+
+        for input in [
+            "Design: 01-01-2026, 5d | progress 101%",
+            "Design: 01-01-2026, 5d | progress 40",
+            "Design: 01-01-2026, 5d | progress -5%",
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.message.starts_with("Invalid progress"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_status_overlays() {
+        let input = "
+Design: 01-01-2026, 5d | done
+Build: continue, 10d | active | crit
+Test: continue, 3d | crit
+Release: continue, 1d";
+
+        let gantt_chart = parse(input).unwrap();
+        let statuses: Vec<(Option<TaskStatus>, bool)> = gantt_chart
+            .tasks
+            .iter()
+            .map(|task| (task.status, task.critical))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                (Some(TaskStatus::Done), false),
+                (Some(TaskStatus::Active), true),
+                (None, true),
+                (None, false),
+            ]
+        );
+
+        let err = parse("Design: 01-01-2026, 5d | finished").unwrap_err();
+        assert!(err.message.starts_with("Invalid overlay"));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let input = "
+Design: 01-01-2026, 5d | id design
+Docs: 02-01-2026, 2d
+Build: after design, 10d | id build
+Test: after build, 3d";
+
+        let gantt_chart = parse(input).unwrap();
+        let tasks = &gantt_chart.tasks;
+        assert_eq!(tasks[0].id.as_deref(), Some("design"));
+        assert_eq!(tasks[2].depends_on, [0]);
+        assert_eq!(tasks[2].start_date, tasks[0].end_date);
+        assert_eq!(tasks[3].depends_on, [2]);
+        assert!(tasks[1].depends_on.is_empty());
+
+        for (input, expected) in [
+            ("Build: after design, 10d", "Unknown task id 'design'"),
+            (
+                "Design: 01-01-2026, 5d | id design\nBuild: continue, 1d | id design",
+                "Duplicate task id 'design'",
+            ),
+            ("Design: 01-01-2026, 5d | id two words", "Invalid task id"),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.message.starts_with(expected), "{}", err.message);
+        }
+    }
+
+    #[test]
+    fn test_critical_path() {
+        // Build splits into a long backend branch and a short frontend branch that join again
+        let input = "
+Design: 01-01-2026, 5d | id design
+Backend: after design, 10d | id backend
+Frontend: after design, 3d | id frontend
+Docs: 01-01-2026, 12d
+Integrate: after frontend backend, 2d | id integrate
+Release: after integrate, 1d | id release";
+
+        let gantt_chart = parse(input).unwrap();
+        let names: Vec<&str> = gantt_chart
+            .critical_path()
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, ["Design", "Backend", "Integrate", "Release"]);
+
+        // A milestone after the last task closes the path
+        let gantt_chart = parse(&format!("{input}\nShip: after release, 19-01-2026")).unwrap();
+        let names: Vec<&str> = gantt_chart
+            .critical_path()
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, ["Design", "Backend", "Integrate", "Release", "Ship"]);
+
+        // Without dependencies the longest single task is the critical path
+        let gantt_chart = parse("A: 01-01-2026, 3d\nB: 01-01-2026, 7d").unwrap();
+        assert_eq!(gantt_chart.critical_path()[0].name, "B");
+        assert!(parse("").unwrap().critical_path().is_empty());
+    }
+
+    #[test]
+    fn test_assignee_overlay() {
+        let input = "
+Design: 01-01-2026, 5d | assignee Ada Lovelace | done
+Build: continue, 10d";
+
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(
+            gantt_chart.tasks[0].assignee.as_deref(),
+            Some("Ada Lovelace")
+        );
+        assert_eq!(gantt_chart.tasks[1].assignee, None);
+
+        let err = parse("Design: 01-01-2026, 5d | assignee").unwrap_err();
+        assert!(
+            err.message.starts_with("Invalid overlay"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_title_directives() {
+        let input = "
+title Release plan
+subtitle Q1 2026
+Design: 01-01-2026, 5d
+title page: continue, 2d";
+
+        let gantt_chart = parse(input).unwrap();
+        assert_eq!(gantt_chart.title.as_deref(), Some("Release plan"));
+        assert_eq!(gantt_chart.subtitle.as_deref(), Some("Q1 2026"));
+        assert_eq!(gantt_chart.tasks[1].name, "title page");
+
+        let gantt_chart = parse("Design: 01-01-2026, 5d").unwrap();
+        assert_eq!((gantt_chart.title, gantt_chart.subtitle), (None, None));
+
+        let err = parse(
+            "title Plan
+Design: 01-01-2026, 5d
+title Other",
+        )
+        .unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("Duplicate title"));
+        let err = parse(
+            "subtitle Q1
+subtitle Q2
+Design: 01-01-2026, 5d",
+        )
+        .unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("Duplicate subtitle"));
+    }
+
+    #[test]
+    fn test_until_end_dates() {
+        let input = "
+Design: 01-01-2026, 5d
+Implementation: 05-01-2026, until 15-01-2026
+Docs: 08-01-2026, until end of Implementation";
+
+        let gantt_chart = parse(input).unwrap();
+        let end_date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        assert_eq!(gantt_chart.tasks[1].end_date, end_date);
+        assert_eq!(gantt_chart.tasks[2].end_date, end_date);
+
+        for (input, expected) in [
+            (
+                "Docs: 08-01-2026, until end of Implementation\nImplementation: 05-01-2026, 10d",
+                "Unknown task 'Implementation'",
+            ),
+            (
+                "Design: 01-01-2026, until tomorrow",
+                "Invalid end date 'until tomorrow'",
+            ),
+            (
+                "Design: 01-01-2026, 5d\nDocs: 08-01-2026, until end of Design",
+                "End date cannot be earlier than start date",
+            ),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.message.starts_with(expected), "{}", err.message);
+        }
+    }
+
+    #[test]
+    fn test_parse_append() {
+        let head = "
+section Design
+Wireframes: 01-01-2026, 5d | id wireframes
+Review: continue, 2d";
+        let tail = "
+Implementation: continue, 10d
+Docs: after wireframes, until end of Implementation";
+
+        let existing = parse(head).unwrap();
+        let appended = GanttChart::parse_append(tail, existing).unwrap();
+        assert_eq!(appended, parse(&format!("{head}\n{tail}")).unwrap());
+        assert_eq!(appended.tasks.len(), 4);
+        assert_eq!(appended.sections[0].tasks, 0..4);
+
+        let err = GanttChart::parse_append("Docs: continue", parse(head).unwrap()).unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let input = "
+title Release
+subtitle Q1
+today marker: on
+Kickoff: 01-01-2026, 01-01-2026 | id kickoff
+section Design
+Wireframes: after kickoff, 5d | id wireframes | progress 40% | assignee Ana
+Review: continue, 2d | actual 07-01-2026, 10-01-2026 | done | crit
+section Empty
+section Build
+Implementation: after wireframes kickoff, 10d | active
+section Later";
+
+        let gantt_chart = parse(input).unwrap();
+        let text = gantt_chart.to_string();
+        assert_eq!(
+            text,
+            "\
+title Release
+subtitle Q1
+today marker: on
+Kickoff: 01-01-2026, 01-01-2026 | id kickoff
+section Design
+Wireframes: after kickoff, 06-01-2026 | progress 40% | id wireframes | assignee Ana
+Review: 06-01-2026, 08-01-2026 | actual 07-01-2026, 10-01-2026 | done | crit
+section Empty
+section Build
+Implementation: after wireframes kickoff, 16-01-2026 | active
+section Later
+"
+        );
+        assert_eq!(parse(&text).unwrap(), gantt_chart);
+    }
+
+    #[test]
+    fn test_tasks_in_range() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Implementation: 05-01-2026, 15-01-2026
+Launch: 20-01-2026, 20-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2026, 1, day).unwrap();
+
+        let names = |start, end| -> Vec<&str> {
+            gantt_chart
+                .tasks_in_range(start, end)
+                .iter()
+                .map(|task| task.name.as_str())
+                .collect()
+        };
+        // Both ends are inclusive
+        assert_eq!(names(date(5), date(5)), ["Design", "Implementation"]);
+        assert_eq!(names(date(16), date(20)), ["Launch"]);
+        assert!(names(date(16), date(19)).is_empty());
+    }
 }
@@ -1,115 +1,1997 @@
+use chrono::Datelike;
 use unicode_width::UnicodeWidthStr;
 
-use crate::gantt::layout::{GanttLayout, MARGIN_BOTTOM, MARGIN_TOP, TaskLayout, TickLayout};
+use crate::canvas::{Canvas, ClippedLabel, OutputOptions};
+use crate::color::{Color, Style};
+use crate::gantt::layout::{
+    ActualLayout, ColumnLayout, DependencyLayout, GanttLayout, LANE_OVERLAP_LABEL,
+    LEGEND_ENTRY_GAP, LegendEntryLayout, LegendKind, MilestoneLayout, MonthLayout, SectionLayout,
+    SummaryLayout, TODAY_LABEL, TaskLayout, TickLayout, TitleLayout, TodayMarkerLayout,
+    VerticalLayout, layout_with_config, outside_name_placement,
+};
+use crate::gantt::options::GanttOptions;
+use crate::gantt::parser::{GanttChart, TaskStatus};
+use crate::layout::truncate_name;
 
-pub struct Canvas {
-    pub grid: Vec<Vec<char>>,
-    pub width: usize,
-    pub height: usize,
+/// Marks the middle of a compressed gap in the timeline
+const AXIS_BREAK_CHAR: char = '≈';
+/// Fills the blank cells of weekend columns
+const WEEKEND_CHAR: char = '·';
+const WEEKDAY_INITIALS: [char; 7] = ['M', 'T', 'W', 'T', 'F', 'S', 'S'];
+
+/// Colors cycled through by the sections of a chart
+pub const SECTION_PALETTE: [Color; 3] = [Color::Blue, Color::Magenta, Color::Yellow];
+
+const DONE_STYLE: Style = Style {
+    color: Some(Color::Green),
+    bold: false,
+    dim: true,
+};
+const ACTIVE_STYLE: Style = Style {
+    color: Some(Color::Cyan),
+    bold: false,
+    dim: false,
+};
+const SHADING_STYLE: Style = Style {
+    color: None,
+    bold: false,
+    dim: true,
+};
+const TITLE_STYLE: Style = Style {
+    color: None,
+    bold: true,
+    dim: false,
+};
+const SUBTITLE_STYLE: Style = Style {
+    color: None,
+    bold: false,
+    dim: true,
+};
+const SUMMARY_STYLE: Style = Style {
+    color: None,
+    bold: true,
+    dim: false,
+};
+const CRITICAL_STYLE: Style = Style {
+    color: Some(Color::Red),
+    bold: false,
+    dim: false,
+};
+
+/// What becomes of a task name too wide for the interior of its bar
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NameFit {
+    /// Drawn right of the bar, or left of it when more of the name fits there
+    #[default]
+    SpillRight,
+    /// Cut with `…` to the interior. Spills when the interior is narrower than two columns.
+    TruncateInside,
+    /// The uppercase initials of its words inside, e.g. `DM` for `Database migration`. Spills
+    /// when the initials do not fit either.
+    Initials,
 }
 
-impl Canvas {
-    pub fn new(width: usize, height: usize) -> Self {
-        let grid = vec![vec![' '; width]; height];
-        Canvas {
-            grid,
-            width: width,
-            height: height,
-        }
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Color, padding and final newline of the output. Task bars are colored by section and
+    /// status.
+    pub output: OutputOptions,
+    /// Draw the bars on the critical path with the theme's critical bar, and in red with `color`
+    pub critical_path: bool,
+    pub theme: GanttTheme,
+    /// Shortening of task names that do not fit in their bar, with `LabelPlacement::Inline`
+    pub name_fit: NameFit,
+}
+
+/// Characters of a task bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarChars {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub left: char,
+    pub right: char,
+    /// Fills the interior of the bar. A filled bar has no room for the name, which is drawn
+    /// right of it instead.
+    pub fill: Option<char>,
+    /// Progress fill of the interior, for bars without `fill`
+    pub progress: char,
+}
+
+/// Characters the chart is drawn with
+#[derive(Debug, Clone, PartialEq)]
+pub struct GanttTheme {
+    pub bar: BarChars,
+    /// Bars on the critical path with `RenderOptions::critical_path`
+    pub critical_bar: BarChars,
+    /// Draws the thin bars of summary rows
+    pub summary: char,
+    /// Shades the row stripes
+    pub stripe: char,
+}
+
+impl Default for GanttTheme {
+    fn default() -> Self {
+        GanttTheme::boxes()
     }
+}
 
-    pub fn set_char(&mut self, x: usize, y: usize, ch: char) {
-        if y < self.height && x < self.width {
-            self.grid[y][x] = ch;
-        } else {
-            panic!("Index out of range.")
+impl GanttTheme {
+    /// Box-drawing outlines with the name inside, and double lines for the critical path
+    pub fn boxes() -> Self {
+        GanttTheme {
+            bar: BarChars {
+                top_left: '┌',
+                top_right: '┐',
+                bottom_left: '└',
+                bottom_right: '┘',
+                horizontal: '─',
+                left: '|',
+                right: '|',
+                fill: None,
+                progress: '█',
+            },
+            critical_bar: BarChars {
+                top_left: '╔',
+                top_right: '╗',
+                bottom_left: '╚',
+                bottom_right: '╝',
+                horizontal: '═',
+                left: '║',
+                right: '║',
+                fill: None,
+                progress: '█',
+            },
+            summary: '━',
+            stripe: '░',
         }
     }
 
-    pub fn get_char(&self, x: usize, y: usize) -> char {
-        if y < self.height && x < self.width {
-            self.grid[y][x]
-        } else {
-            panic!("Index out of range.")
+    /// Solid bars with the name right of them, and shaded bars for the critical path
+    pub fn blocks() -> Self {
+        let solid = |ch: char| BarChars {
+            top_left: ch,
+            top_right: ch,
+            bottom_left: ch,
+            bottom_right: ch,
+            horizontal: ch,
+            left: ch,
+            right: ch,
+            fill: Some(ch),
+            progress: ch,
+        };
+        GanttTheme {
+            bar: solid('█'),
+            critical_bar: solid('▓'),
+            summary: '━',
+            stripe: '░',
         }
     }
 
-    pub fn to_string(&self) -> String {
-        self.grid
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Plain ASCII bars like `[ name ]` between `+====+` borders
+    pub fn ascii() -> Self {
+        let bar = BarChars {
+            top_left: '+',
+            top_right: '+',
+            bottom_left: '+',
+            bottom_right: '+',
+            horizontal: '=',
+            left: '[',
+            right: ']',
+            fill: None,
+            progress: '#',
+        };
+        GanttTheme {
+            bar,
+            critical_bar: BarChars {
+                top_left: '#',
+                top_right: '#',
+                bottom_left: '#',
+                bottom_right: '#',
+                horizontal: '#',
+                ..bar
+            },
+            summary: '-',
+            stripe: '.',
+        }
     }
 }
 
 pub fn render(gantt_layout: &GanttLayout) -> String {
+    render_with_options(gantt_layout, &RenderOptions::default())
+}
+
+/// Lays out and renders a chart with the layout and render parts of `options`
+pub fn render_chart(gantt_chart: &GanttChart, options: &GanttOptions) -> String {
+    render_with_options(
+        &layout_with_config(gantt_chart, &options.layout),
+        &options.render,
+    )
+}
+
+/// A label that does not fit on the canvas, from `try_render_with_options`
+#[derive(Debug, PartialEq)]
+pub struct RenderError {
+    /// The label as laid out, before it is clipped
+    pub label: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Render error: {}", self.message)
+    }
+}
+
+pub fn try_render(gantt_layout: &GanttLayout) -> Result<String, RenderError> {
+    try_render_with_options(gantt_layout, &RenderOptions::default())
+}
+
+/// Renders the chart like `render_with_options`, which clips text at the edges of the canvas,
+/// but fails on the first label that would be clipped or shortened with `…`
+pub fn try_render_with_options(
+    gantt_layout: &GanttLayout,
+    options: &RenderOptions,
+) -> Result<String, RenderError> {
+    let canvas = render_canvas(gantt_layout, options);
+    match canvas.clipped_labels.first() {
+        Some(ClippedLabel { label, message }) => Err(RenderError {
+            label: label.clone(),
+            message: message.clone(),
+        }),
+        None => Ok(canvas.to_string_with_options(&options.output)),
+    }
+}
+
+pub fn render_with_options(gantt_layout: &GanttLayout, options: &RenderOptions) -> String {
+    render_canvas(gantt_layout, options).to_string_with_options(&options.output)
+}
+
+fn render_canvas(gantt_layout: &GanttLayout, options: &RenderOptions) -> Canvas {
+    if let Some(vertical) = &gantt_layout.vertical {
+        return render_vertical(gantt_layout, vertical, options);
+    }
+
     let mut canvas = Canvas::new(gantt_layout.width, gantt_layout.height);
 
+    // Drawn first so that everything else covers the shading
+    for &(x_start, x_end) in &gantt_layout.weekend_columns {
+        for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
+            for x in x_start..=x_end {
+                canvas.set_char(x, y, WEEKEND_CHAR);
+            }
+        }
+    }
+    // The tick lines are drawn over the stripes and stay unbroken
+    for stripe in &gantt_layout.stripes {
+        for y in stripe.y_start..=stripe.y_end {
+            for x in stripe.x_start..=stripe.x_end {
+                canvas.set_char(x, y, options.theme.stripe);
+            }
+        }
+    }
+
     for tick_layout in &gantt_layout.tick_layouts {
-        draw_tick(tick_layout, &mut canvas);
+        draw_tick(tick_layout, gantt_layout, &mut canvas);
+        if let Some(weekday_y) = gantt_layout.weekday_y {
+            let weekday = tick_layout.date.weekday().num_days_from_monday() as usize;
+            canvas.set_char(tick_layout.x, weekday_y, WEEKDAY_INITIALS[weekday]);
+        }
+    }
+
+    if let Some(month_y) = gantt_layout.month_y {
+        for month in &gantt_layout.months {
+            draw_month(month, month_y, &mut canvas);
+        }
+    }
+
+    for &y in &gantt_layout.section_rules {
+        draw_section_rule(y, gantt_layout, &mut canvas);
+    }
+
+    // Only in blank cells, and before the bars, which cover them
+    for guide in &gantt_layout.guides {
+        for x in guide.x_start..=guide.x_end {
+            if canvas.get_char(x, guide.y) == ' ' {
+                canvas.set_char(x, guide.y, gantt_layout.guide_char);
+            }
+        }
+    }
+
+    // Drawn before the bars, which only show the marker in their borders
+    for &x in &gantt_layout.axis_breaks {
+        draw_axis_break(x, gantt_layout, &mut canvas);
+    }
+
+    for section_layout in &gantt_layout.section_layouts {
+        draw_section_header(section_layout, &mut canvas);
+    }
+
+    for summary_layout in &gantt_layout.summary_layouts {
+        draw_summary(summary_layout, options, &mut canvas);
+    }
+
+    for lane_layout in &gantt_layout.lane_layouts {
+        canvas.draw_label("Lane name", lane_layout.x, lane_layout.y, &lane_layout.name);
+        for &y in &lane_layout.overlap_ys {
+            canvas.draw_label("Lane name", lane_layout.x, y, LANE_OVERLAP_LABEL);
+        }
+    }
+
+    // Drawn before the bars so that connectors passing behind a bar do not cross its interior
+    for dependency_layout in &gantt_layout.dependency_layouts {
+        draw_dependency(dependency_layout, &mut canvas);
     }
 
     for task_layout in &gantt_layout.task_layouts {
-        draw_task(task_layout, &mut canvas);
+        let on_critical_path = options.critical_path && task_layout.on_critical_path;
+        let bar = if on_critical_path {
+            &options.theme.critical_bar
+        } else {
+            &options.theme.bar
+        };
+        draw_task(task_layout, bar, options.name_fit, &mut canvas);
+        for &x in &gantt_layout.axis_breaks {
+            if task_layout.x_start < x && x < task_layout.x_end {
+                canvas.set_char(x, task_layout.y, AXIS_BREAK_CHAR);
+                canvas.set_char(x, task_layout.y + 2, AXIS_BREAK_CHAR);
+            }
+        }
+
+        if let Some(actual_layout) = &task_layout.actual {
+            draw_actual(actual_layout, &mut canvas);
+        }
+
+        if options.output.color
+            && let Some(style) = bar_style(
+                task_layout.section,
+                task_layout.status,
+                task_layout.critical || on_critical_path,
+            )
+        {
+            let (x_start, x_end, y) = (task_layout.x_start, task_layout.x_end, task_layout.y);
+            canvas.set_style(x_start, y, x_end, y + 2, style);
+            if let Some(actual_layout) = &task_layout.actual {
+                style_actual(actual_layout, style, &mut canvas);
+            }
+        }
+    }
+
+    for milestone_layout in &gantt_layout.milestone_layouts {
+        draw_milestone(milestone_layout, &mut canvas);
+
+        if let Some(actual_layout) = &milestone_layout.actual {
+            draw_actual(actual_layout, &mut canvas);
+        }
+
+        if options.output.color
+            && let Some(style) = bar_style(
+                milestone_layout.section,
+                milestone_layout.status,
+                milestone_layout.critical
+                    || (options.critical_path && milestone_layout.on_critical_path),
+            )
+        {
+            let (x, y) = (milestone_layout.x, milestone_layout.y);
+            canvas.set_style(x, y, x, y, style);
+            if let Some(actual_layout) = &milestone_layout.actual {
+                style_actual(actual_layout, style, &mut canvas);
+            }
+        }
+    }
+
+    draw_title(&gantt_layout.title_layouts, options, &mut canvas);
+    draw_legend(&gantt_layout.legend, options, &mut canvas);
+
+    // Only the shading left uncovered is dimmed, not the bars drawn over it
+    if options.output.color {
+        for &(x_start, x_end) in &gantt_layout.weekend_columns {
+            for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
+                for x in x_start..=x_end {
+                    if canvas.get_char(x, y) == WEEKEND_CHAR {
+                        canvas.set_style(x, y, x, y, SHADING_STYLE);
+                    }
+                }
+            }
+        }
+        for stripe in &gantt_layout.stripes {
+            for y in stripe.y_start..=stripe.y_end {
+                for x in stripe.x_start..=stripe.x_end {
+                    if canvas.get_char(x, y) == options.theme.stripe {
+                        canvas.set_style(x, y, x, y, SHADING_STYLE);
+                    }
+                }
+            }
+        }
+    }
+
+    // Drawn last so the marker stays visible where it crosses task boxes
+    if let Some(today_marker) = &gantt_layout.today_marker {
+        draw_today_marker(today_marker, gantt_layout, &mut canvas);
+    }
+
+    canvas
+}
+
+fn render_vertical(
+    gantt_layout: &GanttLayout,
+    vertical: &VerticalLayout,
+    options: &RenderOptions,
+) -> Canvas {
+    let mut canvas = Canvas::new(gantt_layout.width, gantt_layout.height);
+
+    for y in vertical.axis_start_y..=vertical.axis_end_y {
+        canvas.set_char(vertical.axis_x, y, '│');
+    }
+    // Drawn first so that the bars cover the tick lines
+    for tick in &vertical.ticks {
+        canvas.set_char(vertical.axis_x, tick.y, '┤');
+        for x in vertical.chart_x..=vertical.chart_end_x {
+            canvas.set_char(x, tick.y, '-');
+        }
+        if let Some(label) = &tick.label {
+            canvas.draw_label("Tick label", vertical.label_x, tick.y, label);
+        }
+    }
+
+    for column in &vertical.columns {
+        canvas.draw_label(
+            "Name of task",
+            column.x_start,
+            vertical.header_y,
+            &column.name,
+        );
+
+        let on_critical_path = options.critical_path && column.on_critical_path;
+        if column.milestone {
+            canvas.set_char(column.center_x(), column.y_start, '◆');
+        } else {
+            let bar = if on_critical_path {
+                &options.theme.critical_bar
+            } else {
+                &options.theme.bar
+            };
+            draw_column_bar(column, bar, &mut canvas);
+        }
+
+        if options.output.color
+            && let Some(style) = bar_style(
+                column.section,
+                column.status,
+                column.critical || on_critical_path,
+            )
+        {
+            if column.milestone {
+                let x = column.center_x();
+                canvas.set_style(x, column.y_start, x, column.y_start, style);
+            } else {
+                canvas.set_style(
+                    column.x_start,
+                    column.y_start,
+                    column.x_end,
+                    column.y_end,
+                    style,
+                );
+            }
+        }
+    }
+
+    draw_title(&gantt_layout.title_layouts, options, &mut canvas);
+    draw_legend(&gantt_layout.legend, options, &mut canvas);
+
+    // Drawn last so the marker stays visible where it crosses task boxes
+    if let Some(today_y) = vertical.today_y {
+        for x in vertical.chart_x..=vertical.chart_end_x {
+            canvas.set_char(x, today_y, '━');
+        }
+        let x = vertical.chart_end_x + 2;
+        canvas.draw_label("Today marker label", x, today_y, TODAY_LABEL);
+    }
+
+    canvas
+}
+
+/// Draws a task bar of a vertical chart, with the progress filling it from the top
+fn draw_column_bar(column: &ColumnLayout, bar: &BarChars, canvas: &mut Canvas) {
+    let (x_start, x_end) = (column.x_start, column.x_end);
+    let (y_start, y_end) = (column.y_start, column.y_end);
+
+    canvas.set_char(x_start, y_start, bar.top_left);
+    canvas.set_char(x_start, y_end, bar.bottom_left);
+    for x in x_start + 1..x_end {
+        canvas.set_char(x, y_start, bar.horizontal);
+        canvas.set_char(x, y_end, bar.horizontal);
+    }
+    canvas.set_char(x_end, y_start, bar.top_right);
+    canvas.set_char(x_end, y_end, bar.bottom_right);
+
+    for y in y_start + 1..y_end {
+        canvas.set_char(x_start, y, bar.left);
+        // Remove tick lines inside the box
+        let filled = column
+            .fill_until_y
+            .is_some_and(|fill_until_y| y < fill_until_y);
+        let interior = match bar.fill {
+            Some(fill) => fill,
+            None if filled => bar.progress,
+            None => ' ',
+        };
+        for x in x_start + 1..x_end {
+            canvas.set_char(x, y, interior);
+        }
+        canvas.set_char(x_end, y, bar.right);
+    }
+}
+
+/// Draws the title lines, bold with `color`, and the subtitle lines dimmed
+fn draw_title(title_layouts: &[TitleLayout], options: &RenderOptions, canvas: &mut Canvas) {
+    for line in title_layouts {
+        canvas.draw_label("Title", line.x, line.y, &line.text);
+        if options.output.color {
+            let style = if line.subtitle {
+                SUBTITLE_STYLE
+            } else {
+                TITLE_STYLE
+            };
+            let x_end = line.x + line.text.width().saturating_sub(1);
+            canvas.set_style(line.x, line.y, x_end, line.y, style);
+        }
+    }
+}
+
+/// Draws a summary row as a thin bar with its label, bold with `color`
+fn draw_summary(summary_layout: &SummaryLayout, options: &RenderOptions, canvas: &mut Canvas) {
+    let SummaryLayout {
+        x_start,
+        x_end,
+        y,
+        label_x,
+        ref label,
+    } = *summary_layout;
+
+    for x in x_start..=x_end {
+        canvas.set_char(x, y, options.theme.summary);
+    }
+    canvas.draw_label("Summary label", label_x, y, label);
+
+    if options.output.color {
+        let label_end = label_x + label.width().saturating_sub(1);
+        canvas.set_style(
+            x_start.min(label_x),
+            y,
+            x_end.max(label_end),
+            y,
+            SUMMARY_STYLE,
+        );
+    }
+}
+
+/// Draws the legend entries. Without color, the statuses and `crit` look like any other bar,
+/// so their entries are left out and the entries after them on the row move left.
+fn draw_legend(legend: &[LegendEntryLayout], options: &RenderOptions, canvas: &mut Canvas) {
+    // The row and column of the next entry
+    let mut next: Option<(usize, usize)> = None;
+    for entry in legend {
+        let color_only = matches!(entry.kind, LegendKind::Status(_) | LegendKind::Critical);
+        if color_only && !options.output.color {
+            continue;
+        }
+        let x = match next {
+            Some((y, x)) if y == entry.y => x,
+            _ => legend
+                .iter()
+                .find(|first| first.y == entry.y)
+                .map_or(entry.x, |first| first.x),
+        };
+        next = Some((entry.y, x + 2 + entry.label.width() + LEGEND_ENTRY_GAP));
+
+        draw_legend_entry(entry, x, canvas);
+
+        let style = match entry.kind {
+            LegendKind::Section(section) => bar_style(Some(section), None, false),
+            LegendKind::Status(status) => bar_style(None, Some(status), false),
+            LegendKind::Critical => bar_style(None, None, true),
+            LegendKind::Milestone => None,
+        };
+        if options.output.color
+            && let Some(style) = style
+        {
+            canvas.set_style(x, entry.y, x, entry.y, style);
+        }
+    }
+}
+
+/// Status colors take precedence over section colors, and `crit` over the status.
+/// Tasks without a section or status are not colored.
+fn bar_style(section: Option<usize>, status: Option<TaskStatus>, critical: bool) -> Option<Style> {
+    if critical {
+        return Some(CRITICAL_STYLE);
+    }
+    match status {
+        Some(TaskStatus::Done) => Some(DONE_STYLE),
+        Some(TaskStatus::Active) => Some(ACTIVE_STYLE),
+        None => section.map(|section| Style {
+            color: Some(SECTION_PALETTE[section % SECTION_PALETTE.len()]),
+            ..Style::default()
+        }),
+    }
+}
+
+fn style_actual(actual_layout: &ActualLayout, style: Style, canvas: &mut Canvas) {
+    canvas.set_style(
+        actual_layout.x_start,
+        actual_layout.y,
+        actual_layout.x_end,
+        actual_layout.y,
+        style,
+    );
+}
+
+/// The name as drawn inside the bar, shortened with `name_fit`. `None` when it is drawn outside
+/// the bar, as filled bars and bars with a duration label have no room for it.
+fn inside_name(task_layout: &TaskLayout, bar: &BarChars, name_fit: NameFit) -> Option<String> {
+    let name = &task_layout.name;
+    let interior_width = task_layout.x_end.saturating_sub(task_layout.x_start + 1);
+    if bar.fill.is_some() || (task_layout.duration.is_some() && name.width() > interior_width) {
+        return None;
+    }
+    if name.width() <= interior_width {
+        return Some(name.clone());
     }
 
-    canvas.to_string()
+    match name_fit {
+        NameFit::SpillRight => None,
+        NameFit::TruncateInside => {
+            (interior_width >= 2).then(|| truncate_name(name, interior_width))
+        }
+        NameFit::Initials => {
+            let initials: String = name
+                .split_whitespace()
+                .filter_map(|word| word.chars().next())
+                .flat_map(char::to_uppercase)
+                .collect();
+            (initials.width() <= interior_width).then_some(initials)
+        }
+    }
 }
 
-fn draw_task(task_layout: &TaskLayout, canvas: &mut Canvas) {
+fn draw_task(task_layout: &TaskLayout, bar: &BarChars, name_fit: NameFit, canvas: &mut Canvas) {
     let x_start = task_layout.x_start;
     let x_end = task_layout.x_end;
     let y = task_layout.y;
     let name = &task_layout.name;
-    let box_internal_width = x_end - x_start - 1;
+    let box_internal_width = x_end.saturating_sub(x_start + 1);
 
     // Top border
-    canvas.set_char(x_start, y, '┌');
+    canvas.set_char(x_start, y, bar.top_left);
     for x in x_start + 1..x_end {
-        canvas.set_char(x, y, '─');
+        canvas.set_char(x, y, bar.horizontal);
     }
-    canvas.set_char(x_end, y, '┐');
+    canvas.set_char(x_end, y, bar.top_right);
 
     // Mid line
-    canvas.set_char(x_start, y + 1, '|');
+    canvas.set_char(x_start, y + 1, bar.left);
     // Remove tick lines inside the box
     for x in x_start + 1..x_end {
-        canvas.set_char(x, y + 1, ' ');
+        canvas.set_char(x, y + 1, bar.fill.unwrap_or(' '));
     }
+    // The name is drawn over the fill so that it stays readable
+    if bar.fill.is_none()
+        && let Some(fill_until_x) = task_layout.fill_until_x
+    {
+        for x in x_start + 1..fill_until_x {
+            canvas.set_char(x, y + 1, bar.progress);
+        }
+    }
+    canvas.set_char(x_end, y + 1, bar.right);
 
-    let name_start_x = if name.width() > box_internal_width {
-        x_end + 1
-    } else {
-        x_start + (box_internal_width + 1) / 2 - (name.width() - 1) / 2
+    // A filled bar has no interior for the name
+    let centered_x = |text: &str| {
+        (x_start + box_internal_width.div_ceil(2))
+            .saturating_sub(text.width().saturating_sub(1) / 2)
     };
-
-    for (i, ch) in name.chars().enumerate() {
-        // TODO: Handle text overflow.
-        if name_start_x + i >= canvas.width {
-            break;
+    let (name_start_x, placed) = if let Some(name_x) = task_layout.name_x {
+        (name_x, name.clone())
+    } else if let Some(inside) = inside_name(task_layout, bar, name_fit) {
+        (centered_x(&inside), inside)
+    } else {
+        let (x, placed) = outside_name_placement(name, x_start, x_end, canvas.width);
+        if &placed != name {
+            canvas.clipped_labels.push(ClippedLabel {
+                label: name.clone(),
+                message: format!(
+                    "Name of task '{}' is shortened to '{}' to fit beside its bar",
+                    name, placed
+                ),
+            });
         }
-        canvas.set_char(name_start_x + i, y + 1, ch);
+        (x, placed)
+    };
+    if let Some(duration) = &task_layout.duration {
+        canvas.draw_label("Duration label", centered_x(duration), y + 1, duration);
     }
-    canvas.set_char(x_end, y + 1, '|');
+
+    canvas.draw_label("Name of task", name_start_x, y + 1, &placed);
 
     // Bottom border
-    canvas.set_char(x_start, y + 2, '└');
+    canvas.set_char(x_start, y + 2, bar.bottom_left);
     for x in x_start + 1..x_end {
-        canvas.set_char(x, y + 2, '─');
+        canvas.set_char(x, y + 2, bar.horizontal);
+    }
+    canvas.set_char(x_end, y + 2, bar.bottom_right);
+}
+
+fn draw_milestone(milestone_layout: &MilestoneLayout, canvas: &mut Canvas) {
+    canvas.set_char(milestone_layout.x, milestone_layout.y, '◆');
+    let (x, y) = (milestone_layout.name_x, milestone_layout.y);
+    canvas.draw_label("Name of milestone", x, y, &milestone_layout.name);
+}
+
+fn draw_dependency(dependency_layout: &DependencyLayout, canvas: &mut Canvas) {
+    let DependencyLayout {
+        from_x,
+        from_y,
+        turn_x,
+        to_x,
+        to_y,
+    } = *dependency_layout;
+
+    if from_x > turn_x {
+        for x in turn_x + 1..=from_x {
+            draw_connector_char(canvas, x, from_y, '─');
+        }
+        draw_connector_char(canvas, turn_x, from_y, '┌');
+    } else {
+        draw_connector_char(canvas, turn_x, from_y, '│');
+    }
+    for y in from_y + 1..to_y {
+        draw_connector_char(canvas, turn_x, y, '│');
+    }
+
+    draw_connector_char(canvas, turn_x, to_y, '└');
+    for x in turn_x + 1..to_x {
+        draw_connector_char(canvas, x, to_y, '─');
+    }
+    canvas.set_char(to_x, to_y, '▶');
+}
+
+/// Box-drawing chars by the directions their lines point to: up, down, left, right
+const CONNECTOR_CHARS: [(char, [bool; 4]); 11] = [
+    ('│', [true, true, false, false]),
+    ('─', [false, false, true, true]),
+    ('┌', [false, true, false, true]),
+    ('┐', [false, true, true, false]),
+    ('└', [true, false, false, true]),
+    ('┘', [true, false, true, false]),
+    ('├', [true, true, false, true]),
+    ('┤', [true, true, true, false]),
+    ('┬', [false, true, true, true]),
+    ('┴', [true, false, true, true]),
+    ('┼', [true, true, true, true]),
+];
+
+/// Draws a connector char, joining it with a connector already drawn at that position
+fn draw_connector_char(canvas: &mut Canvas, x: usize, y: usize, ch: char) {
+    let directions = |ch: char| {
+        CONNECTOR_CHARS
+            .iter()
+            .find(|(connector, _)| *connector == ch)
+            .map(|(_, directions)| *directions)
+    };
+
+    let merged = match (directions(canvas.get_char(x, y)), directions(ch)) {
+        (Some(existing), Some(new)) => {
+            let joined: [bool; 4] = std::array::from_fn(|i| existing[i] || new[i]);
+            CONNECTOR_CHARS
+                .iter()
+                .find(|(_, directions)| *directions == joined)
+                .map_or(ch, |(connector, _)| *connector)
+        }
+        _ => ch,
+    };
+    canvas.set_char(x, y, merged);
+}
+
+fn draw_section_rule(y: usize, gantt_layout: &GanttLayout, canvas: &mut Canvas) {
+    let (Some(first_tick), Some(last_tick)) = (
+        gantt_layout.tick_layouts.first(),
+        gantt_layout.tick_layouts.last(),
+    ) else {
+        return;
+    };
+
+    for x in first_tick.x..=last_tick.x {
+        // Crosses the tick lines
+        let ch = if canvas.get_char(x, y) == '|' {
+            '┼'
+        } else {
+            '─'
+        };
+        canvas.set_char(x, y, ch);
+    }
+}
+
+fn draw_axis_break(x: usize, gantt_layout: &GanttLayout, canvas: &mut Canvas) {
+    for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
+        canvas.set_char(x, y, AXIS_BREAK_CHAR);
+    }
+    // Tick labels next to the gap may cover the marker on the axis
+    if canvas.get_char(x, gantt_layout.axis_y) == ' ' {
+        canvas.set_char(x, gantt_layout.axis_y, AXIS_BREAK_CHAR);
+    }
+}
+
+fn draw_section_header(section_layout: &SectionLayout, canvas: &mut Canvas) {
+    let header = format!("▸ {}", section_layout.name);
+    canvas.draw_label(
+        "Section header",
+        section_layout.x,
+        section_layout.y,
+        &header,
+    );
+}
+
+fn draw_actual(actual_layout: &ActualLayout, canvas: &mut Canvas) {
+    for x in actual_layout.x_start..=actual_layout.x_end {
+        canvas.set_char(x, actual_layout.y, '━');
     }
-    canvas.set_char(x_end, y + 2, '┘');
 }
 
-fn draw_tick(tick_layout: &TickLayout, canvas: &mut Canvas) {
-    for y in MARGIN_TOP - 1..canvas.height - MARGIN_BOTTOM + 1 {
+fn draw_tick(tick_layout: &TickLayout, gantt_layout: &GanttLayout, canvas: &mut Canvas) {
+    for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
         canvas.set_char(tick_layout.x, y, '|');
     }
-    let date = tick_layout.date.format("%d-%m-%Y").to_string();
 
-    let date_start_x = tick_layout.x - date.width() / 2;
+    let Some(label) = &tick_layout.label else {
+        return;
+    };
+    let label_start_x = tick_layout.x.saturating_sub(label.width() / 2);
+    canvas.draw_label("Tick label", label_start_x, gantt_layout.axis_y, label);
+}
+
+fn draw_month(month: &MonthLayout, y: usize, canvas: &mut Canvas) {
+    if month.starts_on_chart {
+        canvas.set_char(month.x_start, y, '|');
+    }
+    canvas.draw_label("Month name", month.label_x(), y, &month.label);
+}
+
+fn draw_legend_entry(entry: &LegendEntryLayout, x: usize, canvas: &mut Canvas) {
+    let text = format!("{} {}", entry.symbol, entry.label);
+    canvas.draw_label("Legend entry", x, entry.y, &text);
+}
+
+fn draw_today_marker(
+    today_marker: &TodayMarkerLayout,
+    gantt_layout: &GanttLayout,
+    canvas: &mut Canvas,
+) {
+    for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
+        canvas.set_char(today_marker.x, y, '┃');
+    }
+
+    // The label goes on the row below the tick labels, if the bottom margin leaves room for it
+    let label_y = gantt_layout.axis_y + 1;
+    let label_start_x = today_marker.x.saturating_sub(TODAY_LABEL.width() / 2);
+    canvas.draw_label("Today marker label", label_start_x, label_y, TODAY_LABEL);
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::canvas::rendered_bounds;
+    use crate::gantt::layout::{
+        ACTUAL_BAR_HEIGHT, CHART_WIDTH, DurationLabels, GUTTER_MARGIN_LEFT, GUTTER_PADDING,
+        GanttLayoutConfig, LabelPlacement, MARGIN_BOTTOM, MARGIN_LEFT, MARGIN_TOP, Orientation,
+        RowGuides, SummaryRows, TASK_HEIGHT, WidthMode, layout_with_config,
+    };
+    use crate::gantt::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn test_configurable_chart_width() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Implementation: 05-01-2026, 15-01-2026
+Testing: 15-01-2026, 20-01-2026
+Bugfix: 20-01-2026, 03-02-2026
+Release: 03-02-2026, 06-02-2026";
+        let gantt_chart = parse(input).unwrap();
+
+        for chart_width in [60, 200] {
+            let config = GanttLayoutConfig {
+                chart_width: WidthMode::Fixed(chart_width),
+                ..GanttLayoutConfig::default()
+            };
+            let gantt_layout = layout_with_config(&gantt_chart, &config);
+            let output = render(&gantt_layout);
+
+            let expected_width = chart_width + config.margins.left + config.margins.right;
+            assert_eq!(gantt_layout.width, expected_width);
+            assert_eq!(output.lines().count(), gantt_layout.height);
+            for line in output.lines() {
+                assert_eq!(line.chars().count(), expected_width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tick_labels_not_clobbered() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Implementation: 05-01-2026, 11-01-2026";
+        let gantt_chart = parse(input).unwrap();
+
+        // Daily ticks 12 columns apart (MIN_TICK_SPACING) and 6 columns apart
+        for chart_width in [120, 60] {
+            let config = GanttLayoutConfig {
+                chart_width: WidthMode::Fixed(chart_width),
+                ..GanttLayoutConfig::default()
+            };
+            let gantt_layout = layout_with_config(&gantt_chart, &config);
+            let output = render(&gantt_layout);
+
+            let axis_row = output.lines().nth(gantt_layout.axis_y).unwrap();
+            let rendered_labels: Vec<&str> = axis_row.split_whitespace().collect();
+            let expected_labels: Vec<&str> = gantt_layout
+                .tick_layouts
+                .iter()
+                .filter_map(|tick| tick.label.as_deref())
+                .collect();
+
+            assert_eq!(rendered_labels, expected_labels);
+        }
+    }
+
+    #[test]
+    fn test_today_marker_drawn_over_tasks() {
+        let input = "
+today marker: on
+Design: 01-01-2026, 11-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        let config = GanttLayoutConfig {
+            clock: || NaiveDate::from_ymd_opt(2026, 1, 6).unwrap(),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+
+        let marker_x = gantt_layout.today_marker.as_ref().unwrap().x;
+        let task_y = gantt_layout.task_layouts[0].y;
+        // The marker crosses the task box, including its borders
+        for line in &lines[gantt_layout.tick_start_y..=gantt_layout.tick_end_y] {
+            assert_eq!(line.chars().nth(marker_x), Some('┃'));
+        }
+        assert!(lines[task_y].contains('┌'));
+
+        let label_row = lines[gantt_layout.axis_y + 1];
+        assert_eq!(label_row.trim(), "today");
+        assert_eq!(label_row.find("today"), Some(marker_x - 2));
+    }
+
+    #[test]
+    fn test_gutter_labels() {
+        let input = "
+Design: 01-01-2026, 02-01-2026
+Implementation: 02-01-2026, 20-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        let config = GanttLayoutConfig {
+            label_placement: LabelPlacement::Gutter,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Names are left-aligned in the gutter and bars start right of it
+        let chart_x = "Implementation".len() + GUTTER_MARGIN_LEFT + GUTTER_PADDING;
+        for task_layout in &gantt_layout.task_layouts {
+            let name_row = lines[task_layout.y + 1];
+            assert_eq!(name_row.find(&task_layout.name), Some(GUTTER_MARGIN_LEFT));
+            assert_eq!(name_row.matches(&task_layout.name).count(), 1);
+            assert!(task_layout.x_start >= chart_x);
+        }
+        assert_eq!(gantt_layout.tick_layouts[0].x, chart_x);
+        assert_eq!(
+            gantt_layout.width,
+            chart_x + CHART_WIDTH + config.margins.right
+        );
+    }
+
+    #[test]
+    fn test_resource_lanes() {
+        let input = "
+Design: 01-01-2026, 10-01-2026 | assignee Ada
+Build: 10-01-2026, 20-01-2026 | assignee Ada
+Review: 05-01-2026, 12-01-2026 | assignee Ada
+Docs: 01-01-2026, 1d | assignee Grace";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(40),
+            resource_lanes: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+
+        let ada = &gantt_layout.lane_layouts[0];
+        assert!(lines[ada.y].starts_with(" Ada "));
+        assert!(lines[ada.y].contains("Design"));
+        assert!(lines[ada.y].contains("Build"));
+        let overlap_row = lines[ada.overlap_ys[0]];
+        assert!(overlap_row.starts_with(&format!(" {}", LANE_OVERLAP_LABEL)));
+        assert!(overlap_row.contains("Review"));
+
+        let grace = &gantt_layout.lane_layouts[1];
+        assert!(lines[grace.y].starts_with(" Grace "));
+        assert!(!output.contains("Docs"));
+    }
+
+    #[test]
+    fn test_summary_row() {
+        let input = "
+Design: 01-01-2026, 10-01-2026
+Code: 10-01-2026, 06-02-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(60),
+            summary_rows: SummaryRows::Project,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let summary = &gantt_layout.summary_layouts[0];
+        let row: String = output
+            .lines()
+            .nth(summary.y)
+            .unwrap()
+            .chars()
+            .skip(summary.x_start)
+            .take(summary.x_end - summary.x_start + 1)
+            .collect();
+
+        assert!(row.starts_with("━━━"));
+        assert!(row.ends_with("━━━"));
+        assert!(row.contains(" Project: 01-01-2026 → 06-02-2026, 36d "));
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+
+        let options = RenderOptions {
+            theme: GanttTheme::ascii(),
+            ..RenderOptions::default()
+        };
+        let output = render_with_options(&gantt_layout, &options);
+        assert!(!output.contains('━'));
+        assert!(output.contains("--- Project"));
+    }
+
+    #[test]
+    fn test_try_render() {
+        let input = "Design: 01-01-2026, 10-01-2026\nReview: 12-01-2026, 20-01-2026";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+        assert_eq!(try_render(&gantt_layout).unwrap(), render(&gantt_layout));
+
+        // Too long for either side of a bar spanning the chart
+        let long_name = "A name far longer than the chart is wide";
+        let input = format!("{long_name}: 01-01-2026, 20-01-2026");
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(20),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(&input).unwrap(), &config);
+        let err = try_render(&gantt_layout).unwrap_err();
+        assert_eq!(err.label, long_name);
+        assert!(
+            err.message
+                .starts_with(&format!("Name of task '{long_name}' is shortened"))
+        );
+
+        // Milestone names are clipped at the edge of the canvas
+        let input = format!("Design: 01-01-2026, 20-01-2026\n{long_name}: 10-01-2026, 10-01-2026");
+        let gantt_layout = layout_with_config(&parse(&input).unwrap(), &config);
+        let err = try_render(&gantt_layout).unwrap_err();
+        assert_eq!(
+            err.message,
+            format!(
+                "Name of milestone '{long_name}' is clipped at column {}",
+                gantt_layout.width
+            )
+        );
+    }
+
+    #[test]
+    fn test_one_day_task_on_long_chart() {
+        let input = "
+Project: 01-01-2026, 01-07-2026
+Review: 10-03-2026, 1d";
+        let gantt_chart = parse(input).unwrap();
+        let gantt_layout = layout_with_config(&gantt_chart, &GanttLayoutConfig::default());
+        let output = render(&gantt_layout);
+
+        let review = &gantt_layout.task_layouts[1];
+        let lines: Vec<&str> = output.lines().collect();
+        let top_border: String = lines[review.y]
+            .chars()
+            .skip(review.x_start)
+            .take(3)
+            .collect();
+        assert_eq!(top_border, "┌─┐");
+        assert!(!output.contains("┌┐"));
+    }
+
+    #[test]
+    fn test_long_name_overflow() {
+        let long_name = "A task name that is far too long to fit on the right of it!!";
+        assert_eq!(long_name.len(), 60);
+        let input = format!("Kickoff: 01-01-2026, 2d\n{long_name}: 03-01-2026, 20-01-2026");
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(40),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(&input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let task_layout = &gantt_layout.task_layouts[1];
+        let name_row = output.lines().nth(task_layout.y + 1).unwrap();
+
+        // Truncated right of the bar, which has more room than its left
+        assert_eq!(name_row.chars().count(), gantt_layout.width);
+        assert!(name_row.trim_end().ends_with('…'));
+        assert!(name_row.contains("A task name"));
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+
+        // Moved left of a bar at the end of the chart when it fits there
+        let input =
+            "Kickoff: 05-01-2026, 2d\nA long name for the last task: 23-01-2026, 26-01-2026";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+        let output = render(&gantt_layout);
+        let task_layout = &gantt_layout.task_layouts[1];
+        let name_row = output.lines().nth(task_layout.y + 1).unwrap();
+        let name_x = name_row.find("A long name for the last task").unwrap();
+        assert_eq!(
+            name_x + "A long name for the last task".len() + 1,
+            task_layout.x_start
+        );
+        assert!(!output.contains('…'));
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_title_and_subtitle() {
+        let input = "
+title Release plan
+subtitle Q1 2026
+Design: 05-01-2026, 5d
+Build: continue, 10d";
+        let gantt_chart = parse(input).unwrap();
+        for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+            let config = GanttLayoutConfig {
+                orientation,
+                ..GanttLayoutConfig::default()
+            };
+            let gantt_layout = layout_with_config(&gantt_chart, &config);
+            let output = render(&gantt_layout);
+            let lines: Vec<&str> = output.lines().collect();
+
+            let title = &gantt_layout.title_layouts[0];
+            assert_eq!(lines[title.y].find("Release plan"), Some(title.x));
+            assert!(lines[title.y + 1].contains("Q1 2026"));
+            // Nothing else shares the title rows or the blank row below them
+            for line in &lines[..title.y + 3] {
+                assert!(!line.contains(['|', '┌', '│']), "{}", line);
+            }
+            assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+        }
+
+        let gantt_layout = layout_with_config(&gantt_chart, &GanttLayoutConfig::default());
+        let colored = render_with_options(
+            &gantt_layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        assert!(colored.contains(&format!("{}Release plan", TITLE_STYLE.escape_code())));
+        assert!(colored.contains(&format!("{}Q1 2026", SUBTITLE_STYLE.escape_code())));
+    }
+
+    #[test]
+    fn test_section_headers() {
+        let input = "
+section Design
+Wireframes: 01-01-2026, 5d
+section Build
+Implementation: continue, 10d";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+
+        for section_layout in &gantt_layout.section_layouts {
+            let header = format!("▸ {}", section_layout.name);
+            let row: String = lines[section_layout.y]
+                .chars()
+                .skip(section_layout.x)
+                .take(header.chars().count())
+                .collect();
+            assert_eq!(row, header);
+        }
+        // The spacer above the second header only holds tick lines
+        let spacer = lines[gantt_layout.section_layouts[1].y - 1];
+        assert!(spacer.chars().all(|ch| ch == ' ' || ch == '|'));
+    }
+
+    #[test]
+    fn test_chart_without_sections_unchanged() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Implementation: 05-01-2026, 15-01-2026 | actual 06-01-2026, 18-01-2026";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+
+        assert!(gantt_layout.section_layouts.is_empty());
+        assert_eq!(
+            gantt_layout.height,
+            TASK_HEIGHT * 2 + ACTUAL_BAR_HEIGHT + MARGIN_TOP + MARGIN_BOTTOM
+        );
+        assert_eq!(gantt_layout.task_layouts[1].y, MARGIN_TOP + TASK_HEIGHT);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let input = "
+today marker: on
+section Design
+Wireframes: 01-01-2026, 5d | actual 02-01-2026, 7d | id wireframes
+A task with a long name: continue, 1d
+section Build
+Implementation: after wireframes, 10d
+Launch: 17-01-2026, 17-01-2026";
+        let gantt_chart = parse(input).unwrap();
+        let mut config = GanttLayoutConfig {
+            clock: || NaiveDate::from_ymd_opt(2026, 1, 8).unwrap(),
+            ..GanttLayoutConfig::default()
+        };
+
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let (min_x, min_y, max_x, max_y) = gantt_layout.bounding_box();
+        assert_eq!(
+            (min_x, min_y, max_x, max_y),
+            rendered_bounds(&render(&gantt_layout))
+        );
+
+        // Wider margins move the content away from the canvas edges without resizing it
+        config.margins.left += 4;
+        config.margins.top += 2;
+        let shifted_layout = layout_with_config(&gantt_chart, &config);
+        assert_eq!(
+            shifted_layout.bounding_box(),
+            (min_x + 4, min_y + 2, max_x + 4, max_y + 2)
+        );
+        assert_eq!(shifted_layout.width, gantt_layout.width + 4);
+        assert_eq!(shifted_layout.height, gantt_layout.height + 2);
+
+        // The month header and the legend below the axis
+        let input = "
+section Planning
+Research: 15-01-2026, 40d | done
+Review: continue, 10-03-2026";
+        let config = GanttLayoutConfig {
+            month_header: true,
+            legend: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        assert!(!gantt_layout.months.is_empty() && !gantt_layout.legend.is_empty());
+        assert_eq!(
+            gantt_layout.bounding_box(),
+            rendered_bounds(&render(&gantt_layout))
+        );
+    }
+
+    #[test]
+    fn test_progress_fill() {
+        let input = "
+Design: 01-01-2026, 01-07-2026 | progress 0%
+Build: 01-01-2026, 01-07-2026 | progress 50%
+Test: 01-01-2026, 01-07-2026 | progress 100%
+Review: 05-01-2026, 06-01-2026 | progress 100%";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+
+        let interior = |task: &TaskLayout| -> String {
+            lines[task.y + 1]
+                .chars()
+                .skip(task.x_start + 1)
+                .take(task.x_end - task.x_start - 1)
+                .collect()
+        };
+
+        let tasks = &gantt_layout.task_layouts;
+        assert!(!interior(&tasks[0]).contains('█'));
+        let half_filled = interior(&tasks[1]);
+        assert!(half_filled.starts_with('█'));
+        assert!(half_filled.ends_with(' '));
+        // The name overwrites the fill
+        assert!(interior(&tasks[2]).contains("Test"));
+        assert_eq!(
+            interior(&tasks[2]).replace("Test", "").trim_matches('█'),
+            ""
+        );
+        // A two column bar has a single interior cell
+        assert_eq!(interior(&tasks[3]), "█");
+    }
+
+    #[test]
+    fn test_milestone_on_last_date() {
+        let input = "
+Build: 01-01-2026, 05-01-2026
+Launch: 05-01-2026, 05-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(24),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+
+        // The name would run off the chart right of the diamond, so it is drawn left of it
+        let expected = [
+            "",
+            "      |     |     |     |     |",
+            "      ┌───────────────────────┐",
+            "      |         Build         |",
+            "      └───────────────────────┘",
+            "      |     |     |    Launch ◆",
+            "      |     |     |     |     |",
+            "    01-01 02-01 03-01 04-01 05-01",
+            "",
+        ];
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_row_guides() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 10-01-2026
+Ship: 11-01-2026, 11-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            label_placement: LabelPlacement::Gutter,
+            row_guides: RowGuides::TaskLines,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+
+        // Through the gaps of the name rows, but not the gutter or the bars
+        assert_eq!(
+            lines[3],
+            " Plan   |             |┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈|"
+        );
+        assert_eq!(
+            lines[6],
+            " Build  |┈┈┈┈┈┈|┈┈┈┈┈┈|                    |┈┈┈┈┈┈|"
+        );
+        assert_eq!(
+            lines[8],
+            " Ship   |┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈|┈┈┈┈┈┈◆"
+        );
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            row_guides: RowGuides::AlternateRows,
+            guide_char: Some('.'),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        let guide_rows: Vec<usize> = gantt_layout.guides.iter().map(|guide| guide.y).collect();
+        assert_eq!(guide_rows, [2, 4, 6, 8]);
+        assert_eq!(
+            lines[4],
+            "      └─────────────┘......|......|......|......|"
+        );
+        assert_eq!(
+            lines[8],
+            "      |......|......|......|......|......|......◆ Ship"
+        );
+        // The margin is left blank
+        assert!(lines[6].starts_with("      |......|"));
+    }
+
+    #[test]
+    fn test_row_stripes() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 10-01-2026
+Ship: 11-01-2026, 11-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            row_stripes: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+
+        // Plan and Ship are shaded around the bars, with the tick lines left whole. Build is not.
+        for line in &lines[2..=4] {
+            assert!(line.contains("|░░░░░░|░░░░░░|"), "{}", line);
+        }
+        assert_eq!(
+            lines[8],
+            "      |░░░░░░|░░░░░░|░░░░░░|░░░░░░|░░░░░░|░░░░░░◆ Ship"
+        );
+        for line in &lines[5..=7] {
+            assert!(!line.contains('░'), "{}", line);
+        }
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_themes() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026 | progress 50%
+Build: 07-01-2026, 10-01-2026
+Ship: 11-01-2026, 11-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let render_theme = |theme: GanttTheme| {
+            let options = RenderOptions {
+                theme,
+                ..RenderOptions::default()
+            };
+            let output = render_with_options(&gantt_layout, &options);
+            // The task rows, below the ticks
+            let lines: Vec<String> = output
+                .lines()
+                .map(|line| line.trim_end().to_string())
+                .collect();
+            lines[2..8].to_vec()
+        };
+
+        assert_eq!(
+            render_theme(GanttTheme::default()),
+            render_theme(GanttTheme::boxes())
+        );
+        assert_eq!(
+            render_theme(GanttTheme::boxes()),
+            [
+                "      ┌─────────────┐      |      |      |      |",
+                "      |█████Plan    |      |      |      |      |",
+                "      └─────────────┘      |      |      |      |",
+                "      |      |      ┌────────────────────┐      |",
+                "      |      |      |       Build        |      |",
+                "      |      |      └────────────────────┘      |",
+            ]
+        );
+        // Solid bars have no room for the name, which goes right of them
+        assert_eq!(
+            render_theme(GanttTheme::blocks()),
+            [
+                "      ███████████████      |      |      |      |",
+                "      ███████████████Plan  |      |      |      |",
+                "      ███████████████      |      |      |      |",
+                "      |      |      ██████████████████████      |",
+                "      |      |      ██████████████████████Build |",
+                "      |      |      ██████████████████████      |",
+            ]
+        );
+        assert_eq!(
+            render_theme(GanttTheme::ascii()),
+            [
+                "      +=============+      |      |      |      |",
+                "      [#####Plan    ]      |      |      |      |",
+                "      +=============+      |      |      |      |",
+                "      |      |      +====================+      |",
+                "      |      |      [       Build        ]      |",
+                "      |      |      +====================+      |",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_durations_in_bars() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 12-01-2026
+Ship: 14-01-2026, 14-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            label_placement: LabelPlacement::Gutter,
+            duration_labels: DurationLabels::NarrowBars,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+
+        assert_eq!(
+            lines[2..9],
+            [
+                "        ┌────────┐    |   |    |    |   |    |    |",
+                " Plan   |   2d   |    |   |    |    |   |    |    |",
+                "        └────────┘    |   |    |    |   |    |    |",
+                "        |   |    ┌──────────────────────┐    |    |",
+                " Build  |   |    |          5d          |    |    |",
+                "        |   |    └──────────────────────┘    |    |",
+                " Ship   |   |    |    |   |    |    |   |    |    ◆",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekday_letters_and_weekend_shading() {
+        let input = "
+Plan: 05-01-2026, 07-01-2026
+Build: 07-01-2026, 10-01-2026
+Ship: 11-01-2026, 11-01-2026";
+        let config = GanttLayoutConfig {
+            chart_width: WidthMode::Fixed(42),
+            weekday_letters: true,
+            weekend_shading: true,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
+
+        let expected = [
+            "",
+            "      |      |      |      |      |      |······|",
+            "      ┌─────────────┐      |      |      |······|",
+            "      |     Plan    |      |      |      |······|",
+            "      └─────────────┘      |      |      |······|",
+            "      |      |      ┌────────────────────┐······|",
+            "      |      |      |       Build        |······|",
+            "      |      |      └────────────────────┘······|",
+            "      |      |      |      |      |      |······◆ Ship",
+            "      |      |      |      |      |      |······|",
+            "      M      T      W      T      F      S      S",
+            "    05-01  06-01  07-01  08-01  09-01  10-01  11-01",
+            "",
+        ];
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+
+        // Weekly ticks get neither
+        let input = "Plan: 05-01-2026, 31-01-2026";
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        assert_eq!(gantt_layout.weekday_y, None);
+        assert!(gantt_layout.weekend_columns.is_empty());
+    }
+
+    /// Removes the ANSI escape codes added by `RenderOptions::color`
+    fn strip_escape_codes(output: &str) -> String {
+        let mut stripped = String::new();
+        let mut chars = output.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                chars.by_ref().find(|&ch| ch == 'm');
+            } else {
+                stripped.push(ch);
+            }
+        }
+        stripped
+    }
+
+    #[test]
+    fn test_color_output() {
+        let input = "
+today marker: on
+Kickoff: 01-01-2026, 2d
+section Design
+Wireframes: continue, 5d | done
+Mockups: continue, 4d | actual 08-01-2026, 3d
+section Build
+Implementation: continue, 10d | active | crit
+Beta: continue, 3d
+Launch: continue, 25-01-2026";
+        let config = GanttLayoutConfig {
+            clock: || NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+
+        let plain = render(&gantt_layout);
+        assert!(!plain.contains('\x1b'));
+        let colored = render_with_options(
+            &gantt_layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        assert!(colored.contains('\x1b'));
+        assert_eq!(strip_escape_codes(&colored), plain);
+
+        let lines: Vec<&str> = colored.lines().collect();
+        let task_line = |index: usize| lines[gantt_layout.task_layouts[index].y];
+        // Tasks outside sections are not colored
+        assert!(!task_line(0).contains('\x1b'));
+        assert!(task_line(1).contains(&DONE_STYLE.escape_code()));
+        // Sections cycle through the palette, statuses override the section color
+        let design_style = Style {
+            color: Some(SECTION_PALETTE[0]),
+            ..Style::default()
+        };
+        let build_style = Style {
+            color: Some(SECTION_PALETTE[1]),
+            ..Style::default()
+        };
+        assert!(task_line(2).contains(&design_style.escape_code()));
+        assert!(task_line(3).contains(&CRITICAL_STYLE.escape_code()));
+        assert!(!task_line(3).contains(&ACTIVE_STYLE.escape_code()));
+        assert!(task_line(4).contains(&build_style.escape_code()));
+        let actual_y = gantt_layout.task_layouts[2].actual.as_ref().unwrap().y;
+        assert!(lines[actual_y].contains(&design_style.escape_code()));
+        let launch_y = gantt_layout.milestone_layouts[0].y;
+        assert!(lines[launch_y].contains(&build_style.escape_code()));
+
+        // Ticks, axis labels and the today marker stay uncolored
+        let axis_line = lines[gantt_layout.axis_y];
+        assert!(!axis_line.contains('\x1b'));
+        let today_x = gantt_layout.today_marker.as_ref().unwrap().x;
+        let spacer_line = lines[gantt_layout.tick_start_y];
+        assert!(!spacer_line.contains('\x1b'));
+        assert_eq!(
+            strip_escape_codes(task_line(3)).chars().nth(today_x),
+            Some('┃')
+        );
+    }
+
+    #[test]
+    fn test_dependency_connectors() {
+        let input = "
+Design: 01-01-2026, 5d | id design
+Review: after design, 06-01-2026
+Build: after design, 10d";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+        let output = render(&gantt_layout);
+        let lines: Vec<Vec<char>> = output.lines().map(|line| line.chars().collect()).collect();
+
+        let [to_review, to_build] = &gantt_layout.dependency_layouts[..] else {
+            panic!("Expected two dependency layouts");
+        };
+        // Both connectors share the column below Design and branch off to their successor
+        assert_eq!(to_review.turn_x, to_build.turn_x);
+        let x = to_build.turn_x;
+        assert_eq!(lines[to_review.to_y][x], '├');
+        assert_eq!(lines[to_review.to_y][to_review.to_x], '▶');
+        assert_eq!(lines[to_build.to_y][x], '└');
+        assert_eq!(lines[to_build.to_y][to_build.to_x], '▶');
+        for line in &lines[to_review.to_y + 1..to_build.to_y] {
+            assert_eq!(line[x], '│');
+        }
+    }
+
+    #[test]
+    fn test_section_rules() {
+        let input = "
+section Design
+Wireframes: 01-01-2026, 5d
+section Build
+Implementation: continue, 10d";
+        let gantt_chart = parse(input).unwrap();
+        let mut config = GanttLayoutConfig::default();
+        assert!(
+            layout_with_config(&gantt_chart, &config)
+                .section_rules
+                .is_empty()
+        );
+
+        config.section_rules = true;
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        // The first header is at the top, so only the second one gets a rule above it
+        let build_header_y = gantt_layout.section_layouts[1].y;
+        assert_eq!(gantt_layout.section_rules, [build_header_y - 1]);
+
+        let output = render(&gantt_layout);
+        let rule: String = output
+            .lines()
+            .nth(build_header_y - 1)
+            .unwrap()
+            .chars()
+            .skip(gantt_layout.tick_layouts[0].x)
+            .collect();
+        let rule = rule.trim_end();
+        assert!(rule.starts_with('┼') && rule.ends_with('┼'));
+        assert!(rule.chars().all(|ch| ch == '─' || ch == '┼'));
+        assert_eq!(rule.matches('┼').count(), gantt_layout.tick_layouts.len());
+    }
+
+    #[test]
+    fn test_axis_breaks() {
+        let input = "
+Planning: 05-01-2026, 10d
+Support: 12-01-2026, 19-06-2026
+Launch: 08-06-2026, 10d";
+        let config = GanttLayoutConfig {
+            compress_gaps: Some(14),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let break_x = gantt_layout.axis_breaks[0];
+
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+        let column = |y: usize| lines[y].chars().nth(break_x).unwrap();
+        // Blank rows and the borders of the bar across the gap get the marker
+        let support_y = gantt_layout.task_layouts[1].y;
+        for y in gantt_layout.tick_start_y..=gantt_layout.tick_end_y {
+            let expected = if y == support_y + 1 { ' ' } else { '≈' };
+            assert_eq!(column(y), expected, "row {y}");
+        }
+    }
+
+    #[test]
+    fn test_legend() {
+        let input = "
+Kickoff: 01-01-2026, 2d
+section Design
+Wireframes: continue, 5d | done
+section Build
+Implementation: continue, 10d | crit
+Launch: continue, 18-01-2026
+section Later";
+        let gantt_chart = parse(input).unwrap();
+        let mut config = GanttLayoutConfig::default();
+        let plain_layout = layout_with_config(&gantt_chart, &config);
+        assert!(plain_layout.legend.is_empty());
+
+        config.legend = true;
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        assert!(gantt_layout.height > plain_layout.height);
+        // Only what the chart uses is listed
+        let labels: Vec<&str> = gantt_layout
+            .legend
+            .iter()
+            .map(|entry| entry.label.as_str())
+            .collect();
+        assert_eq!(labels, ["done", "critical", "milestone", "Design", "Build"]);
+
+        let output = render(&gantt_layout);
+        let legend_y = gantt_layout.legend[0].y;
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        // Statuses and `crit` are shown by color only, so without color they are left out
+        assert_eq!(
+            lines[legend_y],
+            format!("{}◆ milestone", " ".repeat(MARGIN_LEFT))
+        );
+        assert_eq!(
+            lines[legend_y + 1],
+            format!("{}■ Design  ■ Build", " ".repeat(MARGIN_LEFT))
+        );
+        assert!(legend_y > gantt_layout.axis_y);
+        assert_eq!(gantt_layout.height, legend_y + 2);
+
+        // The symbols take the colors of the bars they explain
+        let colored = render_with_options(
+            &gantt_layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        let design_style = Style {
+            color: Some(SECTION_PALETTE[0]),
+            ..Style::default()
+        };
+        let colored_lines: Vec<&str> = colored.lines().collect();
+        assert!(colored_lines[legend_y].contains(&format!("{}■", CRITICAL_STYLE.escape_code())));
+        assert!(colored_lines[legend_y + 1].contains(&format!("{}■", design_style.escape_code())));
+        let colored_lines: Vec<String> = strip_escape_codes(&colored)
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        assert_eq!(
+            colored_lines[legend_y],
+            format!("{}■ done  ■ critical  ◆ milestone", " ".repeat(MARGIN_LEFT))
+        );
+        assert_eq!(colored_lines[legend_y + 1], lines[legend_y + 1]);
+    }
+
+    #[test]
+    fn test_critical_path_highlighting() {
+        let input = "
+Design: 01-01-2026, 5d | id design
+Backend: after design, 10d | id backend
+Frontend: after design, 3d | id frontend
+Integrate: after frontend backend, 2d | id integrate
+Release: after integrate, 18-01-2026";
+        let gantt_layout =
+            layout_with_config(&parse(input).unwrap(), &GanttLayoutConfig::default());
+
+        // Off by default
+        let plain = render(&gantt_layout);
+        assert!(!plain.contains('╔'));
+
+        let options = RenderOptions {
+            critical_path: true,
+            ..RenderOptions::default()
+        };
+        let output = render_with_options(&gantt_layout, &options);
+        let lines: Vec<&str> = output.lines().collect();
+        let task_line = |index: usize| lines[gantt_layout.task_layouts[index].y];
+        assert!(task_line(0).contains('╔'));
+        assert!(task_line(1).contains('╔'));
+        assert!(!task_line(2).contains('╔'));
+        assert!(task_line(3).contains('╔'));
+
+        let colored = render_with_options(
+            &gantt_layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                critical_path: true,
+                ..RenderOptions::default()
+            },
+        );
+        let lines: Vec<&str> = colored.lines().collect();
+        let milestone_y = gantt_layout.milestone_layouts[0].y;
+        assert!(lines[gantt_layout.task_layouts[1].y].contains(&CRITICAL_STYLE.escape_code()));
+        assert!(!lines[gantt_layout.task_layouts[2].y].contains('\x1b'));
+        assert!(lines[milestone_y].contains(&CRITICAL_STYLE.escape_code()));
+    }
+
+    #[test]
+    fn test_name_fit() {
+        let input = "\
+Database migration: 01-01-2026, 3d
+Rollout: 04-01-2026, 30d";
+        let gantt_layout = layout_with_config(
+            &parse(input).unwrap(),
+            &GanttLayoutConfig {
+                chart_width: WidthMode::Fixed(60),
+                ..GanttLayoutConfig::default()
+            },
+        );
+        let task = &gantt_layout.task_layouts[0];
+        let name_row = |name_fit| {
+            let output = render_with_options(
+                &gantt_layout,
+                &RenderOptions {
+                    name_fit,
+                    ..RenderOptions::default()
+                },
+            );
+            output
+                .lines()
+                .nth(task.y + 1)
+                .unwrap()
+                .trim_end()
+                .to_string()
+        };
+
+        assert!(name_row(NameFit::SpillRight).contains("|Database migration "));
+        assert!(name_row(NameFit::TruncateInside).contains("|Da…|"));
+        assert!(name_row(NameFit::Initials).contains("| DM|"));
+    }
+
+    #[test]
+    fn test_vertical_orientation() {
+        let input = "
+Design: 01-01-2026, 05-01-2026
+Implementation: 05-01-2026, 15-01-2026 | progress 50%
+Testing: 15-01-2026, 20-01-2026
+Bugfix: 20-01-2026, 03-02-2026
+Release: 03-02-2026, 06-02-2026";
+        let gantt_chart = parse(input).unwrap();
+        assert!(
+            layout_with_config(&gantt_chart, &GanttLayoutConfig::default())
+                .vertical
+                .is_none()
+        );
+
+        let config = GanttLayoutConfig {
+            orientation: Orientation::Vertical,
+            chart_height: 20,
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&gantt_chart, &config);
+        let output = render(&gantt_layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), gantt_layout.height);
+        for line in &lines {
+            assert_eq!(line.chars().count(), gantt_layout.width);
+        }
+        assert!(gantt_layout.task_layouts.is_empty());
+
+        let vertical = gantt_layout.vertical.as_ref().unwrap();
+        // Weekly ticks, each labelled at the start of its row
+        assert_eq!(vertical.ticks.len(), 7);
+        for tick in &vertical.ticks {
+            let label = tick.label.as_ref().unwrap();
+            let row: String = lines[tick.y].chars().skip(vertical.label_x).collect();
+            assert!(row.starts_with(&format!("{} ┤", label)));
+        }
+
+        // Bands from left to right, bars further down as time goes on
+        for pair in vertical.columns.windows(2) {
+            assert!(pair[0].x_end < pair[1].x_start);
+            assert!(pair[0].y_start < pair[1].y_start);
+        }
+        let header: String = lines[vertical.header_y]
+            .chars()
+            .skip(vertical.chart_x)
+            .collect();
+        assert!(header.starts_with("Design  Implementation  Testing"));
+
+        // The top half of the interior is filled
+        let implementation = &vertical.columns[1];
+        let interior = |y: usize| -> String {
+            lines[y]
+                .chars()
+                .skip(implementation.x_start + 1)
+                .take(implementation.x_end - implementation.x_start - 1)
+                .collect()
+        };
+        assert_eq!(interior(implementation.y_start + 1), "█".repeat(12));
+        assert_eq!(interior(implementation.y_end - 1).trim(), "");
+
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_vertical_milestone_and_today_marker() {
+        let input = "
+today marker: on
+Build: 01-01-2026, 05-01-2026
+Launch: 05-01-2026, 05-01-2026";
+        let config = GanttLayoutConfig {
+            orientation: Orientation::Vertical,
+            chart_height: 8,
+            clock: || NaiveDate::from_ymd_opt(2026, 1, 3).unwrap(),
+            ..GanttLayoutConfig::default()
+        };
+        let gantt_layout = layout_with_config(&parse(input).unwrap(), &config);
+        let output = render(&gantt_layout);
 
-    for (i, ch) in date.chars().enumerate() {
-        canvas.set_char(date_start_x + i, canvas.height - MARGIN_BOTTOM + 1, ch);
+        let expected = [
+            "",
+            "",
+            "                   Build  Launch",
+            "",
+            "      01-01-2026 ┤ ┌───┐--------",
+            "                 │ |   |",
+            "      02-01-2026 ┤ |   |--------",
+            "                 │ |   |",
+            "      03-01-2026 ┤ ━━━━━━━━━━━━━ today",
+            "                 │ |   |",
+            "      04-01-2026 ┤ |   |--------",
+            "                 │ |   |",
+            "      05-01-2026 ┤ └───┘----◆---",
+            "",
+            "",
+        ];
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines, expected);
+        assert_eq!(gantt_layout.bounding_box(), rendered_bounds(&output));
     }
 }
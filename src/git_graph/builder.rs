@@ -0,0 +1,185 @@
+use crate::git_graph::parser::{GitGraph, GitGraphConfig, GitGraphState};
+
+#[derive(Debug, Clone)]
+pub struct BuildError {
+    /// 1-based position of the failing action in the builder call chain
+    pub step: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Build error at step {}: {}", self.step, self.message)
+    }
+}
+
+/// Builds a `GitGraph` with the same rules as the text syntax. The first failing
+/// action is remembered and reported by `build`; later actions are ignored.
+#[derive(Debug)]
+pub struct GitGraphBuilder {
+    state: GitGraphState,
+    step: usize,
+    error: Option<BuildError>,
+}
+
+impl Default for GitGraphBuilder {
+    fn default() -> Self {
+        GitGraphBuilder::new()
+    }
+}
+
+impl GitGraphBuilder {
+    pub fn new() -> Self {
+        GitGraphBuilder::with_config(GitGraphConfig::default())
+    }
+
+    pub fn with_config(config: GitGraphConfig) -> Self {
+        GitGraphBuilder {
+            state: GitGraphState::with_config(config),
+            step: 0,
+            error: None,
+        }
+    }
+
+    pub fn commit(&mut self, message: &str) -> &mut Self {
+        self.apply(|state| state.commit(message.to_string()))
+    }
+
+    pub fn branch(&mut self, name: &str) -> &mut Self {
+        self.apply(|state| state.branch(name.to_string()))
+    }
+
+    pub fn checkout(&mut self, name: &str) -> &mut Self {
+        self.apply(|state| state.checkout(name.to_string()))
+    }
+
+    pub fn merge(&mut self, name: &str) -> &mut Self {
+        self.merge_all(&[name])
+    }
+
+    /// Merges several branches at once into a single octopus merge commit
+    pub fn merge_all(&mut self, names: &[&str]) -> &mut Self {
+        self.apply(|state| state.merge(names.iter().map(|name| name.to_string()).collect()))
+    }
+
+    /// Tags the head of the current branch
+    pub fn tag(&mut self, name: &str) -> &mut Self {
+        self.apply(|state| state.tag(name.to_string()))
+    }
+
+    pub fn build(&self) -> Result<GitGraph, BuildError> {
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(self.state.git_graph().clone()),
+        }
+    }
+
+    fn apply(
+        &mut self,
+        action: impl FnOnce(&mut GitGraphState) -> Result<(), String>,
+    ) -> &mut Self {
+        self.step += 1;
+        if self.error.is_none()
+            && let Err(message) = action(&mut self.state)
+        {
+            self.error = Some(BuildError {
+                step: self.step,
+                message,
+            });
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::git_graph::parser::{parse, parse_with_config};
+
+    use super::*;
+
+    #[test]
+    fn test_builder_matches_parser() {
+        let input = "\
+commit     init
+commit     core
+
+branch     dev
+commit     setup
+commit     config
+commit     refactor
+
+branch     feature-search
+commit     ui
+commit     api
+
+checkout   dev
+merge      feature-search
+commit     stabilize
+
+checkout   main
+merge      dev";
+
+        let parsed = parse(input).unwrap();
+        let built = GitGraphBuilder::new()
+            .commit("init")
+            .commit("core")
+            .branch("dev")
+            .commit("setup")
+            .commit("config")
+            .commit("refactor")
+            .branch("feature-search")
+            .commit("ui")
+            .commit("api")
+            .checkout("dev")
+            .merge("feature-search")
+            .commit("stabilize")
+            .checkout("main")
+            .merge("dev")
+            .build()
+            .unwrap();
+
+        assert_eq!(built, parsed);
+    }
+
+    #[test]
+    fn test_builder_reports_first_error() {
+        let result = GitGraphBuilder::new()
+            .commit("init")
+            .merge("dev")
+            .checkout("missing")
+            .build();
+
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.step, 2);
+        assert!(err.message.contains("Branch dev does not exist"));
+    }
+
+    #[test]
+    fn test_builder_branch_without_commits() {
+        let result = GitGraphBuilder::new().branch("dev").build();
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .message
+                .contains("Current branch (main) has no commits yet")
+        );
+    }
+
+    #[test]
+    fn test_builder_branch_from_empty_main() {
+        let config = GitGraphConfig {
+            allow_empty_base: true,
+        };
+        let parsed = parse_with_config("branch dev\ncommit setup", &config).unwrap();
+        let built = GitGraphBuilder::with_config(config)
+            .branch("dev")
+            .commit("setup")
+            .build()
+            .unwrap();
+
+        assert_eq!(built, parsed);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use crate::git_graph::parser::GitGraph;
+
+#[derive(Debug, PartialEq)]
+pub struct CommitLayout {
+    /// Index into `GitGraphLayout::lanes`
+    pub lane: usize,
+    /// The commit's global index, so commits are in the order they were made
+    pub row: usize,
+    pub message: String,
+    /// Branches merged by this commit. Empty for a regular commit.
+    pub merged_from: Vec<String>,
+    /// Shown next to the message, e.g. `(tag: v1.2.0)`
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectorKind {
+    /// From the base commit on the parent lane to the first commit of a branch
+    Branch,
+    /// From the tip of a merged branch to the merge commit
+    Merge,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConnectorLayout {
+    pub from_lane: usize,
+    pub from_row: usize,
+    pub to_lane: usize,
+    pub to_row: usize,
+    pub kind: ConnectorKind,
+}
+
+/// Positions in lanes (columns) and rows, left to the renderer to scale
+#[derive(Debug)]
+pub struct GitGraphLayout {
+    /// Branch names by lane, `main` first
+    pub lanes: Vec<String>,
+    /// Sorted by row
+    pub commit_layouts: Vec<CommitLayout>,
+    pub connector_layouts: Vec<ConnectorLayout>,
+    /// Number of lanes
+    pub width: usize,
+    /// Number of rows
+    pub height: usize,
+}
+
+pub fn layout(git_graph: &GitGraph) -> GitGraphLayout {
+    // Lanes in branch creation order, so `main` is on the left
+    let lanes: Vec<String> = git_graph
+        .branches()
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    let mut commit_layouts = Vec::new();
+    // Lane of each commit by global index, to find where connectors start
+    let mut commit_lanes = HashMap::new();
+    for (lane, name) in lanes.iter().enumerate() {
+        for commit in git_graph[name].commits() {
+            commit_lanes.insert(commit.index(), lane);
+            commit_layouts.push(CommitLayout {
+                lane,
+                row: commit.index(),
+                message: commit.message().to_string(),
+                merged_from: commit.merged_from().to_vec(),
+                tags: commit.tags().to_vec(),
+            });
+        }
+    }
+    commit_layouts.sort_by_key(|commit_layout| commit_layout.row);
+
+    let mut connector_layouts = Vec::new();
+    for (lane, name) in lanes.iter().enumerate() {
+        let branch = &git_graph[name];
+
+        // Branches from an empty base or without commits have nothing to connect
+        if let (Some(base_commit), Some(first_commit)) =
+            (branch.base_commit(), branch.commits().first())
+        {
+            connector_layouts.push(ConnectorLayout {
+                from_lane: commit_lanes[&base_commit],
+                from_row: base_commit,
+                to_lane: lane,
+                to_row: first_commit.index(),
+                kind: ConnectorKind::Branch,
+            });
+        }
+
+        for commit in branch.commits() {
+            for &source in commit.merge_sources() {
+                connector_layouts.push(ConnectorLayout {
+                    from_lane: commit_lanes[&source],
+                    from_row: source,
+                    to_lane: lane,
+                    to_row: commit.index(),
+                    kind: ConnectorKind::Merge,
+                });
+            }
+        }
+    }
+    connector_layouts.sort_by_key(|connector| (connector.to_row, connector.from_lane));
+
+    let height = commit_layouts.last().map_or(0, |commit| commit.row + 1);
+
+    GitGraphLayout {
+        width: lanes.len(),
+        height,
+        lanes,
+        commit_layouts,
+        connector_layouts,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::git_graph::parser::parse;
+
+    const PERFECT_INPUT: &str = "\
+commit     init
+commit     core
+
+branch     dev
+commit     setup
+commit     config
+commit     refactor
+
+branch     feature-search
+commit     ui
+commit     api
+
+checkout   dev
+merge      feature-search
+commit     stabilize
+
+checkout   main
+merge      dev";
+
+    #[test]
+    fn test_perfect_input() {
+        let git_graph_layout = layout(&parse(PERFECT_INPUT).unwrap());
+
+        assert_eq!(git_graph_layout.lanes, ["main", "dev", "feature-search"]);
+        assert_eq!(git_graph_layout.width, 3);
+        assert_eq!(git_graph_layout.height, 10);
+
+        let positions: Vec<(usize, usize, &str)> = git_graph_layout
+            .commit_layouts
+            .iter()
+            .map(|commit| (commit.lane, commit.row, commit.message.as_str()))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (0, 0, "init"),
+                (0, 1, "core"),
+                (1, 2, "setup"),
+                (1, 3, "config"),
+                (1, 4, "refactor"),
+                (2, 5, "ui"),
+                (2, 6, "api"),
+                (1, 7, "Merge branch feature-search into branch dev"),
+                (1, 8, "stabilize"),
+                (0, 9, "Merge branch dev into branch main"),
+            ]
+        );
+        assert_eq!(
+            git_graph_layout.commit_layouts[9].merged_from,
+            ["dev".to_string()]
+        );
+
+        let connector = |from_lane, from_row, to_lane, to_row, kind| ConnectorLayout {
+            from_lane,
+            from_row,
+            to_lane,
+            to_row,
+            kind,
+        };
+        assert_eq!(
+            git_graph_layout.connector_layouts,
+            [
+                connector(0, 1, 1, 2, ConnectorKind::Branch),
+                connector(1, 4, 2, 5, ConnectorKind::Branch),
+                connector(2, 6, 1, 7, ConnectorKind::Merge),
+                connector(1, 8, 0, 9, ConnectorKind::Merge),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branch_without_commits() {
+        let git_graph = parse("commit init\nbranch idle\ncheckout main\ncommit core").unwrap();
+        let git_graph_layout = layout(&git_graph);
+
+        assert_eq!(git_graph_layout.lanes, ["main", "idle"]);
+        assert_eq!(git_graph_layout.height, 2);
+        assert!(git_graph_layout.connector_layouts.is_empty());
+        assert!(
+            git_graph_layout
+                .commit_layouts
+                .iter()
+                .all(|commit| commit.lane == 0)
+        );
+    }
+}
@@ -1 +1,3 @@
+pub mod builder;
+pub mod layout;
 pub mod parser;
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::validation::validate_branch_name;
 
 #[derive(Debug)]
 pub struct ParseError {
@@ -12,182 +16,584 @@ impl std::fmt::Display for ParseError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Commit {
     index: usize,
     message: String,
-    merged_from: Option<String>,
+    /// Branches merged by this commit. Empty for a regular commit, several for an octopus merge.
+    merged_from: Vec<String>,
+    /// Head commit index of each branch in `merged_from` at the time of the merge
+    merge_sources: Vec<usize>,
+    /// From `tag` lines, in the order they were added
+    tags: Vec<String>,
+}
+
+impl Commit {
+    /// A regular commit, with the global `index` giving its position in the history
+    pub fn new(index: usize, message: impl Into<String>) -> Self {
+        Commit {
+            index,
+            message: message.into(),
+            merged_from: Vec::new(),
+            merge_sources: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// A merge commit of the branches `merged_from`, whose heads were `merge_sources`
+    pub fn merge(
+        index: usize,
+        message: impl Into<String>,
+        merged_from: Vec<String>,
+        merge_sources: Vec<usize>,
+    ) -> Self {
+        Commit {
+            index,
+            message: message.into(),
+            merged_from,
+            merge_sources,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn merged_from(&self) -> &[String] {
+        &self.merged_from
+    }
+
+    pub fn merge_sources(&self) -> &[usize] {
+        &self.merge_sources
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn is_merge(&self) -> bool {
+        !self.merged_from.is_empty()
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Branch {
     commits: Vec<Commit>,
     base_commit: Option<usize>,
 }
 
-pub type GitGraph = HashMap<String, Branch>;
+impl Branch {
+    pub fn new(base_commit: Option<usize>, commits: Vec<Commit>) -> Self {
+        Branch {
+            commits,
+            base_commit,
+        }
+    }
 
-fn init_git_graph() -> GitGraph {
-    let mut git_graph = HashMap::new();
+    /// In the order they were made
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
 
-    git_graph.insert(
-        String::from("main"),
-        Branch {
-            commits: Vec::new(),
-            base_commit: None,
-        },
-    );
+    /// Index of the commit the branch started from. `None` for `main`, and for branches
+    /// started from an empty `main` with `GitGraphConfig::allow_empty_base`.
+    pub fn base_commit(&self) -> Option<usize> {
+        self.base_commit
+    }
+}
 
-    git_graph
+/// Branches by name, in the order they were created. `main` comes first.
+#[derive(Debug, Clone)]
+pub struct GitGraph {
+    branches: IndexMap<String, Branch>,
 }
 
-pub fn parse(input: &str) -> Result<GitGraph, ParseError> {
-    let mut git_graph = init_git_graph();
-    let mut current_branch_name = String::from("main");
-    let mut current_index = 0;
+/// Equal when the branches are equal and were created in the same order, unlike `IndexMap`
+/// equality which ignores order
+impl PartialEq for GitGraph {
+    fn eq(&self, other: &Self) -> bool {
+        self.branches.iter().eq(other.branches.iter())
+    }
+}
 
-    for (line_number, line) in input.lines().enumerate() {
-        if line.trim().is_empty() {
-            continue;
+impl GitGraph {
+    /// Branches with their names, in creation order
+    pub fn branches(&self) -> impl Iterator<Item = (&str, &Branch)> {
+        self.branches
+            .iter()
+            .map(|(name, branch)| (name.as_str(), branch))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Branch> {
+        self.branches.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.branches.contains_key(name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Branch> {
+        self.branches.get_mut(name)
+    }
+
+    /// Adds a branch after the others. The name must be new.
+    fn push(&mut self, name: String, branch: Branch) {
+        let previous = self.branches.insert(name, branch);
+        debug_assert!(previous.is_none());
+    }
+}
+
+impl std::ops::Index<&str> for GitGraph {
+    type Output = Branch;
+
+    fn index(&self, name: &str) -> &Branch {
+        self.get(name)
+            .unwrap_or_else(|| panic!("Branch {} does not exist", name))
+    }
+}
+
+fn init_git_graph() -> GitGraph {
+    GitGraph {
+        branches: IndexMap::from([(
+            String::from("main"),
+            Branch {
+                commits: Vec::new(),
+                base_commit: None,
+            },
+        )]),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GitGraphConfig {
+    /// Let `branch` start from a branch without commits, like git does. The new branch then
+    /// starts where that branch starts, which is the root for an empty `main`.
+    pub allow_empty_base: bool,
+}
+
+/// The state machine behind the git graph syntax. Both the text parser and
+/// `GitGraphBuilder` drive it, so they enforce the same rules.
+#[derive(Debug)]
+pub(crate) struct GitGraphState {
+    git_graph: GitGraph,
+    current_branch_name: String,
+    current_index: usize,
+    config: GitGraphConfig,
+}
+
+impl GitGraphState {
+    pub(crate) fn with_config(config: GitGraphConfig) -> Self {
+        GitGraphState {
+            git_graph: init_git_graph(),
+            current_branch_name: String::from("main"),
+            current_index: 0,
+            config,
         }
+    }
 
-        let line_number = line_number + 1;
+    pub(crate) fn git_graph(&self) -> &GitGraph {
+        &self.git_graph
+    }
 
-        let (action, rest) = line.trim().split_once(' ').ok_or(ParseError {
-            line: line_number,
-            message: "Invalid syntax".to_string(),
-        })?;
-        let action = action.trim();
-        let rest = rest.trim().to_string();
+    pub(crate) fn into_git_graph(self) -> GitGraph {
+        self.git_graph
+    }
+
+    /// Commits on the current branch. An empty message is replaced with `commit #<index>`.
+    pub(crate) fn commit(&mut self, message: String) -> Result<(), String> {
+        let message = if message.is_empty() {
+            format!("commit #{}", self.current_index)
+        } else {
+            message
+        };
+        let current_branch = self
+            .git_graph
+            .get_mut(&self.current_branch_name)
+            .expect("Internal error. Current branch not found");
+
+        current_branch
+            .commits
+            .push(Commit::new(self.current_index, message));
+        self.current_index += 1;
 
-        match action {
-            "commit" => {
-                let current_branch = git_graph
-                    .get_mut(&current_branch_name)
-                    .expect("Internal error. Current branch not found");
-
-                current_branch.commits.push(Commit {
-                    index: current_index,
-                    message: rest,
-                    merged_from: None,
-                });
-                current_index += 1;
+        Ok(())
+    }
+
+    pub(crate) fn branch(&mut self, new_branch_name: String) -> Result<(), String> {
+        validate_branch(&new_branch_name)?;
+        if self.git_graph.contains(&new_branch_name) {
+            return Err(format!(
+                "Cannot create new branch. Branch {} already exists",
+                new_branch_name
+            ));
+        }
+
+        let current_branch = self
+            .git_graph
+            .get(&self.current_branch_name)
+            .expect("Internal error. Current branch not found");
+        let base_commit = match current_branch.commits.last() {
+            Some(commit) => Some(commit.index),
+            None if self.config.allow_empty_base => current_branch.base_commit,
+            None => {
+                return Err(format!(
+                    "Cannot create a new branch. Current branch ({}) has no commits yet",
+                    self.current_branch_name
+                ));
             }
-            "branch" => {
-                let new_branch_name = rest;
-
-                if git_graph.contains_key(&new_branch_name) {
-                    return Err(ParseError {
-                        line: line_number,
-                        message: format!(
-                            "Cannot create new branch. Branch {} already exists",
-                            new_branch_name
-                        ),
-                    });
-                }
+        };
 
-                let base_index = git_graph
-                    .get(&current_branch_name)
-                    .expect("Internal error. Current branch not found")
-                    .commits
-                    .last()
-                    .map(|commit| commit.index);
-
-                match base_index {
-                    Some(base_index) => {
-                        let new_branch = Branch {
-                            commits: Vec::new(),
-                            base_commit: Some(base_index),
-                        };
-                        current_branch_name = new_branch_name.clone();
-                        git_graph.insert(new_branch_name, new_branch);
-                    }
+        let new_branch = Branch {
+            commits: Vec::new(),
+            base_commit,
+        };
+        self.current_branch_name = new_branch_name.clone();
+        self.git_graph.push(new_branch_name, new_branch);
+        Ok(())
+    }
+
+    pub(crate) fn checkout(&mut self, branch_name: String) -> Result<(), String> {
+        validate_branch(&branch_name)?;
+        if self.git_graph.contains(&branch_name) {
+            self.current_branch_name = branch_name;
+            Ok(())
+        } else {
+            Err(format!("Branch {} does not exist", branch_name))
+        }
+    }
+
+    /// Merges one or more branches into the current branch with a single merge commit
+    pub(crate) fn merge(&mut self, source_branch_names: Vec<String>) -> Result<(), String> {
+        let reachable = self.reachable_commits(&self.current_branch_name);
+        let mut merge_sources = Vec::new();
+        for (i, source_branch_name) in source_branch_names.iter().enumerate() {
+            validate_branch(source_branch_name)?;
+            if *source_branch_name == self.current_branch_name {
+                return Err(format!(
+                    "Cannot merge branch {} into itself",
+                    source_branch_name
+                ));
+            }
+            if source_branch_names[..i].contains(source_branch_name) {
+                return Err(format!(
+                    "Branch {} is listed more than once in the merge",
+                    source_branch_name
+                ));
+            }
+            let source_head = match self.git_graph.get(source_branch_name) {
+                Some(source_branch) => match source_branch.commits.last() {
+                    Some(commit) => commit.index,
                     None => {
-                        return Err(ParseError {
-                            line: line_number,
-                            message: format!(
-                                "Cannot create a new branch. Current branch ({}) has no commits yet",
-                                current_branch_name
-                            ),
-                        });
+                        return Err(format!(
+                            "Cannot merge branch {} because it has no commits",
+                            source_branch_name
+                        ));
                     }
+                },
+                None => {
+                    return Err(format!("Branch {} does not exist", source_branch_name));
                 }
+            };
+            if reachable.contains(&source_head) {
+                return Err(format!(
+                    "Cannot merge branch {} into branch {}: it has no commits that {} does not already contain",
+                    source_branch_name, self.current_branch_name, self.current_branch_name
+                ));
             }
-            "checkout" => {
-                let branch_name = rest;
-
-                if git_graph.contains_key(&branch_name) {
-                    current_branch_name = branch_name;
-                } else {
-                    return Err(ParseError {
-                        line: line_number,
-                        message: format!("Branch {} does not exist", branch_name),
-                    });
-                }
-            }
-            "merge" => {
-                let target_branch_name = rest;
-
-                if target_branch_name == current_branch_name {
-                    return Err(ParseError {
-                        line: line_number,
-                        message: format!("Cannot merge branch {} into itself", target_branch_name),
-                    });
+            merge_sources.push(source_head);
+        }
+
+        let sources = match source_branch_names.split_last() {
+            Some((single, [])) => format!("branch {}", single),
+            Some((last, rest)) => format!("branches {} and {}", rest.join(", "), last),
+            None => return Err("Expected at least one branch to merge".to_string()),
+        };
+        let current_branch = self
+            .git_graph
+            .get_mut(&self.current_branch_name)
+            .expect("Internal error. Current branch not found");
+
+        current_branch.commits.push(Commit::merge(
+            self.current_index,
+            format!("Merge {} into branch {}", sources, self.current_branch_name),
+            source_branch_names,
+            merge_sources,
+        ));
+
+        self.current_index += 1;
+
+        Ok(())
+    }
+
+    /// Tags the head of the current branch. Tag names are unique across the graph.
+    pub(crate) fn tag(&mut self, tag_name: String) -> Result<(), String> {
+        validate_branch_name(&tag_name)
+            .map_err(|message| format!("Tag name '{}' {}", tag_name, message))?;
+        let tagged = self
+            .git_graph
+            .branches()
+            .flat_map(|(_, branch)| &branch.commits)
+            .any(|commit| commit.tags.contains(&tag_name));
+        if tagged {
+            return Err(format!("Tag {} already exists", tag_name));
+        }
+
+        let current_branch = self
+            .git_graph
+            .get_mut(&self.current_branch_name)
+            .expect("Internal error. Current branch not found");
+        let head = current_branch.commits.last_mut().ok_or(format!(
+            "Cannot tag branch {}: it has no commits yet",
+            self.current_branch_name
+        ))?;
+        head.tags.push(tag_name);
+
+        Ok(())
+    }
+
+    /// Collects the indices of every commit reachable from the head of
+    /// `branch_name`, following branch points back into parent branches and
+    /// merge commits into the branches they merged.
+    fn reachable_commits(&self, branch_name: &str) -> HashSet<usize> {
+        let branch_of_commit: HashMap<usize, &str> = self
+            .git_graph
+            .branches()
+            .flat_map(|(name, branch)| {
+                branch
+                    .commits
+                    .iter()
+                    .map(move |commit| (commit.index, name))
+            })
+            .collect();
+
+        let mut reachable = HashSet::new();
+        // Each entry is a branch together with the newest commit index on it
+        // that is still part of the history being walked.
+        let mut pending = vec![(branch_name, usize::MAX)];
+        while let Some((name, up_to)) = pending.pop() {
+            let Some(branch) = self.git_graph.get(name) else {
+                continue;
+            };
+            for commit in branch.commits.iter().filter(|c| c.index <= up_to) {
+                if !reachable.insert(commit.index) {
+                    continue;
                 }
-                match git_graph.get(&target_branch_name) {
-                    Some(target_branch) => {
-                        if target_branch.commits.is_empty() {
-                            return Err(ParseError {
-                                line: line_number,
-                                message: format!(
-                                    "Cannot merge branch {} because it has no commits",
-                                    target_branch_name
-                                ),
-                            });
-                        }
-                    }
-                    None => {
-                        return Err(ParseError {
-                            line: line_number,
-                            message: format!("Branch {} does not exist", target_branch_name),
-                        });
-                    }
+                for (merged_from, &source) in commit.merged_from.iter().zip(&commit.merge_sources) {
+                    pending.push((merged_from.as_str(), source));
                 }
+            }
+            if let Some(base) = branch.base_commit
+                && let Some(parent) = branch_of_commit.get(&base)
+            {
+                pending.push((parent, base));
+            }
+        }
 
-                let current_branch = git_graph
-                    .get_mut(&current_branch_name)
-                    .expect("Internal error. Current branch not found");
+        reachable
+    }
+}
+
+fn validate_branch(name: &str) -> Result<(), String> {
+    validate_branch_name(name).map_err(|message| format!("Branch name '{}' {}", name, message))
+}
+
+/// The newest commit of the graph, the one with the highest index. `None` without commits.
+pub fn head_commit(git_graph: &GitGraph) -> Option<&Commit> {
+    git_graph
+        .branches()
+        .flat_map(|(_, branch)| &branch.commits)
+        .max_by_key(|commit| commit.index)
+}
+
+/// Writes the graph back as text that parses to the same graph
+impl std::fmt::Display for GitGraph {
+    /// Replays the commits in the order of their global index. Branches are created in their
+    /// original order, each as early as possible: after its base commit and after the branch
+    /// created before it. The branch it is created from still has the base commit as its head
+    /// then, as it did when the branch was created originally.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut commits: Vec<(&str, &Commit)> = self
+            .branches()
+            .flat_map(|(name, branch)| branch.commits.iter().map(move |commit| (name, commit)))
+            .collect();
+        commits.sort_by_key(|(_, commit)| commit.index);
 
-                current_branch.commits.push(Commit {
-                    index: current_index,
-                    message: format!(
-                        "Merge branch {} into branch {}",
-                        target_branch_name, current_branch_name
-                    ),
-                    merged_from: Some(target_branch_name),
-                });
+        // Each branch with the commit it is created after, `None` for before the first commit
+        let mut created_after = None;
+        let mut pending_branches = self
+            .branches()
+            .skip(1)
+            .map(|(name, branch)| {
+                created_after = created_after.max(branch.base_commit);
+                (name, branch.base_commit, created_after)
+            })
+            .peekable();
 
-                current_index += 1;
+        let mut replay = Replay {
+            heads: vec![("main", None, false)],
+            current_branch_name: "main",
+        };
+        let mut written = None;
+        loop {
+            while let Some((name, base_commit, _)) =
+                pending_branches.next_if(|&(_, _, after)| after == written)
+            {
+                replay.branch(f, name, base_commit)?;
             }
-            _ => {
-                return Err(ParseError {
-                    line: line_number,
-                    message: "Invalid syntax: expected '<action> <name>'".to_string(),
-                });
+            let Some((branch_name, commit)) = commits.get(written.map_or(0, |index| index + 1))
+            else {
+                break;
+            };
+            replay.commit(f, branch_name, commit)?;
+            written = Some(commit.index);
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a graph being written back as text
+struct Replay<'a> {
+    /// Each branch created so far with its head, which is its base commit until it has commits
+    /// of its own, and whether it has any
+    heads: Vec<(&'a str, Option<usize>, bool)>,
+    current_branch_name: &'a str,
+}
+
+impl<'a> Replay<'a> {
+    fn checkout(&mut self, f: &mut std::fmt::Formatter<'_>, name: &'a str) -> std::fmt::Result {
+        if self.current_branch_name != name {
+            writeln!(f, "checkout {}", name)?;
+            self.current_branch_name = name;
+        }
+        Ok(())
+    }
+
+    fn commit(
+        &mut self,
+        f: &mut std::fmt::Formatter<'_>,
+        branch_name: &'a str,
+        commit: &Commit,
+    ) -> std::fmt::Result {
+        self.checkout(f, branch_name)?;
+        if commit.merged_from.is_empty() {
+            writeln!(f, "commit {}", commit.message)?;
+        } else {
+            writeln!(f, "merge {}", commit.merged_from.join(" "))?;
+        }
+        for tag in &commit.tags {
+            writeln!(f, "tag {}", tag)?;
+        }
+        for head in &mut self.heads {
+            if head.0 == branch_name {
+                *head = (branch_name, Some(commit.index), true);
             }
         }
+        Ok(())
+    }
+
+    /// Creates `name` from a branch whose head is `base_commit`. Branches with commits of their
+    /// own come first, as branching from an empty branch needs `allow_empty_base`.
+    fn branch(
+        &mut self,
+        f: &mut std::fmt::Formatter<'_>,
+        name: &'a str,
+        base_commit: Option<usize>,
+    ) -> std::fmt::Result {
+        let mut sources = self
+            .heads
+            .iter()
+            .filter(|&&(_, head, _)| head == base_commit);
+        let source = sources
+            .clone()
+            .find(|&&(_, _, has_commits)| has_commits)
+            .or_else(|| sources.next())
+            .map_or(self.current_branch_name, |&(source, _, _)| source);
+        self.checkout(f, source)?;
+        writeln!(f, "branch {}", name)?;
+        self.current_branch_name = name;
+        self.heads.push((name, base_commit, false));
+        Ok(())
+    }
+}
+
+/// Parses git graphs with options set once, for reuse across inputs
+#[derive(Debug, Clone, Default)]
+pub struct GitGraphParser {
+    pub config: GitGraphConfig,
+}
+
+impl GitGraphParser {
+    pub fn new() -> Self {
+        GitGraphParser::default()
+    }
+
+    pub fn with_config(config: GitGraphConfig) -> Self {
+        GitGraphParser { config }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<GitGraph, ParseError> {
+        parse_with_config(input, &self.config)
+    }
+}
+
+pub fn parse(input: &str) -> Result<GitGraph, ParseError> {
+    parse_with_config(input, &GitGraphConfig::default())
+}
+
+pub fn parse_with_config(input: &str, config: &GitGraphConfig) -> Result<GitGraph, ParseError> {
+    let mut state = GitGraphState::with_config(config.clone());
+
+    for (line_number, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line_number = line_number + 1;
+
+        // Only `commit` works without an argument
+        let (action, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let action = action.trim();
+        let rest = rest.trim().to_string();
+
+        let result = match action {
+            "branch" | "checkout" | "merge" | "tag" if rest.is_empty() => {
+                Err(format!("'{}' requires a name", action))
+            }
+            "commit" => state.commit(rest),
+            "branch" => state.branch(rest),
+            "checkout" => state.checkout(rest),
+            "merge" => state.merge(rest.split_whitespace().map(str::to_string).collect()),
+            "tag" => state.tag(rest),
+            _ => Err("Invalid syntax: expected '<action> <name>'".to_string()),
+        };
+
+        result.map_err(|message| ParseError {
+            line: line_number,
+            message,
+        })?;
     }
 
-    Ok(git_graph)
+    Ok(state.into_git_graph())
 }
 
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
+
     use super::*;
 
-    #[test]
-    fn test_perfect_input() {
-        let input = "\
+    const PERFECT_INPUT: &str = "\
 commit     init
 commit     core
 
@@ -207,84 +613,448 @@ commit     stabilize
 checkout   main
 merge      dev";
 
-        let git_graph = parse(input).unwrap();
-        for branch in vec!["main", "dev", "feature-search"] {
-            assert!(git_graph.contains_key(branch));
+    #[test]
+    fn test_perfect_input() {
+        let git_graph = parse(PERFECT_INPUT).unwrap();
+        for branch in ["main", "dev", "feature-search"] {
+            assert!(git_graph.contains(branch));
         }
 
-        let expected_main_branch = Branch {
-            base_commit: None,
-            commits: vec![
-                Commit {
-                    index: 0,
-                    message: "init".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 1,
-                    message: "core".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 9,
-                    message: "Merge branch dev into branch main".to_string(),
-                    merged_from: Some("dev".to_string()),
-                },
+        let expected_main_branch = Branch::new(
+            None,
+            vec![
+                Commit::new(0, "init"),
+                Commit::new(1, "core"),
+                Commit::merge(
+                    9,
+                    "Merge branch dev into branch main",
+                    vec!["dev".to_string()],
+                    vec![8],
+                ),
             ],
-        };
+        );
         assert_eq!(git_graph.get("main").unwrap(), &expected_main_branch);
 
-        let expected_dev_branch = Branch {
-            commits: vec![
-                Commit {
-                    index: 2,
-                    message: "setup".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 3,
-                    message: "config".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 4,
-                    message: "refactor".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 7,
-                    message: "Merge branch feature-search into branch dev".to_string(),
-                    merged_from: Some("feature-search".to_string()),
-                },
-                Commit {
-                    index: 8,
-                    message: "stabilize".to_string(),
-                    merged_from: None,
-                },
+        let expected_dev_branch = Branch::new(
+            Some(1),
+            vec![
+                Commit::new(2, "setup"),
+                Commit::new(3, "config"),
+                Commit::new(4, "refactor"),
+                Commit::merge(
+                    7,
+                    "Merge branch feature-search into branch dev",
+                    vec!["feature-search".to_string()],
+                    vec![6],
+                ),
+                Commit::new(8, "stabilize"),
             ],
-            base_commit: Some(1),
-        };
+        );
         assert_eq!(git_graph.get("dev").unwrap(), &expected_dev_branch);
 
-        let expected_featute_search_branch = Branch {
-            commits: vec![
-                Commit {
-                    index: 5,
-                    message: "ui".to_string(),
-                    merged_from: None,
-                },
-                Commit {
-                    index: 6,
-                    message: "api".to_string(),
-                    merged_from: None,
-                },
-            ],
-            base_commit: Some(4),
+        let feature_search = git_graph.get("feature-search").unwrap();
+        assert_eq!(feature_search.base_commit(), Some(4));
+        let commits: Vec<(usize, &str)> = feature_search
+            .commits()
+            .iter()
+            .map(|commit| (commit.index(), commit.message()))
+            .collect();
+        assert_eq!(commits, [(5, "ui"), (6, "api")]);
+        assert!(!feature_search.commits()[0].is_merge());
+    }
+
+    #[test]
+    fn test_merge_fresh_branch_into_parent() {
+        let input = "\
+commit     init
+branch     dev
+checkout   main
+merge      dev";
+
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.line, 4);
+    }
+
+    #[test]
+    fn test_merge_ancestor_is_rejected() {
+        let input = "\
+commit     init
+commit     core
+branch     dev
+merge      main";
+
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.line, 4);
+        assert_eq!(
+            error.message,
+            "Cannot merge branch main into branch dev: it has no commits that dev does not already contain"
+        );
+    }
+
+    #[test]
+    fn test_repeated_merge_is_rejected() {
+        let input = "\
+commit     init
+branch     dev
+commit     setup
+checkout   main
+merge      dev
+merge      dev";
+
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.line, 6);
+    }
+
+    #[test]
+    fn test_merge_after_new_commits_is_allowed() {
+        let input = "\
+commit     init
+branch     dev
+commit     setup
+checkout   main
+merge      dev
+checkout   dev
+commit     fix
+checkout   main
+merge      dev
+checkout   dev
+merge      main";
+
+        assert!(parse(input).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_branch_names() {
+        let error = parse("commit init\nbranch with spaces").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(
+            error.message,
+            "Branch name 'with spaces' cannot contain whitespace"
+        );
+
+        let error = parse("commit init\ncheckout main and more").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("cannot contain whitespace"));
+
+        // The parser reports a missing name itself, the builder reaches the name check
+        let mut state = GitGraphState::with_config(GitGraphConfig::default());
+        assert_eq!(
+            state.branch(String::new()).unwrap_err(),
+            "Branch name '' is empty"
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        let input = "\
+commit     init
+tag        v1.0.0
+branch     dev
+commit     fix
+checkout   main
+tag        v1.0.1
+tag        stable";
+
+        let git_graph = parse(input).unwrap();
+        assert_eq!(
+            git_graph["main"].commits()[0].tags(),
+            ["v1.0.0", "v1.0.1", "stable"]
+        );
+        assert!(git_graph["dev"].commits()[0].tags().is_empty());
+        assert_eq!(parse(&git_graph.to_string()).unwrap(), git_graph);
+
+        let error = parse("tag v1.0.0").unwrap_err();
+        assert_eq!(error.line, 1);
+        assert_eq!(
+            error.message,
+            "Cannot tag branch main: it has no commits yet"
+        );
+
+        let error = parse("commit init\ntag v1\ncommit fix\ntag v1").unwrap_err();
+        assert_eq!(error.line, 4);
+        assert_eq!(error.message, "Tag v1 already exists");
+    }
+
+    #[test]
+    fn test_actions_without_argument() {
+        let git_graph = parse("commit\ncommit init\ncommit").unwrap();
+        let messages: Vec<&str> = git_graph["main"]
+            .commits()
+            .iter()
+            .map(Commit::message)
+            .collect();
+        assert_eq!(messages, ["commit #0", "init", "commit #2"]);
+
+        for action in ["branch", "checkout", "merge", "tag"] {
+            let error = parse(&format!("commit init\n{}  ", action)).unwrap_err();
+            assert_eq!(error.line, 2);
+            assert_eq!(error.message, format!("'{}' requires a name", action));
+        }
+    }
+
+    #[test]
+    fn test_octopus_merge() {
+        let input = "\
+commit     init
+branch     feature-a
+commit     a
+checkout   main
+branch     feature-b
+commit     b
+checkout   main
+merge      feature-a   feature-b";
+
+        let git_graph = parse(input).unwrap();
+        let merge = git_graph["main"].commits().last().unwrap();
+        assert_eq!(merge.merged_from(), ["feature-a", "feature-b"]);
+        assert_eq!(merge.merge_sources(), [1, 2]);
+        assert_eq!(
+            merge.message(),
+            "Merge branches feature-a and feature-b into branch main"
+        );
+        assert_eq!(git_graph["main"].commits().len(), 2);
+
+        for (merge_line, message) in [
+            ("merge feature-a missing", "Branch missing does not exist"),
+            (
+                "merge feature-a main",
+                "Cannot merge branch main into itself",
+            ),
+            ("merge feature-a feature-a", "listed more than once"),
+        ] {
+            let input =
+                format!("commit init\nbranch feature-a\ncommit a\ncheckout main\n{merge_line}");
+            let error = parse(&input).unwrap_err();
+            assert_eq!(error.line, 5);
+            assert!(error.message.contains(message), "{}", error.message);
+        }
+    }
+
+    #[test]
+    fn test_git_graph_parser_reuse() {
+        let parser = GitGraphParser::with_config(GitGraphConfig {
+            allow_empty_base: true,
+        });
+
+        let git_graph = parser.parse("branch dev\ncommit setup").unwrap();
+        assert_eq!(git_graph["dev"].base_commit(), None);
+        let git_graph = parser.parse("branch feature\ncommit search").unwrap();
+        assert_eq!(git_graph["feature"].commits()[0].index(), 0);
+        assert!(GitGraphParser::new().parse("branch dev").is_err());
+    }
+
+    #[test]
+    fn test_branch_from_empty_main() {
+        let input = "\
+branch     dev
+commit     setup
+checkout   main
+commit     init
+merge      dev";
+
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let config = GitGraphConfig {
+            allow_empty_base: true,
         };
+        let git_graph = parse_with_config(input, &config).unwrap();
+        assert_eq!(git_graph["dev"].base_commit(), None);
+        assert_eq!(git_graph["dev"].commits()[0].message(), "setup");
+        let merge = git_graph["main"].commits().last().unwrap();
+        assert_eq!(merge.merged_from(), ["dev"]);
+        assert_eq!(merge.merge_sources(), [0]);
 
+        // A branch from an empty branch starts where that branch starts
+        let input = "\
+commit     init
+branch     dev
+branch     feature
+commit     search";
+        let git_graph = parse_with_config(input, &config).unwrap();
+        assert_eq!(git_graph["feature"].base_commit(), Some(0));
+    }
+
+    #[test]
+    fn test_branches_in_creation_order() {
+        let input = "\
+commit     init
+branch     zeta
+commit     z
+checkout   main
+branch     alpha
+checkout   main
+commit     core
+branch     mid
+commit     m";
+
+        // The same order on every parse, unlike the random order of a hash map
+        for _ in 0..10 {
+            let git_graph = parse(input).unwrap();
+            let names: Vec<&str> = git_graph.branches().map(|(name, _)| name).collect();
+            assert_eq!(names, ["main", "zeta", "alpha", "mid"]);
+        }
+
+        // Recreated in the same order when written back
+        let git_graph = parse(input).unwrap();
+        let text = git_graph.to_string();
+        assert_eq!(parse(&text).unwrap(), git_graph, "written as:\n{}", text);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let git_graph = parse(PERFECT_INPUT).unwrap();
+        let text = git_graph.to_string();
         assert_eq!(
-            git_graph.get("feature-search").unwrap(),
-            &expected_featute_search_branch
+            text,
+            "\
+commit init
+commit core
+branch dev
+commit setup
+commit config
+commit refactor
+branch feature-search
+commit ui
+commit api
+checkout dev
+merge feature-search
+commit stabilize
+checkout main
+merge dev
+"
         );
+        assert_eq!(parse(&text).unwrap(), git_graph);
+
+        // Branches off the same commit, one of them without commits
+        let input =
+            "commit a\nbranch x\ncheckout main\nbranch y\ncommit b\ncheckout main\ncommit c";
+        let git_graph = parse(input).unwrap();
+        assert_eq!(parse(&git_graph.to_string()).unwrap(), git_graph);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Action {
+        Commit(String),
+        Branch(String),
+        /// Index into the branches created so far, wrapping around
+        Checkout(prop::sample::Index),
+        Merge(prop::sample::Index),
+        Tag(String),
+    }
+
+    fn action() -> impl Strategy<Value = Action> {
+        prop_oneof![
+            3 => "[A-Za-z][A-Za-z0-9 ]{0,12}[A-Za-z0-9]".prop_map(Action::Commit),
+            1 => "[a-z][a-z0-9-]{0,8}".prop_map(Action::Branch),
+            1 => any::<prop::sample::Index>().prop_map(Action::Checkout),
+            1 => any::<prop::sample::Index>().prop_map(Action::Merge),
+            1 => "v[0-9]\\.[0-9]".prop_map(Action::Tag),
+        ]
+    }
+
+    /// Writes the actions as input, dropping the ones the parser would reject. Also returns the
+    /// graph the input should parse to and the index of its head commit, tracked here rather than
+    /// through `GitGraphState` so the parser is not checked against itself.
+    fn to_input(actions: &[Action]) -> (String, GitGraph, Option<usize>) {
+        let mut expected = init_git_graph();
+        // Commit indices reachable from the head of each branch, by branch position
+        let mut reachable = vec![HashSet::new()];
+        let mut tags = HashSet::new();
+        let mut current = 0;
+        let mut next_index = 0;
+        let mut input = String::new();
+        for action in actions {
+            let branch_names: Vec<String> = expected.branches.keys().cloned().collect();
+            let head = expected.branches[current]
+                .commits
+                .last()
+                .map(|commit| commit.index);
+            let line = match action {
+                Action::Commit(message) => {
+                    let commit = Commit::new(next_index, message.clone());
+                    expected.branches[current].commits.push(commit);
+                    reachable[current].insert(next_index);
+                    next_index += 1;
+                    format!("commit {}", message)
+                }
+                Action::Branch(name) => {
+                    if branch_names.contains(name) || head.is_none() {
+                        continue;
+                    }
+                    reachable.push(reachable[current].clone());
+                    expected.push(name.clone(), Branch::new(head, Vec::new()));
+                    current = branch_names.len();
+                    format!("branch {}", name)
+                }
+                Action::Checkout(index) => {
+                    current = index.index(branch_names.len());
+                    format!("checkout {}", branch_names[current])
+                }
+                Action::Merge(index) => {
+                    let source = index.index(branch_names.len());
+                    let source_head = expected.branches[source]
+                        .commits
+                        .last()
+                        .map(|commit| commit.index);
+                    let Some(source_head) = source_head else {
+                        continue;
+                    };
+                    if source == current || reachable[current].contains(&source_head) {
+                        continue;
+                    }
+                    let merged = reachable[source].clone();
+                    reachable[current].extend(merged);
+                    reachable[current].insert(next_index);
+                    let (name, into) = (&branch_names[source], &branch_names[current]);
+                    expected.branches[current].commits.push(Commit::merge(
+                        next_index,
+                        format!("Merge branch {} into branch {}", name, into),
+                        vec![name.clone()],
+                        vec![source_head],
+                    ));
+                    next_index += 1;
+                    format!("merge {}", name)
+                }
+                Action::Tag(name) => {
+                    if head.is_none() || !tags.insert(name.clone()) {
+                        continue;
+                    }
+                    let commits = &mut expected.branches[current].commits;
+                    commits.last_mut().unwrap().tags.push(name.clone());
+                    format!("tag {}", name)
+                }
+            };
+            input.push_str(&line);
+            input.push('\n');
+        }
+        (input, expected, next_index.checked_sub(1))
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_does_not_panic(
+            input in "((commit|branch|checkout|merge)? [a-z -]{0,8}\n){0,20}"
+        ) {
+            let _ = parse(&input);
+        }
+
+        #[test]
+        fn test_valid_graphs(actions in proptest::collection::vec(action(), 0..40)) {
+            let (input, expected, head) = to_input(&actions);
+            let git_graph = parse(&input).unwrap();
+
+            prop_assert_eq!(&git_graph, &expected, "parsed from:\n{}", input);
+            prop_assert_eq!(head_commit(&git_graph).map(Commit::index), head);
+        }
+
+        #[test]
+        fn test_generated_display_round_trip(actions in proptest::collection::vec(action(), 0..40)) {
+            let (input, _, _) = to_input(&actions);
+            let git_graph = parse(&input).unwrap();
+
+            let text = git_graph.to_string();
+            prop_assert_eq!(parse(&text).unwrap(), git_graph, "written as:\n{}", text);
+        }
     }
 }
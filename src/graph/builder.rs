@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use crate::graph::parser::{Edge, Graph, Node, validate_node};
+
+#[derive(Debug)]
+pub struct BuildError {
+    pub message: String,
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Build error: {}", self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    nodes: HashSet<Node>,
+    edges: Vec<Edge>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        GraphBuilder::default()
+    }
+
+    pub fn add_node(&mut self, name: &str) -> &mut Self {
+        self.nodes.insert(name.to_string());
+        self
+    }
+
+    /// Adds an edge, creating `from` and `to` if they do not exist yet.
+    pub fn add_edge(&mut self, from: &str, to: &str) -> &mut Self {
+        self.push_edge(from, to, None)
+    }
+
+    pub fn add_edge_with_label(&mut self, from: &str, to: &str, label: &str) -> &mut Self {
+        self.push_edge(from, to, Some(label.to_string()))
+    }
+
+    pub fn build(&self) -> Result<Graph, BuildError> {
+        for node in &self.nodes {
+            validate_node(0, node).map_err(|err| BuildError {
+                message: format!("Invalid node '{}': {}", node, err.message),
+            })?;
+        }
+
+        Ok(Graph {
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
+            clusters: Vec::new(),
+        })
+    }
+
+    fn push_edge(&mut self, from: &str, to: &str, label: Option<String>) -> &mut Self {
+        self.add_node(from);
+        self.add_node(to);
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            label,
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn test_builder_matches_parser() {
+        let input = "\
+cpu -> control-unit
+cpu -> alu
+control-unit -> decoder
+alu -> registers
+memory -> bus";
+
+        let parsed = parse(input).unwrap();
+        let built = GraphBuilder::new()
+            .add_edge("cpu", "control-unit")
+            .add_edge("cpu", "alu")
+            .add_edge("control-unit", "decoder")
+            .add_edge("alu", "registers")
+            .add_edge("memory", "bus")
+            .build()
+            .unwrap();
+
+        assert_eq!(built.nodes, parsed.nodes);
+        assert_eq!(built.edges, parsed.edges);
+    }
+
+    #[test]
+    fn test_isolated_node_and_label() {
+        let graph = GraphBuilder::new()
+            .add_node("cache")
+            .add_edge_with_label("cpu", "alu", "compute")
+            .build()
+            .unwrap();
+
+        assert_eq!(graph.nodes.len(), 3);
+        assert!(graph.nodes.contains("cache"));
+        assert_eq!(
+            graph.edges,
+            vec![Edge {
+                from: "cpu".to_string(),
+                to: "alu".to_string(),
+                label: Some("compute".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_node_name_too_long() {
+        let long_name = "A".repeat(81);
+        let result = GraphBuilder::new().add_edge("cpu", &long_name).build();
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .message
+                .contains("Node name is too long (max 80 characters)")
+        );
+    }
+
+    #[test]
+    fn test_node_name_with_new_line() {
+        let result = GraphBuilder::new().add_edge("cpu", "al\nu").build();
+
+        assert!(
+            result
+                .unwrap_err()
+                .message
+                .contains("Node name cannot contain a new line")
+        );
+    }
+}
@@ -1,36 +1,121 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::graph::parser::{Graph, Node};
 
-
-fn build_adjacency_graph(graph: &Graph) -> HashMap<String, Vec<String>> {
+pub fn build_adjacency_graph(graph: &Graph) -> HashMap<String, Vec<String>> {
     let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
 
     for node in &graph.nodes {
-        adjacency.entry(node.clone()).or_insert_with(Vec::new);
+        adjacency.entry(node.clone()).or_default();
     }
 
     for edge in &graph.edges {
         adjacency
             .entry(edge.from.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(edge.to.clone());
     }
 
     adjacency
 }
 
-fn assign_ranks(graph: &Graph, adjacency: &HashMap<Node, Vec<Node>>) -> HashMap<Node, usize> {
-    let mut ranks = HashMap::new();
+/// How nodes are assigned to ranks (layers)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RankingStrategy {
+    /// A node's rank is one more than the highest rank of its predecessors, so every edge
+    /// points to a higher rank and edges between neighbours span exactly one rank
+    #[default]
+    LongestPath,
+    /// A node's rank is its BFS distance from the nearest source. Nodes sit as close to the
+    /// top as possible, at the cost of edges that point within a rank or upwards.
+    ShortestPath,
+}
 
-    let mut in_degrees = HashMap::new();
-    for node in &graph.nodes {
-        in_degrees.insert(node.clone(), 0);
+#[derive(Debug, Clone, Default)]
+pub struct GraphLayoutOptions {
+    pub ranking: RankingStrategy,
+}
+
+impl GraphLayoutOptions {
+    pub fn new() -> Self {
+        GraphLayoutOptions::default()
     }
-    for edge in &graph.edges {
-        *in_degrees.get_mut(&edge.to).unwrap() += 1;
+
+    pub fn ranking(mut self, ranking: RankingStrategy) -> Self {
+        self.ranking = ranking;
+        self
+    }
+}
+
+/// Ranks nodes by longest path. Nodes on cycles are left unranked, but a self-edge does not
+/// count as a cycle.
+pub fn assign_ranks(graph: &Graph, adjacency: &HashMap<Node, Vec<Node>>) -> HashMap<Node, usize> {
+    assign_ranks_with_options(graph, adjacency, &GraphLayoutOptions::default())
+}
+
+pub fn assign_ranks_with_options(
+    graph: &Graph,
+    adjacency: &HashMap<Node, Vec<Node>>,
+    options: &GraphLayoutOptions,
+) -> HashMap<Node, usize> {
+    match options.ranking {
+        RankingStrategy::LongestPath => longest_path_ranks(graph, adjacency),
+        RankingStrategy::ShortestPath => shortest_path_ranks(graph, adjacency),
+    }
+}
+
+/// Number of incoming edges of each node. A self-edge would keep its node's in-degree from ever
+/// reaching zero, so it is not counted.
+fn in_degrees(graph: &Graph) -> HashMap<Node, usize> {
+    let mut in_degrees: HashMap<Node, usize> =
+        graph.nodes.iter().map(|node| (node.clone(), 0)).collect();
+    for edge in graph.edges.iter().filter(|edge| edge.from != edge.to) {
+        if let Some(in_degree) = in_degrees.get_mut(&edge.to) {
+            *in_degree += 1;
+        }
     }
+    in_degrees
+}
+
+/// The nodes without incoming edges, sorted by name. These are the entry points of the graph,
+/// ranked 0 by both strategies even when other parts of the graph form cycles. Self-edges do
+/// not count. Empty when every node is on or behind a cycle with no way in, as in `a -> b`,
+/// `b -> a`.
+pub fn sources(graph: &Graph) -> Vec<Node> {
+    let mut sources: Vec<Node> = in_degrees(graph)
+        .into_iter()
+        .filter(|&(_, in_degree)| in_degree == 0)
+        .map(|(node, _)| node)
+        .collect();
+    sources.sort();
+    sources
+}
+
+/// The nodes without outgoing edges, sorted by name. Self-edges do not count. Empty when every
+/// node leads into a cycle.
+pub fn sinks(graph: &Graph) -> Vec<Node> {
+    let origins: HashSet<&Node> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from != edge.to)
+        .map(|edge| &edge.from)
+        .collect();
+    let mut sinks: Vec<Node> = graph
+        .nodes
+        .iter()
+        .filter(|node| !origins.contains(node))
+        .cloned()
+        .collect();
+    sinks.sort();
+    sinks
+}
 
+/// Kahn's topological sort, layer by layer: a node is ranked in the layer where its last
+/// predecessor has been ranked, which is its longest path from a source
+fn longest_path_ranks(graph: &Graph, adjacency: &HashMap<Node, Vec<Node>>) -> HashMap<Node, usize> {
+    let mut ranks = HashMap::new();
+
+    let mut in_degrees = in_degrees(graph);
     let mut queue = VecDeque::new();
     for (node, in_degree) in &in_degrees {
         if *in_degree == 0 {
@@ -40,13 +125,16 @@ fn assign_ranks(graph: &Graph, adjacency: &HashMap<Node, Vec<Node>>) -> HashMap<
 
     let mut current_rank = 0;
     while !queue.is_empty() {
-        let current_layer_size = queue.len();
+        let current_layer: Vec<Node> = queue.drain(..).collect();
 
-        for _ in 0..current_layer_size {
-            let node = queue.pop_front().unwrap();
-
-            for neighbor in adjacency.get(&node).unwrap() {
-                let degree = in_degrees.get_mut(neighbor).unwrap();
+        for node in current_layer {
+            for neighbor in adjacency.get(&node).into_iter().flatten() {
+                if *neighbor == node {
+                    continue;
+                }
+                let Some(degree) = in_degrees.get_mut(neighbor) else {
+                    continue;
+                };
                 *degree -= 1;
                 if *degree == 0 {
                     queue.push_back(neighbor.clone());
@@ -61,3 +149,185 @@ fn assign_ranks(graph: &Graph, adjacency: &HashMap<Node, Vec<Node>>) -> HashMap<
 
     ranks
 }
+
+/// Multi-source BFS from the nodes without incoming edges. Nodes only reachable through a
+/// cycle with no source are left unranked.
+fn shortest_path_ranks(
+    graph: &Graph,
+    adjacency: &HashMap<Node, Vec<Node>>,
+) -> HashMap<Node, usize> {
+    let targets: HashSet<&Node> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from != edge.to)
+        .map(|edge| &edge.to)
+        .collect();
+
+    let mut ranks = HashMap::new();
+    let mut queue = VecDeque::new();
+    for node in &graph.nodes {
+        if !targets.contains(node) {
+            ranks.insert(node.clone(), 0);
+            queue.push_back(node.clone());
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let rank = ranks[&node];
+        for neighbor in adjacency.get(&node).into_iter().flatten() {
+            if !ranks.contains_key(neighbor) {
+                ranks.insert(neighbor.clone(), rank + 1);
+                queue.push_back(neighbor.clone());
+            }
+        }
+    }
+
+    ranks
+}
+
+/// Groups nodes by rank. Within a rank, members of the same cluster are kept next to each
+/// other (in cluster order) and unclustered nodes come last.
+pub fn order_nodes(graph: &Graph, ranks: &HashMap<Node, usize>) -> Vec<Vec<Node>> {
+    let rank_count = ranks.values().max().map_or(0, |max_rank| max_rank + 1);
+    let mut layers: Vec<Vec<Node>> = vec![Vec::new(); rank_count];
+
+    for (node, &rank) in ranks {
+        layers[rank].push(node.clone());
+    }
+
+    for layer in &mut layers {
+        layer.sort_by_cached_key(|node| {
+            (
+                graph.cluster_of(node).unwrap_or(graph.clusters.len()),
+                node.clone(),
+            )
+        });
+    }
+
+    layers
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn test_order_nodes_keeps_clusters_together() {
+        let input = "\
+root -> a
+root -> m
+root -> z
+root -> b
+subgraph Left
+    a -> leaf
+    z -> leaf
+end";
+        let graph = parse(input).unwrap();
+        let adjacency = build_adjacency_graph(&graph);
+        let ranks = assign_ranks(&graph, &adjacency);
+
+        let layers = order_nodes(&graph, &ranks);
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[0], vec!["root".to_string()]);
+        assert_eq!(
+            layers[1],
+            vec![
+                "a".to_string(),
+                "z".to_string(),
+                "b".to_string(),
+                "m".to_string()
+            ]
+        );
+        assert_eq!(layers[2], vec!["leaf".to_string()]);
+    }
+
+    #[test]
+    fn test_ranking_strategies_on_uneven_diamond() {
+        // Both arms of the diamond end in `end`, one arm is two edges longer
+        let input = "\
+start -> short
+short -> end
+start -> long1
+long1 -> long2
+long2 -> long3
+long3 -> end";
+        let graph = parse(input).unwrap();
+        let adjacency = build_adjacency_graph(&graph);
+
+        let longest_path = assign_ranks(&graph, &adjacency);
+        let shortest_path = assign_ranks_with_options(
+            &graph,
+            &adjacency,
+            &GraphLayoutOptions::new().ranking(RankingStrategy::ShortestPath),
+        );
+        assert_eq!(
+            GraphLayoutOptions::new().ranking,
+            RankingStrategy::LongestPath
+        );
+
+        for ranks in [&longest_path, &shortest_path] {
+            assert_eq!(ranks["start"], 0);
+            assert_eq!(ranks["short"], 1);
+            assert_eq!(ranks["long3"], 3);
+        }
+        // Longest path keeps every edge pointing down: `end` waits for the long arm
+        assert_eq!(longest_path["end"], 4);
+        // BFS puts `end` right below `short`, so `long3 -> end` points upwards
+        assert_eq!(shortest_path["end"], 2);
+    }
+
+    #[test]
+    fn test_self_loop() {
+        let input = "\
+start -> retry
+retry -> retry
+retry -> done";
+        let graph = parse(input).unwrap();
+        let adjacency = build_adjacency_graph(&graph);
+
+        for strategy in [RankingStrategy::LongestPath, RankingStrategy::ShortestPath] {
+            let ranks = assign_ranks_with_options(
+                &graph,
+                &adjacency,
+                &GraphLayoutOptions::new().ranking(strategy),
+            );
+            assert_eq!(ranks.len(), 3);
+            assert_eq!(ranks["start"], 0);
+            assert_eq!(ranks["retry"], 1);
+            assert_eq!(ranks["done"], 2);
+        }
+        assert!(
+            graph
+                .edges
+                .iter()
+                .any(|edge| edge.from == "retry" && edge.to == "retry")
+        );
+    }
+
+    #[test]
+    fn test_sources_and_sinks() {
+        let input = "\
+build -> test
+lint -> test
+test -> deploy
+test -> report
+deploy -> deploy";
+        let graph = parse(input).unwrap();
+        assert_eq!(sources(&graph), ["build", "lint"]);
+        // The self-edge of `deploy` does not keep it from being a sink
+        assert_eq!(sinks(&graph), ["deploy", "report"]);
+
+        // A source into a cycle is still ranked 0, and the cycle has no sink
+        let graph = parse("start -> a\na -> b\nb -> a").unwrap();
+        assert_eq!(sources(&graph), ["start"]);
+        assert!(sinks(&graph).is_empty());
+        let ranks = assign_ranks(&graph, &build_adjacency_graph(&graph));
+        assert_eq!(ranks.get("start"), Some(&0));
+
+        let graph = parse("a -> b\nb -> c\nc -> a").unwrap();
+        assert!(sources(&graph).is_empty());
+        assert!(sinks(&graph).is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod builder;
 pub mod layout;
 pub mod parser;
+pub mod routing;
 // IMPORTANT TODO: This module is almost identical to the Sequence diagram module.
 // Both have a parser, a layout engine, and a renderer.
 // Find a way to make the code reusable.
@@ -1,23 +1,133 @@
 use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::validation::validate_participant_name;
 
 pub type Node = String;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Edge {
     pub from: Node,
     pub to: Node,
+    pub label: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub label: String,
+    /// Member nodes in order of first appearance. A node belongs to at most one cluster.
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Graph {
     pub nodes: HashSet<Node>,
     pub edges: Vec<Edge>,
+    pub clusters: Vec<Cluster>,
+}
+
+impl Graph {
+    /// Index into `clusters` of the cluster containing `node`
+    pub fn cluster_of(&self, node: &str) -> Option<usize> {
+        self.clusters
+            .iter()
+            .position(|cluster| cluster.nodes.iter().any(|member| member == node))
+    }
+
+    /// Each edge with the node it leaves, in input order
+    pub fn edges(&self) -> impl Iterator<Item = (&str, &Edge)> {
+        self.edges.iter().map(|edge| (edge.from.as_str(), edge))
+    }
+
+    /// Ranges of `edges` to write inside the `subgraph` block of each cluster, in cluster
+    /// order. Each block is the one ending first that introduces the cluster's nodes in order,
+    /// leaving the most edges for the blocks after it. `None` when no input could produce the
+    /// clusters, e.g. for a graph built in code.
+    fn cluster_blocks(&self) -> Option<Vec<Range<usize>>> {
+        let mut blocks = Vec::new();
+        let mut position = 0;
+        for (index, cluster) in self.clusters.iter().enumerate() {
+            let block = if cluster.nodes.is_empty() {
+                position..position
+            } else {
+                (position..self.edges.len())
+                    .filter_map(|start| Some(start..self.block_end(index, start)?))
+                    .min_by_key(|block| block.end)?
+            };
+            position = block.end;
+            blocks.push(block);
+        }
+        Some(blocks)
+    }
+
+    /// End of the shortest block from edge `start` that introduces the nodes of the cluster at
+    /// `index` in order. Inside the block, every node must already be in an earlier cluster or
+    /// belong to this one, since any other node would join it.
+    fn block_end(&self, index: usize, start: usize) -> Option<usize> {
+        let nodes = &self.clusters[index].nodes;
+        let mut introduced = 0;
+        for (offset, edge) in self.edges[start..].iter().enumerate() {
+            for node in [&edge.from, &edge.to] {
+                match self.cluster_of(node) {
+                    Some(cluster) if cluster < index => {}
+                    Some(cluster) if cluster == index => {
+                        if nodes[..introduced].contains(node) {
+                            continue;
+                        }
+                        if nodes.get(introduced) != Some(node) {
+                            return None;
+                        }
+                        introduced += 1;
+                    }
+                    _ => return None,
+                }
+            }
+            if introduced == nodes.len() {
+                return Some(start + offset + 1);
+            }
+        }
+        None
+    }
+}
+
+/// Writes the graph as input, one `from -> to` line per edge and clusters as `subgraph` blocks,
+/// which parses back to an equal graph. Edge labels and nodes without edges have no syntax of
+/// their own and are left out, as are clusters no input could produce.
+impl std::fmt::Display for Graph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let blocks = self.cluster_blocks().unwrap_or_default();
+
+        let mut position = 0;
+        for (cluster, block) in self.clusters.iter().zip(blocks) {
+            for edge in &self.edges[position..block.start] {
+                writeln!(f, "{} -> {}", edge.from, edge.to)?;
+            }
+            writeln!(f, "subgraph {}", cluster.label)?;
+            for edge in &self.edges[block.clone()] {
+                writeln!(f, "    {} -> {}", edge.from, edge.to)?;
+            }
+            writeln!(f, "end")?;
+            position = block.end;
+        }
+        for edge in &self.edges[position..] {
+            writeln!(f, "{} -> {}", edge.from, edge.to)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Graph {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse(input)
+    }
 }
 
 #[derive(Debug)]
 pub struct ParseError {
-    line: usize,
-    message: String,
+    pub line: usize,
+    pub message: String,
 }
 
 impl std::fmt::Display for ParseError {
@@ -26,9 +136,26 @@ impl std::fmt::Display for ParseError {
     }
 }
 
+/// Parses graphs with options set once, for reuse across inputs. Graphs have no parse options
+/// yet, so this is the same as `parse`.
+#[derive(Debug, Clone, Default)]
+pub struct GraphParser;
+
+impl GraphParser {
+    pub fn new() -> Self {
+        GraphParser
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Graph, ParseError> {
+        parse(input)
+    }
+}
+
 pub fn parse(input: &str) -> Result<Graph, ParseError> {
     let mut nodes = HashSet::new();
     let mut edges = Vec::new();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut open_cluster: Option<(usize, Cluster)> = None;
 
     for (index, line) in input.lines().enumerate() {
         let line = line.trim();
@@ -37,18 +164,75 @@ pub fn parse(input: &str) -> Result<Graph, ParseError> {
         }
         let line_number = index + 1;
 
+        if !line.contains("->") {
+            if let Some(label) = line.strip_prefix("subgraph ") {
+                if let Some((_, cluster)) = &open_cluster {
+                    return Err(ParseError {
+                        line: line_number,
+                        message: format!(
+                            "Nested subgraphs are not supported. Subgraph '{}' is still open",
+                            cluster.label
+                        ),
+                    });
+                }
+
+                let label = label.trim();
+                if label.is_empty() {
+                    return Err(ParseError {
+                        line: line_number,
+                        message: "Subgraph label cannot be empty".to_string(),
+                    });
+                }
+
+                open_cluster = Some((
+                    line_number,
+                    Cluster {
+                        label: label.to_string(),
+                        nodes: Vec::new(),
+                    },
+                ));
+                continue;
+            }
+
+            if line == "end" {
+                match open_cluster.take() {
+                    Some((_, cluster)) => clusters.push(cluster),
+                    None => {
+                        return Err(ParseError {
+                            line: line_number,
+                            message: "Found 'end' without a matching 'subgraph'".to_string(),
+                        });
+                    }
+                }
+                continue;
+            }
+        }
+
         if let Some((from_node, to_node)) = line.split_once("->") {
             let from_node = from_node.trim();
             let to_node = to_node.trim();
 
-            validate_node(line_number, &from_node)?;
-            validate_node(line_number, &to_node)?;
+            validate_node(line_number, from_node)?;
+            validate_node(line_number, to_node)?;
 
             nodes.insert(from_node.to_string());
             nodes.insert(to_node.to_string());
+
+            if let Some((_, cluster)) = &mut open_cluster {
+                for node in [from_node, to_node] {
+                    let is_member = clusters
+                        .iter()
+                        .chain(std::iter::once(&*cluster))
+                        .any(|cluster| cluster.nodes.iter().any(|member| member == node));
+                    if !is_member {
+                        cluster.nodes.push(node.to_string());
+                    }
+                }
+            }
             edges.push(Edge {
                 from: from_node.to_string(),
                 to: to_node.to_string(),
+                label: None,
             });
         } else {
             return Err(ParseError {
@@ -58,39 +242,50 @@ pub fn parse(input: &str) -> Result<Graph, ParseError> {
         }
     }
 
-    Ok(Graph { nodes, edges })
-}
-
-pub fn validate_node(line_number: usize, name: &str) -> Result<(), ParseError> {
-    if name.is_empty() {
+    if let Some((line_number, cluster)) = open_cluster {
         return Err(ParseError {
             line: line_number,
-            message: "Node name cannot be empty".to_string(),
+            message: format!("Subgraph '{}' is not closed with 'end'", cluster.label),
         });
     }
 
-    if name.len() > 80 {
-        return Err(ParseError {
-            line: line_number,
-            message: "Node name too long. Max 80 chars".to_string(),
-        });
-    }
+    Ok(Graph {
+        nodes,
+        edges,
+        clusters,
+    })
+}
 
-    if name.contains("->") {
-        // TODO: Currently, dont allow multiple edges on same line. Fix this later.
+pub fn validate_node(line_number: usize, name: &str) -> Result<(), ParseError> {
+    // Keeps the wording graph errors had before the shared check, which says `is empty`
+    if name.is_empty() {
         return Err(ParseError {
             line: line_number,
-            message: "Node name cannot contain '->'".to_string(),
+            message: "Node name cannot be empty".to_string(),
         });
     }
-
-    Ok(())
+    validate_participant_name(name).map_err(|message| ParseError {
+        line: line_number,
+        message: format!("Node name {}", message),
+    })
 }
 
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
+
+    use crate::graph::builder::GraphBuilder;
+    use crate::graph::layout::{assign_ranks, build_adjacency_graph, sinks, sources};
+
     use super::*;
 
+    #[test]
+    fn test_graph_parser_reuse() {
+        let parser = GraphParser::new();
+        assert_eq!(parser.parse("a -> b").unwrap().edges.len(), 1);
+        assert_eq!(parser.parse("a -> b\nb -> c").unwrap().edges.len(), 2);
+    }
+
     #[test]
     fn test_input_whitespaces() {
         let input = "
@@ -127,50 +322,62 @@ registers            ->    \t   bus                   \n";
             Edge {
                 from: "cpu".to_string(),
                 to: "control-unit".to_string(),
+                label: None,
             },
             Edge {
                 from: "cpu".to_string(),
                 to: "alu".to_string(),
+                label: None,
             },
             Edge {
                 from: "cpu".to_string(),
                 to: "registers".to_string(),
+                label: None,
             },
             Edge {
                 from: "cpu".to_string(),
                 to: "cache".to_string(),
+                label: None,
             },
             Edge {
                 from: "control-unit".to_string(),
                 to: "decoder".to_string(),
+                label: None,
             },
             Edge {
                 from: "control-unit".to_string(),
                 to: "registers".to_string(),
+                label: None,
             },
             Edge {
                 from: "alu".to_string(),
                 to: "registers".to_string(),
+                label: None,
             },
             Edge {
                 from: "cache".to_string(),
                 to: "bus".to_string(),
+                label: None,
             },
             Edge {
                 from: "decoder".to_string(),
                 to: "instruction-register".to_string(),
+                label: None,
             },
             Edge {
                 from: "instruction-register".to_string(),
                 to: "registers".to_string(),
+                label: None,
             },
             Edge {
                 from: "memory".to_string(),
                 to: "bus".to_string(),
+                label: None,
             },
             Edge {
                 from: "registers".to_string(),
                 to: "bus".to_string(),
+                label: None,
             },
         ];
 
@@ -178,6 +385,22 @@ registers            ->    \t   bus                   \n";
         assert_eq!(graph.edges, expected_edges);
     }
 
+    #[test]
+    fn test_edges_with_source() {
+        let input = "\
+browser -> login-form
+login-form -> auth-service
+auth-service -> user-db
+auth-service -> session-store
+session-store -> browser";
+        let graph = parse(input).unwrap();
+
+        assert_eq!(graph.edges().count(), 5);
+        let (from, edge) = graph.edges().next().unwrap();
+        assert_eq!((from, edge.to.as_str()), ("browser", "login-form"));
+        assert!(graph.edges().all(|(from, edge)| from == edge.from));
+    }
+
     #[test]
     fn test_empty_node() {
         let input = "\
@@ -245,4 +468,153 @@ registers -> bus";
         assert_eq!(err.line, 9);
         assert!(err.message.contains("Node name cannot contain '->'"));
     }
+
+    #[test]
+    fn test_subgraphs() {
+        let input = "\
+client -> gateway
+subgraph Backend
+    gateway -> api
+    api -> worker
+end
+subgraph Storage
+    db -> replica
+    worker -> cache
+end
+api -> db";
+
+        let graph = parse(input).unwrap();
+        assert_eq!(
+            graph.clusters,
+            vec![
+                Cluster {
+                    label: "Backend".to_string(),
+                    nodes: vec![
+                        "gateway".to_string(),
+                        "api".to_string(),
+                        "worker".to_string()
+                    ],
+                },
+                Cluster {
+                    label: "Storage".to_string(),
+                    nodes: vec!["db".to_string(), "replica".to_string(), "cache".to_string()],
+                },
+            ]
+        );
+        assert_eq!(graph.cluster_of("client"), None);
+        assert_eq!(graph.cluster_of("worker"), Some(0));
+        assert_eq!(graph.cluster_of("db"), Some(1));
+        assert_eq!(graph.edges.len(), 6);
+
+        // Written back as the same blocks
+        assert_eq!(graph.to_string(), format!("{}\n", input));
+    }
+
+    #[test]
+    fn test_nested_subgraph() {
+        let input = "\
+subgraph Backend
+    subgraph Api
+        api -> worker
+    end
+end";
+        let result = parse(input);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("Nested subgraphs are not supported"));
+    }
+
+    #[test]
+    fn test_unclosed_subgraph() {
+        let input = "\
+subgraph Backend
+    api -> worker";
+        let result = parse(input);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("is not closed with 'end'"));
+    }
+
+    /// Distinct node names, and edges as pairs of indices into them
+    fn nodes_and_edges() -> impl Strategy<Value = (Vec<String>, Vec<(usize, usize)>)> {
+        proptest::collection::hash_set("[a-z][a-z0-9_-]{0,8}", 2..10).prop_flat_map(|names| {
+            let count = names.len();
+            let edges = proptest::collection::vec((0..count, 0..count), 1..20);
+            (Just(names.into_iter().collect()), edges)
+        })
+    }
+
+    fn edge_input<'a>(names: &[String], edges: impl Iterator<Item = &'a (usize, usize)>) -> String {
+        edges
+            .map(|&(from, to)| format!("{} -> {}\n", names[from], names[to]))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn test_dags_are_fully_ranked((names, edges) in nodes_and_edges()) {
+            // Edges only point to later names, so there is no cycle
+            let dag_edges: Vec<(usize, usize)> = edges
+                .iter()
+                .filter(|(from, to)| from != to)
+                .map(|&(from, to)| (from.min(to), from.max(to)))
+                .collect();
+            prop_assume!(!dag_edges.is_empty());
+            let graph = parse(&edge_input(&names, dag_edges.iter())).unwrap();
+
+            let ranks = assign_ranks(&graph, &build_adjacency_graph(&graph));
+            prop_assert_eq!(ranks.len(), graph.nodes.len());
+            for edge in &graph.edges {
+                prop_assert!(ranks[&edge.from] < ranks[&edge.to]);
+            }
+            prop_assert!(!sources(&graph).is_empty());
+            prop_assert!(!sinks(&graph).is_empty());
+        }
+
+        #[test]
+        fn test_isolated_node_has_no_edges((names, edges) in nodes_and_edges()) {
+            let mut builder = GraphBuilder::new();
+            for &(from, to) in &edges {
+                builder.add_edge(&names[from], &names[to]);
+            }
+            // Generated names are lowercase
+            let graph = builder.add_node("Isolated").build().unwrap();
+
+            // No edge in or out: an in-degree and out-degree of 0
+            let isolated = "Isolated".to_string();
+            prop_assert!(sources(&graph).contains(&isolated));
+            prop_assert!(sinks(&graph).contains(&isolated));
+        }
+
+        #[test]
+        fn test_display_round_trip(
+            (names, edges) in nodes_and_edges(),
+            blocks in proptest::collection::vec(0..3_usize, 20),
+        ) {
+            // Consecutive edges with the same non-zero block number share a subgraph
+            let mut input = String::new();
+            let mut open_block = 0;
+            for (index, edge) in edges.iter().enumerate() {
+                if blocks[index] != open_block {
+                    if open_block != 0 {
+                        input.push_str("end\n");
+                    }
+                    if blocks[index] != 0 {
+                        input.push_str(&format!("subgraph Cluster {}\n", index));
+                    }
+                    open_block = blocks[index];
+                }
+                input.push_str(&edge_input(&names, std::iter::once(edge)));
+            }
+            if open_block != 0 {
+                input.push_str("end\n");
+            }
+            let graph = parse(&input).unwrap();
+
+            let text = graph.to_string();
+            prop_assert_eq!(text.parse::<Graph>().unwrap(), graph, "written as:\n{}", text);
+        }
+    }
 }
@@ -0,0 +1,395 @@
+/// A node box in canvas coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    /// Left column
+    pub x: usize,
+    /// Top row
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    fn right(&self) -> usize {
+        self.x + self.width.saturating_sub(1)
+    }
+
+    fn bottom(&self) -> usize {
+        self.y + self.height.saturating_sub(1)
+    }
+
+    fn center_x(&self) -> usize {
+        self.x + self.width.saturating_sub(1) / 2
+    }
+
+    fn center_y(&self) -> usize {
+        self.y + self.height.saturating_sub(1) / 2
+    }
+}
+
+/// Routes an edge between two boxes with right-angle connectors, as the `(x, y, char)` cells to
+/// draw from `from` to `to`. The last cell is the arrowhead, unless a corner is right next to
+/// `to`.
+///
+/// Boxes sharing columns are joined by a straight vertical line, and boxes sharing rows by a
+/// straight horizontal line. Otherwise the edge leaves `from` through its top or bottom, turns
+/// once at the middle row of `to` and enters it from the side. Overlapping boxes get no route,
+/// except for an edge from a box to itself, which gets a loop (see [`route_self_loop`]).
+pub fn route_edge(from: &Rect, to: &Rect) -> Vec<(usize, usize, char)> {
+    if from == to {
+        return route_self_loop(from);
+    }
+
+    let columns = overlap((from.x, from.right()), (to.x, to.right()));
+    let rows = overlap((from.y, from.bottom()), (to.y, to.bottom()));
+
+    match (columns, rows) {
+        (Some(_), Some(_)) => Vec::new(),
+        (Some((left, right)), None) => {
+            let x = (left + right) / 2;
+            if from.bottom() < to.y {
+                vertical(x, from.bottom() + 1, to.y - 1, '▼')
+            } else {
+                vertical(x, from.y - 1, to.bottom() + 1, '▲')
+            }
+        }
+        (None, Some((top, bottom))) => {
+            let y = (top + bottom) / 2;
+            if from.right() < to.x {
+                horizontal(from.right() + 1, to.x - 1, y, '▶')
+            } else {
+                horizontal(from.x - 1, to.right() + 1, y, '◀')
+            }
+        }
+        (None, None) => {
+            let x = from.center_x();
+            let y = to.center_y();
+            let downwards = from.bottom() < to.y;
+            let rightwards = from.right() < to.x;
+
+            let corner = match (downwards, rightwards) {
+                (true, true) => '└',
+                (true, false) => '┘',
+                (false, true) => '┌',
+                (false, false) => '┐',
+            };
+            let mut cells = if downwards {
+                vertical(x, from.bottom() + 1, y, corner)
+            } else {
+                vertical(x, from.y - 1, y, corner)
+            };
+
+            // A corner right next to `to` leaves no room for the horizontal segment
+            if rightwards && x + 1 < to.x {
+                cells.extend(horizontal(x + 1, to.x - 1, y, '▶'));
+            } else if !rightwards && x > to.right() + 1 {
+                cells.extend(horizontal(x - 1, to.right() + 1, y, '◀'));
+            }
+            cells
+        }
+    }
+}
+
+/// Routes an edge from a box back to itself as a small loop on its right side. It leaves at
+/// the middle row and comes back one row below, so a box needs at least two rows. Lower
+/// boxes get no loop.
+pub fn route_self_loop(node: &Rect) -> Vec<(usize, usize, char)> {
+    if node.height < 2 {
+        return Vec::new();
+    }
+
+    let x = node.right() + 1;
+    let y = node.center_y();
+    vec![
+        (x, y, '─'),
+        (x + 1, y, '┐'),
+        (x + 1, y + 1, '┘'),
+        (x, y + 1, '◀'),
+    ]
+}
+
+/// Routes edges from several boxes into one, as a bundle: each edge leaves its source through
+/// the bottom and drops to a shared row just above `to`, which gathers them into a single trunk
+/// entering `to` at its center column. This replaces one connector per edge, which would
+/// overlap next to a node many nodes point at.
+///
+/// Sources that are not at least two rows above `to` can't reach the shared row and are routed
+/// on their own with [`route_edge`], as are all sources when fewer than two remain.
+pub fn route_fan_in(sources: &[Rect], to: &Rect) -> Vec<(usize, usize, char)> {
+    // The shared row, above the row of the arrowhead
+    let bus = to.y.checked_sub(2);
+    let (bundled, single): (Vec<&Rect>, Vec<&Rect>) = sources
+        .iter()
+        .partition(|from| bus.is_some_and(|bus| *from != to && from.bottom() < bus));
+
+    let mut cells: Vec<_> = single
+        .iter()
+        .flat_map(|from| route_edge(from, to))
+        .collect();
+    let Some(bus) = bus.filter(|_| bundled.len() >= 2) else {
+        cells.extend(bundled.iter().flat_map(|from| route_edge(from, to)));
+        return cells;
+    };
+
+    let trunk = to.center_x();
+    let stubs: Vec<usize> = bundled.iter().map(|from| from.center_x()).collect();
+    for (from, &x) in bundled.iter().zip(&stubs) {
+        cells.extend((from.bottom() + 1..bus).map(|y| (x, y, '│')));
+    }
+
+    let left = stubs.iter().copied().fold(trunk, usize::min);
+    let right = stubs.iter().copied().fold(trunk, usize::max);
+    for x in left..=right {
+        let up = stubs.contains(&x);
+        let ch = junction(up, x == trunk, x > left, x < right);
+        cells.push((x, bus, ch));
+    }
+    cells.push((trunk, bus + 1, '▼'));
+    cells
+}
+
+/// Routes edges given as `(from, to)` boxes, bundling the edges into each box reached from
+/// several others with [`route_fan_in`]. Targets are handled in the order they first appear.
+pub fn route_edges_bundled(edges: &[(Rect, Rect)]) -> Vec<(usize, usize, char)> {
+    let mut targets: Vec<(Rect, Vec<Rect>)> = Vec::new();
+    for (from, to) in edges {
+        match targets.iter_mut().find(|(target, _)| target == to) {
+            Some((_, sources)) => sources.push(*from),
+            None => targets.push((*to, vec![*from])),
+        }
+    }
+
+    targets
+        .iter()
+        .flat_map(|(to, sources)| route_fan_in(sources, to))
+        .collect()
+}
+
+/// The box-drawing character joining lines towards the given sides of a cell
+fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (true, true, true, true) => '┼',
+        (true, true, true, false) => '┤',
+        (true, true, false, true) => '├',
+        (true, false, true, true) => '┴',
+        (true, false, true, false) => '┘',
+        (true, false, false, true) => '└',
+        (false, true, true, true) => '┬',
+        (false, true, true, false) => '┐',
+        (false, true, false, true) => '┌',
+        (true, _, false, false) | (false, true, false, false) => '│',
+        (false, false, _, _) => '─',
+    }
+}
+
+/// Shared part of two inclusive ranges
+fn overlap(a: (usize, usize), b: (usize, usize)) -> Option<(usize, usize)> {
+    let start = a.0.max(b.0);
+    let end = a.1.min(b.1);
+    (start <= end).then_some((start, end))
+}
+
+/// A vertical line from `from_y` to `to_y` in either direction, ending with `end`
+fn vertical(x: usize, from_y: usize, to_y: usize, end: char) -> Vec<(usize, usize, char)> {
+    let cells = steps(from_y, to_y).map(|y| (x, y, '│')).collect();
+    with_end(cells, end)
+}
+
+/// A horizontal line from `from_x` to `to_x` in either direction, ending with `end`
+fn horizontal(from_x: usize, to_x: usize, y: usize, end: char) -> Vec<(usize, usize, char)> {
+    let cells = steps(from_x, to_x).map(|x| (x, y, '─')).collect();
+    with_end(cells, end)
+}
+
+/// The inclusive range from `from` to `to`, counting down when `to` is smaller
+fn steps(from: usize, to: usize) -> Box<dyn Iterator<Item = usize>> {
+    if from <= to {
+        Box::new(from..=to)
+    } else {
+        Box::new((to..=from).rev())
+    }
+}
+
+fn with_end(mut cells: Vec<(usize, usize, char)>, end: char) -> Vec<(usize, usize, char)> {
+    if let Some(cell) = cells.last_mut() {
+        cell.2 = end;
+    }
+    cells
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_column_is_straight() {
+        let from = Rect {
+            x: 2,
+            y: 0,
+            width: 7,
+            height: 3,
+        };
+        let to = Rect {
+            x: 4,
+            y: 6,
+            width: 9,
+            height: 3,
+        };
+
+        let cells = route_edge(&from, &to);
+        // Centered on the shared columns 4..=8
+        assert_eq!(cells, [(6, 3, '│'), (6, 4, '│'), (6, 5, '▼')]);
+
+        let cells = route_edge(&to, &from);
+        assert_eq!(cells, [(6, 5, '│'), (6, 4, '│'), (6, 3, '▲')]);
+    }
+
+    #[test]
+    fn test_non_aligned_boxes_turn_once() {
+        let from = Rect {
+            x: 0,
+            y: 0,
+            width: 5,
+            height: 3,
+        };
+        let to = Rect {
+            x: 8,
+            y: 5,
+            width: 5,
+            height: 3,
+        };
+
+        let cells = route_edge(&from, &to);
+        let corners: Vec<_> = cells
+            .iter()
+            .filter(|(_, _, ch)| "┌┐└┘".contains(*ch))
+            .collect();
+        assert_eq!(corners, [&(2, 6, '└')]);
+        assert_eq!(
+            cells,
+            [
+                (2, 3, '│'),
+                (2, 4, '│'),
+                (2, 5, '│'),
+                (2, 6, '└'),
+                (3, 6, '─'),
+                (4, 6, '─'),
+                (5, 6, '─'),
+                (6, 6, '─'),
+                (7, 6, '▶'),
+            ]
+        );
+
+        // Upwards to the left
+        let cells = route_edge(&to, &from);
+        assert_eq!(cells[..2], [(10, 4, '│'), (10, 3, '│')]);
+        assert_eq!(cells[3], (10, 1, '┐'));
+        assert_eq!(cells.last(), Some(&(5, 1, '◀')));
+    }
+
+    #[test]
+    fn test_same_row_and_overlapping_boxes() {
+        let left = Rect {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 3,
+        };
+        let right = Rect {
+            x: 6,
+            y: 1,
+            width: 3,
+            height: 3,
+        };
+        assert_eq!(
+            route_edge(&left, &right),
+            [(3, 1, '─'), (4, 1, '─'), (5, 1, '▶')]
+        );
+
+        let overlapping = Rect { x: 2, ..right };
+        assert!(route_edge(&left, &overlapping).is_empty());
+    }
+
+    #[test]
+    fn test_self_loop() {
+        let node = Rect {
+            x: 0,
+            y: 0,
+            width: 5,
+            height: 4,
+        };
+        assert_eq!(
+            route_edge(&node, &node),
+            [(5, 1, '─'), (6, 1, '┐'), (6, 2, '┘'), (5, 2, '◀')]
+        );
+
+        // Both rows of the loop stay beside the box
+        let low = Rect { height: 2, ..node };
+        assert_eq!(
+            route_self_loop(&low),
+            [(5, 0, '─'), (6, 0, '┐'), (6, 1, '┘'), (5, 1, '◀')]
+        );
+        assert!(route_self_loop(&Rect { height: 1, ..node }).is_empty());
+    }
+
+    #[test]
+    fn test_fan_in_shares_one_trunk() {
+        let source = |x| Rect {
+            x,
+            y: 0,
+            width: 5,
+            height: 3,
+        };
+        let sources = [source(0), source(8), source(16)];
+        let to = Rect {
+            x: 8,
+            y: 6,
+            width: 5,
+            height: 3,
+        };
+
+        let cells = route_fan_in(&sources, &to);
+        // One stub per source down to the shared row at y = 4, then a single trunk
+        let bus: String = cells
+            .iter()
+            .filter(|&&(_, y, _)| y == 4)
+            .map(|&(_, _, ch)| ch)
+            .collect();
+        assert_eq!(bus, "└───────┼───────┘");
+        assert_eq!(
+            cells
+                .iter()
+                .filter(|&&(_, y, _)| y == 5)
+                .collect::<Vec<_>>(),
+            [&(10, 5, '▼')]
+        );
+        assert_eq!(cells.iter().filter(|&&(_, _, ch)| ch == '▼').count(), 1);
+    }
+
+    #[test]
+    fn test_bundled_edges_by_target() {
+        let node = |x, y| Rect {
+            x,
+            y,
+            width: 5,
+            height: 3,
+        };
+        let (a, b, hub, beside) = (node(0, 0), node(8, 0), node(4, 6), node(12, 6));
+
+        // Two edges into `hub` share a trunk, the single edge into `beside` is routed alone
+        let cells = route_edges_bundled(&[(a, hub), (b, beside), (b, hub)]);
+        let route_to_beside = route_edge(&b, &beside);
+        assert!(cells.ends_with(&route_to_beside));
+        assert_eq!(
+            cells[..cells.len() - route_to_beside.len()],
+            route_fan_in(&[a, b], &hub)
+        );
+
+        // A source next to the target can't reach the shared row
+        let cells = route_fan_in(&[a, beside], &hub);
+        assert_eq!(
+            cells,
+            [route_edge(&beside, &hub), route_edge(&a, &hub)].concat()
+        );
+    }
+}
@@ -1,19 +1,30 @@
 use std::cmp::max;
+use std::collections::HashMap;
 
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::parser::{Edge, Participant, SequenceDiagram};
+use crate::parser::{
+    Block, EdgeStyle, LabelPosition, ParseError, ParticipantKind, Position, RefBlock,
+    SequenceDiagram, parse,
+};
+use crate::tokenizer::{TokenizeError, tokenize};
 
 #[derive(Debug)]
 pub struct ParticipantLayout {
     pub name: String,
+    /// Text drawn in the participant boxes: the name, or its index with `use_indices`
+    pub label: String,
+    pub kind: ParticipantKind,
     pub center_x: usize,
+    /// Lower than the other boxes for a participant created partway through
     pub top_box_y: usize,
-    pub bottom_box_y: usize,
+    /// `None` for a participant destroyed partway through
+    pub bottom_box_y: Option<usize>,
     pub width: usize,
+    pub height: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ArrowDirection {
     Left,
     Right,
@@ -23,9 +34,14 @@ pub enum ArrowDirection {
 pub struct EdgeLayout {
     pub start_x: usize,
     pub end_x: usize,
+    /// First row of the edge
     pub y: usize,
+    pub arrow_y: usize,
+    /// Row of the message, set with the message
+    pub message_y: Option<usize>,
     pub direction: ArrowDirection,
     pub message: Option<String>,
+    pub style: Option<EdgeStyle>,
 }
 
 #[derive(Debug)]
@@ -33,211 +49,2099 @@ pub struct LifelineLayout {
     pub x: usize,
     pub start_y: usize,
     pub end_y: usize,
+    /// Ends with a cross at `end_y`
+    pub destroyed: bool,
+}
+
+#[derive(Debug)]
+pub struct NoteLayout {
+    pub center_x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub text: String,
+}
+
+/// Kind of a block, which sets the look of its frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Par,
+    /// Drawn with a double line
+    Critical,
+    /// Drawn with a dashed line and a bold label
+    Break,
+    Group,
+}
+
+/// Frame of a block such as `par`
+#[derive(Debug)]
+pub struct BlockLayout {
+    pub kind: BlockKind,
+    pub x_start: usize,
+    pub x_end: usize,
+    pub y_start: usize,
+    pub y_end: usize,
+    /// Drawn in the top border, e.g. `par Fetch data`
+    pub label: String,
+    pub dividers: Vec<DividerLayout>,
+}
+
+/// Box of a `ref over`, with a `ref` tag in its top-left corner
+#[derive(Debug)]
+pub struct RefLayout {
+    pub x_start: usize,
+    pub x_end: usize,
+    /// Top row of the three-row box
+    pub y: usize,
+    pub label: String,
+}
+
+/// Extra vertical space from a `---` line
+#[derive(Debug)]
+pub struct SpacerLayout {
+    pub y: usize,
+    pub height: usize,
+    /// `(x_start, y, x_end)` of the `┈` line drawn with `spacer_divider`
+    pub divider: Option<(usize, usize, usize)>,
+}
+
+/// Dashed line between two regions of a block
+#[derive(Debug)]
+pub struct DividerLayout {
+    pub y: usize,
+    pub label: String,
+}
+
+/// A single line of text, such as the title
+#[derive(Debug, PartialEq)]
+pub struct TextLayout {
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
 }
 
 #[derive(Debug)]
 pub struct SequenceDiagramLayout {
+    pub title: Option<TextLayout>,
+    /// Maps participant indices to names with `use_indices`
+    pub legend: Option<TextLayout>,
     pub participant_layouts: Vec<ParticipantLayout>,
     pub edge_layouts: Vec<EdgeLayout>,
     pub lifeline_layouts: Vec<LifelineLayout>,
+    pub note_layouts: Vec<NoteLayout>,
+    /// Enclosing blocks come before the blocks inside them
+    pub block_layouts: Vec<BlockLayout>,
+    pub spacer_layouts: Vec<SpacerLayout>,
+    pub ref_layouts: Vec<RefLayout>,
     pub width: usize,
     pub height: usize,
+    /// Problems that did not prevent the layout, e.g. a diagram wider than the terminal
+    pub warnings: Vec<String>,
+}
+
+impl SequenceDiagramLayout {
+    /// Smallest area holding every drawn character, as inclusive
+    /// `(min_x, min_y, max_x, max_y)` canvas coordinates. `(0, 0, 0, 0)` for an empty diagram.
+    pub fn bounding_box(&self) -> (usize, usize, usize, usize) {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        let mut extend = |min_x: usize, min_y: usize, max_x: usize, max_y: usize| {
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => {
+                    (x0.min(min_x), y0.min(min_y), x1.max(max_x), y1.max(max_y))
+                }
+                None => (min_x, min_y, max_x, max_y),
+            });
+        };
+
+        for participant in &self.participant_layouts {
+            let left_x = (participant.center_x + 1).saturating_sub(participant.width.div_ceil(2));
+            let right_x = left_x + participant.width.saturating_sub(1);
+            // Boxes are three rows high
+            extend(
+                left_x,
+                participant.top_box_y,
+                right_x,
+                participant.top_box_y + 2,
+            );
+            if let Some(bottom_box_y) = participant.bottom_box_y {
+                let bottom_box_top_y = bottom_box_y - participant.height;
+                extend(left_x, bottom_box_top_y, right_x, bottom_box_top_y + 2);
+            }
+        }
+
+        for lifeline in &self.lifeline_layouts {
+            extend(lifeline.x, lifeline.start_y, lifeline.x, lifeline.end_y);
+        }
+
+        for edge in &self.edge_layouts {
+            let min_x = edge.start_x.min(edge.end_x);
+            let max_x = edge.start_x.max(edge.end_x);
+            extend(min_x, edge.arrow_y, max_x, edge.arrow_y);
+            if let (Some(message), Some(message_y)) = (&edge.message, edge.message_y) {
+                // Same placement as the renderer: centered on the arrow
+                let message_x = ((min_x + max_x) / 2).saturating_sub(message.width() / 2);
+                let message_end_x = message_x + message.width().saturating_sub(1);
+                extend(message_x, message_y, message_end_x, message_y);
+            }
+        }
+
+        for note in &self.note_layouts {
+            let left_x = (note.center_x + 1).saturating_sub(note.width.div_ceil(2));
+            let right_x = left_x + note.width.saturating_sub(1);
+            extend(left_x, note.y, right_x, note.y + 2);
+        }
+
+        for block in &self.block_layouts {
+            extend(block.x_start, block.y_start, block.x_end, block.y_end);
+        }
+
+        for ref_layout in &self.ref_layouts {
+            // Boxes are three rows high, like notes
+            extend(
+                ref_layout.x_start,
+                ref_layout.y,
+                ref_layout.x_end,
+                ref_layout.y + 2,
+            );
+        }
+
+        for (x_start, y, x_end) in self
+            .spacer_layouts
+            .iter()
+            .filter_map(|spacer| spacer.divider)
+        {
+            extend(x_start, y, x_end, y);
+        }
+
+        for text in self.title.iter().chain(self.legend.iter()) {
+            extend(
+                text.x,
+                text.y,
+                text.x + text.text.width().saturating_sub(1),
+                text.y,
+            );
+        }
+
+        bounds.unwrap_or((0, 0, 0, 0))
+    }
 }
 
 pub const EDGE_SPACING: usize = 1;
 pub const PARTICIPANT_HEIGHT: usize = 3;
+pub const NOTE_HEIGHT: usize = 3;
+pub const SPACER_HEIGHT: usize = 2;
 pub const PARTICIPANT_PADDING_X: usize = 1;
 pub const MESSAGE_PADDING_X: usize = 1;
+/// Columns of arrow line left on each side of a message centered over the arrow
+pub const CENTERED_MESSAGE_MARGIN: usize = 2;
 pub const BORDER_WIDTH: usize = 1;
+/// Columns between a block frame and the outermost lifeline or note inside it
+pub const BLOCK_PADDING_X: usize = 2;
+/// Columns between a ref box and the outermost lifeline it spans
+pub const REF_PADDING_X: usize = 2;
+/// Width of the `│ ref │` tag at the left of a ref box, borders included
+pub const REF_TAG_WIDTH: usize = 7;
+/// Extra blank rows above and below a critical block
+pub const CRITICAL_BLOCK_MARGIN: usize = 1;
 
 pub const MARGIN_LEFT: usize = 1;
 pub const MARGIN_RIGHT: usize = 1;
 pub const MARGIN_TOP: usize = 1;
 pub const MARGIN_BOTTOM: usize = 1;
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Margins {
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            left: MARGIN_LEFT,
+            right: MARGIN_RIGHT,
+            top: MARGIN_TOP,
+            bottom: MARGIN_BOTTOM,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceLayoutConfig {
+    /// Blank rows between consecutive edges
+    pub edge_spacing: usize,
+    pub participant_height: usize,
+    pub note_height: usize,
+    pub participant_padding_x: usize,
+    pub message_padding_x: usize,
+    pub border_width: usize,
+    pub margins: Margins,
+    /// Show 1-based participant indices in the boxes and list the names in a legend row
+    pub use_indices: bool,
+    /// Shrink the horizontal padding so the diagram fits the terminal width
+    pub auto_width: bool,
+    /// Source of the terminal width used by `auto_width`. `None` when it cannot be determined.
+    pub terminal_width: fn() -> Option<usize>,
+    /// Shrink the horizontal padding so the diagram fits this many columns. With `auto_width`,
+    /// the narrower of this and the terminal width applies.
+    pub max_width: Option<usize>,
+    /// Place the first participant on the right, for right-to-left reading order
+    pub rtl: bool,
+    /// Truncate participant names wider than this many columns in the boxes, ending them with
+    /// `…`. The diagram keeps the full names.
+    pub max_participant_display_len: Option<usize>,
+    /// Participants left out of the boxes, lifelines and legend. Their edges still end where
+    /// the participant would have been.
+    pub hide_participants: Vec<String>,
+    /// Blank rows added by each `---` line
+    pub spacer_height: usize,
+    /// Draw a faint `┈` line across the lifelines in each spacer
+    pub spacer_divider: bool,
+}
+
+/// Terminal width from the attached terminal, falling back to the `COLUMNS` environment variable
+fn detect_terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return Some(width as usize);
+    }
+
+    std::env::var("COLUMNS").ok()?.trim().parse().ok()
+}
+
+impl Default for SequenceLayoutConfig {
+    fn default() -> Self {
+        SequenceLayoutConfig {
+            edge_spacing: EDGE_SPACING,
+            participant_height: PARTICIPANT_HEIGHT,
+            note_height: NOTE_HEIGHT,
+            participant_padding_x: PARTICIPANT_PADDING_X,
+            message_padding_x: MESSAGE_PADDING_X,
+            border_width: BORDER_WIDTH,
+            margins: Margins::default(),
+            use_indices: false,
+            auto_width: false,
+            terminal_width: detect_terminal_width,
+            max_width: None,
+            rtl: false,
+            max_participant_display_len: None,
+            hide_participants: Vec::new(),
+            spacer_height: SPACER_HEIGHT,
+            spacer_divider: false,
+        }
+    }
+}
+
+impl SequenceLayoutConfig {
+    /// Width of a box (participant or note) around text of the given width
+    fn box_width(&self, text_width: usize) -> usize {
+        text_width + self.participant_padding_x * 2 + self.border_width * 2
+    }
+
+    /// Reduces the horizontal padding by one step. Returns false when there is nothing left to
+    /// reduce.
+    fn shrink_padding(&mut self) -> bool {
+        if self.message_padding_x > 0 {
+            self.message_padding_x -= 1;
+        } else if self.participant_padding_x > 0 {
+            self.participant_padding_x -= 1;
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
 pub fn calculate_sequence_layout(sequence_diagram: &SequenceDiagram) -> SequenceDiagramLayout {
-    let (edges_with_message, edges_without_message) = count_edges(sequence_diagram);
+    calculate_sequence_layout_with_config(sequence_diagram, &SequenceLayoutConfig::default())
+}
 
-    let total_height = (edges_with_message + edges_without_message + 1) * EDGE_SPACING
-        + edges_with_message * 2
-        + edges_without_message * 1
-        + PARTICIPANT_HEIGHT * 2
-        + MARGIN_TOP
-        + MARGIN_BOTTOM;
+pub fn calculate_sequence_layout_with_config(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> SequenceDiagramLayout {
+    let terminal_width = if config.auto_width {
+        (config.terminal_width)()
+    } else {
+        None
+    };
+    match (config.max_width, terminal_width) {
+        (Some(max_width), Some(terminal_width)) if terminal_width < max_width => fit_to_width(
+            sequence_diagram,
+            config,
+            terminal_width,
+            "the terminal width",
+        ),
+        (Some(max_width), _) => {
+            fit_to_width(sequence_diagram, config, max_width, "the maximum width")
+        }
+        (None, Some(terminal_width)) => fit_to_width(
+            sequence_diagram,
+            config,
+            terminal_width,
+            "the terminal width",
+        ),
+        (None, None) => calculate_unconstrained_layout(sequence_diagram, config),
+    }
+}
 
-    let positions = calculate_horizontal_positions(sequence_diagram);
+/// Why a diagram could not be read from its text
+#[derive(Debug)]
+pub enum SequenceError {
+    Tokenize(TokenizeError),
+    Parse(ParseError),
+}
 
-    let last_part_position = positions.last().copied().unwrap_or(0);
-    let last_part_width = sequence_diagram
-        .participants
-        .last()
-        .map(|p| p.width())
-        .unwrap_or(0)
-        + PARTICIPANT_PADDING_X * 2
-        + BORDER_WIDTH * 2;
+impl std::fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::Tokenize(err) => err.fmt(f),
+            SequenceError::Parse(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<TokenizeError> for SequenceError {
+    fn from(err: TokenizeError) -> Self {
+        SequenceError::Tokenize(err)
+    }
+}
+
+impl From<ParseError> for SequenceError {
+    fn from(err: ParseError) -> Self {
+        SequenceError::Parse(err)
+    }
+}
+
+/// Width and height in char cells of the rendered diagram, without rendering it
+pub fn measure(input: &str) -> Result<(usize, usize), SequenceError> {
+    measure_with_config(input, &SequenceLayoutConfig::default())
+}
+
+pub fn measure_with_config(
+    input: &str,
+    config: &SequenceLayoutConfig,
+) -> Result<(usize, usize), SequenceError> {
+    let sequence_diagram = parse(tokenize(input)?)?;
+    let layout = calculate_sequence_layout_with_config(&sequence_diagram, config);
+    Ok((layout.width, layout.height))
+}
+
+/// Lays out the diagram with the padding of `config`, shrinking the padding until the diagram is
+/// at most `max_width` columns wide. A diagram that does not fit even without padding is laid out
+/// anyway, with a warning naming the `limit`.
+fn fit_to_width(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+    max_width: usize,
+    limit: &str,
+) -> SequenceDiagramLayout {
+    let mut config = config.clone();
+
+    loop {
+        let mut layout = calculate_unconstrained_layout(sequence_diagram, &config);
+        if layout.width <= max_width {
+            return layout;
+        }
+
+        if !config.shrink_padding() {
+            layout.warnings.push(format!(
+                "Diagram is {} columns wide and does not fit {} of {} columns",
+                layout.width, limit, max_width
+            ));
+            return layout;
+        }
+    }
+}
+
+fn calculate_unconstrained_layout(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> SequenceDiagramLayout {
+    let labels = participant_labels(sequence_diagram, config);
+    let content_widths = calculate_content_widths(sequence_diagram, &labels);
+    let positions = calculate_horizontal_positions(sequence_diagram, &content_widths, config);
+
+    // The rightmost participant is the last one, or the first one right-to-left
+    let (last_part_position, last_part_content_width) = positions
+        .iter()
+        .zip(&content_widths)
+        .max_by_key(|(position, _)| **position)
+        .map(|(&position, &width)| (position, width))
+        .unwrap_or((0, 0));
+    let last_part_width = config.box_width(last_part_content_width);
 
     // Plus 1 because of 0-base index. width = index of last column + 1
-    let total_width = last_part_position + last_part_width / 2 + MARGIN_RIGHT + 1;
+    let mut total_width = last_part_position + last_part_width / 2 + config.margins.right + 1;
+
+    let (mut title, mut legend, top_y) = calculate_header_layouts(sequence_diagram, config);
+    for text_layout in title.iter().chain(legend.iter()) {
+        total_width = max(
+            total_width,
+            config.margins.left + text_layout.text.width() + config.margins.right,
+        );
+    }
+    if let Some(legend) = &mut legend {
+        legend.x = config.margins.left;
+    }
 
-    let part_layouts = calculate_participant_layouts(total_height, sequence_diagram, &positions);
-    let lifeline_layouts = calculate_lifeline_layouts(total_height, &positions);
-    let edge_layouts = calculate_edge_layouts(sequence_diagram, &positions);
+    let (edge_layouts, note_layouts, marker_rows, end_y) =
+        calculate_edge_and_note_layouts(sequence_diagram, &labels, &positions, top_y, config);
+    let ref_layouts = calculate_ref_layouts(sequence_diagram, &positions, marker_rows.ref_ys);
+    let block_layouts =
+        calculate_block_layouts(sequence_diagram, &positions, marker_rows.blocks, config);
+    let frame_ends = block_layouts.iter().map(|block| block.x_end);
+    let ref_ends = ref_layouts.iter().map(|ref_layout| ref_layout.x_end);
+    if let Some(x_end) = frame_ends.chain(ref_ends).max() {
+        total_width = max(total_width, x_end + 1 + config.margins.right);
+    }
+    if let Some(title) = &mut title {
+        title.x = (total_width - title.text.width()) / 2;
+    }
+    let total_height = end_y + config.participant_height + config.margins.bottom;
+
+    let part_layouts = calculate_participant_layouts(
+        total_height,
+        sequence_diagram,
+        &labels,
+        &positions,
+        top_y,
+        &marker_rows.lifetimes,
+        config,
+    );
+    let lifeline_layouts = calculate_lifeline_layouts(
+        total_height,
+        sequence_diagram,
+        &positions,
+        top_y,
+        &marker_rows.lifetimes,
+        config,
+    );
+    let visible_positions: Vec<usize> = sequence_diagram
+        .participants
+        .iter()
+        .zip(&positions)
+        .filter(|(name, _)| !config.hide_participants.contains(name))
+        .map(|(_, &position)| position)
+        .collect();
+    let spacer_layouts =
+        calculate_spacer_layouts(marker_rows.spacer_ys, &visible_positions, config);
 
     SequenceDiagramLayout {
+        title,
+        legend,
         edge_layouts,
         lifeline_layouts,
+        note_layouts,
+        block_layouts,
+        spacer_layouts,
+        ref_layouts,
         participant_layouts: part_layouts,
         width: total_width,
         height: total_height,
+        warnings: hidden_participant_warnings(sequence_diagram, config),
     }
 }
 
-fn count_edges(sequence_diagram: &SequenceDiagram) -> (usize, usize) {
-    let (edges_with_message, edges_without_message) =
-        sequence_diagram
-            .edges
+/// Warns about notes and refs over hidden participants, which are drawn without the
+/// participant's box
+fn hidden_participant_warnings(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> Vec<String> {
+    let notes = sequence_diagram
+        .notes
+        .iter()
+        .filter(|note| config.hide_participants.contains(&note.participant))
+        .map(|note| format!("Note over hidden participant '{}'", note.participant));
+    let refs = sequence_diagram.refs.iter().flat_map(|ref_block| {
+        ref_block
+            .participants
             .iter()
-            .fold((0, 0), |(with, without), edge| {
-                if edge.message.is_some() {
-                    (with + 1, without)
-                } else {
-                    (with, without + 1)
-                }
-            });
+            .filter(|name| config.hide_participants.contains(name))
+            .map(|name| format!("Ref over hidden participant '{}'", name))
+    });
+    notes.chain(refs).collect()
+}
 
-    (edges_with_message, edges_without_message)
+fn participant_labels(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> Vec<String> {
+    sequence_diagram
+        .participants
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            if config.use_indices {
+                (index + 1).to_string()
+            } else if let Some(max_len) = config.max_participant_display_len {
+                truncate_name(name, max_len)
+            } else {
+                name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Shortens `name` to at most `max_width` columns, replacing the cut off part with `…`. Empty
+/// when `max_width` is 0, as even the `…` does not fit.
+pub(crate) fn truncate_name(name: &str, max_width: usize) -> String {
+    if name.width() <= max_width {
+        return name.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for ch in name.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width + 1 > max_width {
+            break;
+        }
+        truncated.push(ch);
+        width += ch_width;
+    }
+    truncated.push('…');
+    truncated
 }
 
-fn calculate_horizontal_positions(sequence_diagram: &SequenceDiagram) -> Vec<usize> {
-    let parts = &sequence_diagram.participants;
+/// Lays out the title and legend rows above the participant boxes. Their x is left at 0 until
+/// the diagram width is known. Returns the row the participant boxes start at.
+fn calculate_header_layouts(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> (Option<TextLayout>, Option<TextLayout>, usize) {
+    let mut current_y = config.margins.top;
+
+    let title = sequence_diagram.title.as_ref().map(|text| {
+        let title = TextLayout {
+            x: 0,
+            y: current_y,
+            text: text.clone(),
+        };
+        current_y += 1 + config.edge_spacing;
+        title
+    });
+
+    let legend = (config.use_indices && !sequence_diagram.participants.is_empty()).then(|| {
+        let text = sequence_diagram
+            .participants
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !config.hide_participants.contains(name))
+            .map(|(index, name)| format!("{}={}", index + 1, name))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let legend = TextLayout {
+            x: 0,
+            y: current_y,
+            text,
+        };
+        current_y += 1 + config.edge_spacing;
+        legend
+    });
 
-    let mut horizontal_positions = Vec::new();
+    (title, legend, current_y)
+}
 
-    // Minus 1 because of 0-base index. The position of the left margin should be at 0, not at 1
-    let mut current_position = MARGIN_LEFT - 1;
+/// Width of the widest text drawn in a box centered on each participant: its label or any
+/// note over it. Note boxes use the same padding and border as participant boxes.
+fn calculate_content_widths(sequence_diagram: &SequenceDiagram, labels: &[String]) -> Vec<usize> {
+    sequence_diagram
+        .participants
+        .iter()
+        .zip(labels)
+        .map(|(participant, label)| {
+            sequence_diagram
+                .notes
+                .iter()
+                .filter(|note| &note.participant == participant)
+                .map(|note| note.text.width())
+                .fold(label.width(), max)
+        })
+        .collect()
+}
 
-    if let Some(name) = parts.get(0) {
-        current_position += BORDER_WIDTH + PARTICIPANT_PADDING_X + name.width() / 2;
-        horizontal_positions.push(current_position);
+fn calculate_horizontal_positions(
+    sequence_diagram: &SequenceDiagram,
+    content_widths: &[usize],
+    config: &SequenceLayoutConfig,
+) -> Vec<usize> {
+    let mut message_widths = calculate_message_widths(sequence_diagram, config);
+    // The arrow of a creating edge stops at the box of the created participant, so its message
+    // also has to clear half of the box
+    let indices = participant_indices(sequence_diagram);
+    for lifetime in &sequence_diagram.lifetimes {
+        let Some(edge) = lifetime
+            .created_by
+            .and_then(|index| sequence_diagram.edges.get(index))
+        else {
+            continue;
+        };
+        let (Some(&from), Some(&to), Some(&created)) = (
+            indices.get(edge.from.as_str()),
+            indices.get(edge.to.as_str()),
+            indices.get(lifetime.participant.as_str()),
+        ) else {
+            continue;
+        };
+        if let Some(width) = message_widths.get_mut(&(from.min(to), from.max(to))) {
+            *width += config.box_width(content_widths[created]).div_ceil(2);
+        }
     }
 
-    for i in 1..parts.len() {
-        let left_part = &parts[i - 1];
-        let right_part = &parts[i];
+    // Participant indices from left to right
+    let mut order: Vec<usize> = (0..sequence_diagram.participants.len()).collect();
+    if config.rtl {
+        order.reverse();
+    }
 
-        let space_without_message = left_part.width() / 2
-            + (2 * PARTICIPANT_PADDING_X)
-            + (2 * BORDER_WIDTH)
-            + (right_part.width() + 1) / 2; // Round up
+    let mut horizontal_positions = vec![0; order.len()];
+
+    let mut current_position = config.margins.left;
+
+    if let Some(&first) = order.first() {
+        current_position +=
+            config.border_width + config.participant_padding_x + content_widths[first].div_ceil(2);
+        // Minus 1 because of 0-base index. The position of the left margin should be at 0, not at 1
+        current_position = current_position.saturating_sub(1);
+        horizontal_positions[first] = current_position;
+    }
 
-        let space_with_message = max_edge_width(&sequence_diagram.edges, left_part, right_part);
+    for pair in order.windows(2) {
+        let (left, right) = (pair[0], pair[1]);
+        let space_without_message = content_widths[left] / 2
+            + (2 * config.participant_padding_x)
+            + (2 * config.border_width)
+            + content_widths[right].div_ceil(2); // Round up
+
+        let space_with_message = message_widths
+            .get(&(left.min(right), left.max(right)))
+            .copied()
+            .unwrap_or(0);
 
         let space = max(space_without_message, space_with_message + 1); // Plus 1 for space_with_message because it does not include position of next participant
 
         current_position += space;
-        horizontal_positions.push(current_position);
+        horizontal_positions[right] = current_position;
     }
 
     horizontal_positions
 }
 
-fn max_edge_width(edges: &Vec<Edge>, part1: &Participant, part2: &Participant) -> usize {
-    let mut max_width = 0;
+fn participant_indices(sequence_diagram: &SequenceDiagram) -> HashMap<&str, usize> {
+    sequence_diagram
+        .participants
+        .iter()
+        .enumerate()
+        .map(|(index, participant)| (participant.as_str(), index))
+        .collect()
+}
 
-    for edge in edges {
-        if (&edge.from == part1 && &edge.to == part2) || (&edge.from == part2 && &edge.to == part1)
-        {
-            if let Some(msg) = &edge.message {
-                max_width = max(max_width, msg.width() + MESSAGE_PADDING_X * 2);
-            }
+/// Width of the widest message (including padding) between each pair of participants, keyed by
+/// their indices with the smaller index first
+fn calculate_message_widths(
+    sequence_diagram: &SequenceDiagram,
+    config: &SequenceLayoutConfig,
+) -> HashMap<(usize, usize), usize> {
+    let indices = participant_indices(sequence_diagram);
+    let mut message_widths = HashMap::new();
+
+    for edge in &sequence_diagram.edges {
+        let Some(message) = &edge.message else {
+            continue;
+        };
+        let (Some(&from), Some(&to)) = (
+            indices.get(edge.from.as_str()),
+            indices.get(edge.to.as_str()),
+        ) else {
+            continue;
+        };
+
+        let mut width = message.width() + config.message_padding_x * 2;
+        if edge.style.unwrap_or_default().label_position == LabelPosition::Centered {
+            // Keep some of the arrow visible on both sides of the message
+            width += CENTERED_MESSAGE_MARGIN * 2;
         }
+        let max_width = message_widths
+            .entry((from.min(to), from.max(to)))
+            .or_insert(0);
+        *max_width = max(*max_width, width);
     }
 
-    max_width
+    message_widths
 }
 
 fn calculate_participant_layouts(
     total_height: usize,
     sequence_diagram: &SequenceDiagram,
-    positions: &Vec<usize>,
+    labels: &[String],
+    positions: &[usize],
+    top_y: usize,
+    lifetime_rows: &LifetimeRows,
+    config: &SequenceLayoutConfig,
 ) -> Vec<ParticipantLayout> {
     let mut part_layouts = Vec::new();
 
     for (index, name) in sequence_diagram.participants.iter().enumerate() {
+        if config.hide_participants.contains(name) {
+            continue;
+        }
         let center_x = positions[index];
+        let label = &labels[index];
 
         part_layouts.push(ParticipantLayout {
             name: name.clone(),
+            label: label.clone(),
+            kind: sequence_diagram.kind(name),
             center_x,
-            top_box_y: MARGIN_TOP,
-            bottom_box_y: total_height - MARGIN_BOTTOM,
-            width: name.width() + PARTICIPANT_PADDING_X * 2 + BORDER_WIDTH * 2,
+            top_box_y: lifetime_rows.created.get(&index).copied().unwrap_or(top_y),
+            bottom_box_y: (!lifetime_rows.destroyed.contains_key(&index))
+                .then_some(total_height - config.margins.bottom),
+            width: config.box_width(label.width()),
+            height: config.participant_height,
         });
     }
 
     part_layouts
 }
 
-fn calculate_edge_layouts(
+/// Lays out edges and notes from top to bottom. Returns the layouts and the first row after
+/// the last element.
+fn calculate_edge_and_note_layouts(
     sequence_diagram: &SequenceDiagram,
-    positions: &Vec<usize>,
-) -> Vec<EdgeLayout> {
+    labels: &[String],
+    positions: &[usize],
+    top_y: usize,
+    config: &SequenceLayoutConfig,
+) -> (Vec<EdgeLayout>, Vec<NoteLayout>, MarkerRows, usize) {
     let mut edge_layouts = Vec::new();
-    let mut current_y = MARGIN_TOP + PARTICIPANT_HEIGHT + EDGE_SPACING;
+    let mut note_layouts = Vec::new();
+    let mut marker_rows = MarkerRows {
+        blocks: sequence_diagram
+            .blocks
+            .iter()
+            .map(|_| BlockRows::default())
+            .collect(),
+        spacer_ys: Vec::new(),
+        ref_ys: Vec::new(),
+        lifetimes: LifetimeRows::default(),
+    };
+    let mut markers = markers(sequence_diagram).into_iter().peekable();
+    let mut current_y = top_y + config.participant_height + config.edge_spacing;
+    let indices = participant_indices(sequence_diagram);
+    // Participants by the index of their creating edge
+    let creations: HashMap<usize, usize> = sequence_diagram
+        .lifetimes
+        .iter()
+        .filter_map(|lifetime| {
+            Some((
+                lifetime.created_by?,
+                *indices.get(lifetime.participant.as_str())?,
+            ))
+        })
+        .collect();
+
+    for edge_index in 0..=sequence_diagram.edges.len() {
+        for (note_index, note) in sequence_diagram.notes.iter().enumerate() {
+            if note.position != edge_index {
+                continue;
+            }
+            // After the markers recorded before the note, whatever their spacer count
+            let note_position = Position {
+                edges: edge_index,
+                notes: note_index,
+                spacers: usize::MAX,
+                refs: usize::MAX,
+            };
+            while let Some(marker) = markers.next_if(|marker| marker.position <= note_position) {
+                current_y = layout_marker(
+                    marker,
+                    current_y,
+                    &sequence_diagram.blocks,
+                    &mut marker_rows,
+                    config,
+                );
+            }
+
+            let Some(&index) = indices.get(note.participant.as_str()) else {
+                continue;
+            };
+            note_layouts.push(NoteLayout {
+                center_x: positions[index],
+                y: current_y,
+                width: config.box_width(note.text.width()),
+                text: note.text.clone(),
+            });
+            current_y += config.note_height + config.edge_spacing;
+        }
+
+        while let Some(marker) = markers.next_if(|marker| marker.position.edges <= edge_index) {
+            current_y = layout_marker(
+                marker,
+                current_y,
+                &sequence_diagram.blocks,
+                &mut marker_rows,
+                config,
+            );
+        }
+
+        let Some(edge) = sequence_diagram.edges.get(edge_index) else {
+            break;
+        };
 
-    for edge in &sequence_diagram.edges {
         let from_part = &edge.from;
         let to_part = &edge.to;
 
-        let from_index = sequence_diagram
-            .participants
-            .iter()
-            .position(|p| p == from_part)
-            .unwrap();
-        let to_index = sequence_diagram
-            .participants
-            .iter()
-            .position(|p| p == to_part)
-            .unwrap();
+        let (Some(&from_index), Some(&to_index)) = (
+            indices.get(from_part.as_str()),
+            indices.get(to_part.as_str()),
+        ) else {
+            continue;
+        };
 
-        let arrow_direction = if from_index < to_index {
+        let arrow_direction = if positions[from_index] < positions[to_index] {
             ArrowDirection::Right
         } else {
             ArrowDirection::Left
         };
 
-        let (start_x, end_x) = match arrow_direction {
-            ArrowDirection::Right => (positions[from_index] + 1, positions[to_index] - 1),
-            ArrowDirection::Left => (positions[from_index] - 1, positions[to_index] + 1),
+        let (mut start_x, mut end_x) = match arrow_direction {
+            ArrowDirection::Right => (
+                positions[from_index] + 1,
+                positions[to_index].saturating_sub(1),
+            ),
+            ArrowDirection::Left => (
+                positions[from_index].saturating_sub(1),
+                positions[to_index] + 1,
+            ),
         };
 
+        // The box of a created participant starts on this row, with the arrow pointing at its
+        // middle row
+        let created = creations.get(&edge_index).copied().filter(|&index| {
+            !config
+                .hide_participants
+                .contains(&sequence_diagram.participants[index])
+        });
+        if let Some(created) = created {
+            let width = config.box_width(labels[created].width());
+            let left_x = (positions[created] + 1).saturating_sub(width.div_ceil(2));
+            let right_x = left_x + width - 1;
+            let (near_x, far_x) = match arrow_direction {
+                ArrowDirection::Right => (left_x.saturating_sub(1), right_x + 1),
+                ArrowDirection::Left => (right_x + 1, left_x.saturating_sub(1)),
+            };
+            // A participant created by a message to itself keeps the arrow on its lifeline
+            if from_index != to_index {
+                if created == to_index {
+                    end_x = near_x;
+                } else {
+                    start_x = far_x;
+                }
+            }
+            marker_rows.lifetimes.created.insert(created, current_y);
+        }
+        let arrow_row = if created.is_some() {
+            current_y + 1
+        } else {
+            current_y
+        };
+
+        let label_position = edge.style.unwrap_or_default().label_position;
+        let (arrow_y, message_y) = match (&edge.message, label_position) {
+            (None, _) => (arrow_row, None),
+            (Some(_), LabelPosition::Above) => (current_y + 1, Some(current_y)),
+            (Some(_), LabelPosition::Below) => (arrow_row, Some(arrow_row + 1)),
+            (Some(_), LabelPosition::Centered) => (arrow_row, Some(arrow_row)),
+        };
+        let mut last_y = max(arrow_y, message_y.unwrap_or(0));
+        if created.is_some() {
+            last_y = max(last_y, current_y + config.participant_height - 1);
+        }
+
         edge_layouts.push(EdgeLayout {
             start_x,
             end_x,
             y: current_y,
+            arrow_y,
+            message_y,
             direction: arrow_direction,
             message: edge.message.clone(),
+            style: edge.style,
+        });
+
+        current_y = last_y + config.edge_spacing + 1;
+    }
+
+    // Markers past the last edge and note
+    for marker in markers {
+        current_y = layout_marker(
+            marker,
+            current_y,
+            &sequence_diagram.blocks,
+            &mut marker_rows,
+            config,
+        );
+    }
+
+    (edge_layouts, note_layouts, marker_rows, current_y)
+}
+
+/// Rows of the borders and dividers of a block
+#[derive(Debug, Default)]
+struct BlockRows {
+    y_start: usize,
+    y_end: usize,
+    divider_ys: Vec<usize>,
+}
+
+/// Rows where the participants created or destroyed partway through start and end, by
+/// participant index
+#[derive(Debug, Default)]
+struct LifetimeRows {
+    /// Top row of the box of a created participant
+    created: HashMap<usize, usize>,
+    /// Row of the cross ending the lifeline of a destroyed participant
+    destroyed: HashMap<usize, usize>,
+}
+
+/// Rows given to the markers and to the boxes of created participants
+#[derive(Debug)]
+struct MarkerRows {
+    blocks: Vec<BlockRows>,
+    spacer_ys: Vec<usize>,
+    ref_ys: Vec<usize>,
+    lifetimes: LifetimeRows,
+}
+
+#[derive(Debug)]
+enum MarkerKind {
+    /// Top border of a block
+    Start(usize),
+    /// A block and the index into its dividers
+    Divider(usize, usize),
+    /// Bottom border of a block
+    End(usize),
+    Spacer,
+    Ref,
+    /// End of the lifeline of a participant, by participant index
+    Destroy(usize),
+}
+
+/// Rows placed between the edges and notes, such as block borders
+#[derive(Debug)]
+struct Marker {
+    position: Position,
+    kind: MarkerKind,
+}
+
+/// The block borders, block dividers, spacers, refs and destroyed lifelines in drawing order. At
+/// the same position, the dividers and bottom borders come innermost block first, then blocks are
+/// opened outermost first, then comes the spacer or ref. Markers of an empty region or block stay
+/// right after the block's top border.
+fn markers(sequence_diagram: &SequenceDiagram) -> Vec<Marker> {
+    let blocks = &sequence_diagram.blocks;
+    let mut markers = Vec::new();
+    for (block_index, block) in blocks.iter().enumerate() {
+        markers.push(Marker {
+            position: block.start(),
+            kind: MarkerKind::Start(block_index),
+        });
+        for (divider, &position) in block.dividers().iter().enumerate() {
+            markers.push(Marker {
+                position,
+                kind: MarkerKind::Divider(block_index, divider),
+            });
+        }
+        markers.push(Marker {
+            position: block.end(),
+            kind: MarkerKind::End(block_index),
+        });
+    }
+    for &position in &sequence_diagram.spacers {
+        markers.push(Marker {
+            position,
+            kind: MarkerKind::Spacer,
         });
+    }
+    for ref_block in &sequence_diagram.refs {
+        markers.push(Marker {
+            position: ref_block.position,
+            kind: MarkerKind::Ref,
+        });
+    }
+    let indices = participant_indices(sequence_diagram);
+    for lifetime in &sequence_diagram.lifetimes {
+        if let (Some(position), Some(&index)) = (
+            lifetime.destroyed_at,
+            indices.get(lifetime.participant.as_str()),
+        ) {
+            markers.push(Marker {
+                position,
+                kind: MarkerKind::Destroy(index),
+            });
+        }
+    }
+
+    markers.sort_by_key(|marker| {
+        let at_start = |block: usize| marker.position == blocks[block].start();
+        let order = match marker.kind {
+            MarkerKind::Start(block) => (2, block, 0),
+            MarkerKind::Divider(block, divider) if at_start(block) => (2, block, 1 + divider),
+            MarkerKind::End(block) if at_start(block) => (2, block, usize::MAX),
+            MarkerKind::Divider(block, divider) => (1, usize::MAX - block, divider),
+            MarkerKind::End(block) => (1, usize::MAX - block, usize::MAX),
+            MarkerKind::Spacer | MarkerKind::Ref | MarkerKind::Destroy(_) => (3, 0, 0),
+        };
+        (marker.position, order)
+    });
+    markers
+}
+
+/// Gives the marker its rows and returns the next free row
+fn layout_marker(
+    marker: Marker,
+    current_y: usize,
+    blocks: &[Block],
+    marker_rows: &mut MarkerRows,
+    config: &SequenceLayoutConfig,
+) -> usize {
+    let row = 1 + config.edge_spacing;
+    match marker.kind {
+        MarkerKind::Start(block) => {
+            let y = current_y + block_margin(&blocks[block]);
+            marker_rows.blocks[block].y_start = y;
+            y + row
+        }
+        MarkerKind::Divider(block, _) => {
+            marker_rows.blocks[block].divider_ys.push(current_y);
+            current_y + row
+        }
+        MarkerKind::End(block) => {
+            marker_rows.blocks[block].y_end = current_y;
+            current_y + row + block_margin(&blocks[block])
+        }
+        MarkerKind::Spacer => {
+            marker_rows.spacer_ys.push(current_y);
+            current_y + config.spacer_height
+        }
+        MarkerKind::Ref => {
+            marker_rows.ref_ys.push(current_y);
+            current_y + config.note_height + config.edge_spacing
+        }
+        MarkerKind::Destroy(participant) => {
+            marker_rows
+                .lifetimes
+                .destroyed
+                .insert(participant, current_y);
+            current_y + row
+        }
+    }
+}
+
+/// Frames each block around the lifelines of its edges and the notes inside it, and around the
+/// blocks nested in it. A block without edges or notes spans all participants.
+fn calculate_block_layouts(
+    sequence_diagram: &SequenceDiagram,
+    positions: &[usize],
+    block_rows: Vec<BlockRows>,
+    config: &SequenceLayoutConfig,
+) -> Vec<BlockLayout> {
+    let indices = participant_indices(sequence_diagram);
+    let block_count = sequence_diagram.blocks.len();
+    let mut block_layouts: Vec<Option<BlockLayout>> = (0..block_count).map(|_| None).collect();
 
-        current_y += EDGE_SPACING + 1;
-        if edge.message.is_some() {
-            current_y += 1;
+    // Inner blocks come after their enclosing block, so lay them out first
+    for (block_index, (block, rows)) in sequence_diagram
+        .blocks
+        .iter()
+        .zip(block_rows)
+        .enumerate()
+        .rev()
+    {
+        let (start, end) = (block.start(), block.end());
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+
+        for edge in sequence_diagram
+            .edges
+            .iter()
+            .take(end.edges)
+            .skip(start.edges)
+        {
+            for name in [&edge.from, &edge.to] {
+                if let Some(&index) = indices.get(name.as_str()) {
+                    spans.push((positions[index], positions[index]));
+                }
+            }
+        }
+        for note in sequence_diagram
+            .notes
+            .iter()
+            .take(end.notes)
+            .skip(start.notes)
+        {
+            if let Some(&index) = indices.get(note.participant.as_str()) {
+                let width = config.box_width(note.text.width());
+                let left_x = (positions[index] + 1).saturating_sub(width.div_ceil(2));
+                spans.push((left_x, left_x + width.saturating_sub(1)));
+            }
         }
+        for ref_block in &sequence_diagram.refs {
+            if start <= ref_block.position && ref_block.position < end {
+                spans.extend(ref_span(ref_block, &indices, positions));
+            }
+        }
+        if spans.is_empty() {
+            spans.extend(positions.iter().map(|&position| (position, position)));
+        }
+
+        let mut x_start = spans
+            .iter()
+            .map(|span| span.0)
+            .min()
+            .map_or(config.margins.left, |x| x.saturating_sub(BLOCK_PADDING_X));
+        let mut x_end = spans
+            .iter()
+            .map(|span| span.1)
+            .max()
+            .map_or(x_start, |x| x + BLOCK_PADDING_X);
+
+        for (inner_block, inner) in sequence_diagram.blocks[block_index + 1..]
+            .iter()
+            .zip(&block_layouts[block_index + 1..])
+        {
+            if let Some(inner) = inner
+                && inner_block.start() >= start
+                && inner_block.end() <= end
+            {
+                x_start = x_start.min(inner.x_start.saturating_sub(1));
+                x_end = max(x_end, inner.x_end + 1);
+            }
+        }
+
+        let (label, divider_labels) = block_labels(block);
+        let dividers: Vec<DividerLayout> = rows
+            .divider_ys
+            .into_iter()
+            .zip(divider_labels)
+            .map(|(y, label)| DividerLayout { y, label })
+            .collect();
+
+        // Room for `┌─ label ─┐`
+        for text in std::iter::once(&label).chain(dividers.iter().map(|divider| &divider.label)) {
+            if !text.is_empty() {
+                x_end = max(x_end, x_start + text.width() + 5);
+            }
+        }
+
+        block_layouts[block_index] = Some(BlockLayout {
+            kind: block_kind(block),
+            x_start,
+            x_end,
+            y_start: rows.y_start,
+            y_end: rows.y_end,
+            label,
+            dividers,
+        });
     }
 
-    edge_layouts
+    block_layouts.into_iter().flatten().collect()
 }
 
-fn calculate_lifeline_layouts(total_height: usize, positions: &Vec<usize>) -> Vec<LifelineLayout> {
+/// Centers each ref box under the lifelines it spans, wide enough for the tag and the label
+fn calculate_ref_layouts(
+    sequence_diagram: &SequenceDiagram,
+    positions: &[usize],
+    ref_ys: Vec<usize>,
+) -> Vec<RefLayout> {
+    let indices = participant_indices(sequence_diagram);
+    sequence_diagram
+        .refs
+        .iter()
+        .zip(ref_ys)
+        .filter_map(|(ref_block, y)| {
+            let (x_start, x_end) = ref_span(ref_block, &indices, positions)?;
+            Some(RefLayout {
+                x_start,
+                x_end,
+                y,
+                label: ref_block.label.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Columns of the ref box. `None` when none of its participants is known.
+fn ref_span(
+    ref_block: &RefBlock,
+    indices: &HashMap<&str, usize>,
+    positions: &[usize],
+) -> Option<(usize, usize)> {
+    let lifelines: Vec<usize> = ref_block
+        .participants
+        .iter()
+        .filter_map(|name| indices.get(name.as_str()))
+        .map(|&index| positions[index])
+        .collect();
+    let (min_x, max_x) = (*lifelines.iter().min()?, *lifelines.iter().max()?);
+
+    // Widened evenly on both sides when `│ ref │ label │` needs more room
+    let padded_width = max_x - min_x + 2 * REF_PADDING_X + 1;
+    let width = max(padded_width, REF_TAG_WIDTH + ref_block.label.width() + 3);
+    let x_start = min_x.saturating_sub(REF_PADDING_X + (width - padded_width) / 2);
+    Some((x_start, x_start + width - 1))
+}
+
+/// Places the `┈` line of each spacer on its middle row, from the first lifeline to the last
+fn calculate_spacer_layouts(
+    spacer_ys: Vec<usize>,
+    positions: &[usize],
+    config: &SequenceLayoutConfig,
+) -> Vec<SpacerLayout> {
+    let lifelines = positions.iter().min().zip(positions.iter().max());
+    spacer_ys
+        .into_iter()
+        .map(|y| SpacerLayout {
+            y,
+            height: config.spacer_height,
+            divider: lifelines
+                .filter(|_| config.spacer_divider && config.spacer_height > 0)
+                .map(|(&x_start, &x_end)| (x_start, y + (config.spacer_height - 1) / 2, x_end)),
+        })
+        .collect()
+}
+
+/// The text in the top border and the labels of the dividers
+fn block_labels(block: &Block) -> (String, Vec<String>) {
+    let (keyword, label, divider_labels) = match block {
+        Block::Par(par) => ("par", &par.label, par.and_labels.clone()),
+        Block::Critical(critical) => ("critical", &critical.label, Vec::new()),
+        Block::Break(break_block) => ("break", &break_block.condition, Vec::new()),
+        Block::Group(group) => ("group", &group.label, Vec::new()),
+    };
+    if label.is_empty() {
+        (keyword.to_string(), divider_labels)
+    } else if let Block::Group(_) = block {
+        // Named by its label alone
+        (label.clone(), divider_labels)
+    } else {
+        (format!("{keyword} {label}"), divider_labels)
+    }
+}
+
+fn block_kind(block: &Block) -> BlockKind {
+    match block {
+        Block::Par(_) => BlockKind::Par,
+        Block::Critical(_) => BlockKind::Critical,
+        Block::Break(_) => BlockKind::Break,
+        Block::Group(_) => BlockKind::Group,
+    }
+}
+
+/// Blank rows kept above and below the block, on top of the usual edge spacing
+fn block_margin(block: &Block) -> usize {
+    match block {
+        Block::Par(_) | Block::Break(_) | Block::Group(_) => 0,
+        Block::Critical(_) => CRITICAL_BLOCK_MARGIN,
+    }
+}
+
+/// Lifelines run from the top box to the bottom box, or to the cross of a destroyed
+/// participant
+fn calculate_lifeline_layouts(
+    total_height: usize,
+    sequence_diagram: &SequenceDiagram,
+    positions: &[usize],
+    top_y: usize,
+    lifetime_rows: &LifetimeRows,
+    config: &SequenceLayoutConfig,
+) -> Vec<LifelineLayout> {
     let mut lifeline_layouts = Vec::new();
 
-    for &position in positions {
+    for (index, name) in sequence_diagram.participants.iter().enumerate() {
+        if config.hide_participants.contains(name) {
+            continue;
+        }
+        let top_box_y = lifetime_rows.created.get(&index).copied().unwrap_or(top_y);
+        let destroyed_y = lifetime_rows.destroyed.get(&index).copied();
+
         lifeline_layouts.push(LifelineLayout {
-            start_y: MARGIN_TOP + PARTICIPANT_HEIGHT,
-            end_y: total_height - MARGIN_BOTTOM - PARTICIPANT_HEIGHT - EDGE_SPACING,
-            x: position,
+            start_y: top_box_y + config.participant_height,
+            end_y: destroyed_y.unwrap_or(
+                total_height
+                    - config.margins.bottom
+                    - config.participant_height
+                    - config.edge_spacing,
+            ),
+            x: positions[index],
+            destroyed: destroyed_y.is_some(),
         });
     }
 
     lifeline_layouts
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::rendered_bounds;
+    use crate::renderer::render;
+
+    use super::*;
+
+    #[test]
+    fn test_note_only_participant_has_lifeline() {
+        let input = "\
+Client -> Server: GET /api/data
+note over Auditor: Records the request
+Client <- Server: JSON response";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+
+        assert_eq!(layout.participant_layouts.len(), 3);
+        assert_eq!(layout.participant_layouts[2].name, "Auditor");
+        assert_eq!(layout.lifeline_layouts.len(), 3);
+        assert_eq!(
+            layout.lifeline_layouts[2].x,
+            layout.participant_layouts[2].center_x
+        );
+
+        // The note sits between the two edges
+        let note = &layout.note_layouts[0];
+        assert_eq!(note.center_x, layout.participant_layouts[2].center_x);
+        assert_eq!(note.y, layout.edge_layouts[0].y + 2 + EDGE_SPACING);
+        assert_eq!(
+            layout.edge_layouts[1].y,
+            note.y + NOTE_HEIGHT + EDGE_SPACING
+        );
+    }
+
+    #[test]
+    fn test_edge_spacing_shifts_edges() {
+        let input = "\
+Client -> Server
+Server -> Database
+Server <- Database
+Client <- Server";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+
+        let default_layout = calculate_sequence_layout(&diagram);
+        let config = SequenceLayoutConfig {
+            edge_spacing: 3,
+            ..SequenceLayoutConfig::default()
+        };
+        let spaced_layout = calculate_sequence_layout_with_config(&diagram, &config);
+
+        // Every edge is preceded by one more spacing gap than the edge before it
+        for (index, (default_edge, spaced_edge)) in default_layout
+            .edge_layouts
+            .iter()
+            .zip(&spaced_layout.edge_layouts)
+            .enumerate()
+        {
+            assert_eq!(
+                spaced_edge.y - default_edge.y,
+                (config.edge_spacing - EDGE_SPACING) * (index + 1)
+            );
+        }
+        assert_eq!(
+            spaced_layout.height - default_layout.height,
+            (config.edge_spacing - EDGE_SPACING) * 5
+        );
+    }
+
+    const WIDE_DIAGRAM: &str = "\
+Client -> Server: send the first request message
+Server -> Database: store the record in the db
+Database -> Replica: replicate";
+
+    fn auto_width_config(terminal_width: fn() -> Option<usize>) -> SequenceLayoutConfig {
+        SequenceLayoutConfig {
+            auto_width: true,
+            terminal_width,
+            ..SequenceLayoutConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_auto_width_fits_terminal() {
+        let diagram = parse(tokenize(WIDE_DIAGRAM).unwrap()).unwrap();
+        let default_layout = calculate_sequence_layout(&diagram);
+        assert!(default_layout.width > 80 && default_layout.width <= 120);
+
+        // Already fits, so the layout is unchanged
+        let layout =
+            calculate_sequence_layout_with_config(&diagram, &auto_width_config(|| Some(120)));
+        assert_eq!(layout.width, default_layout.width);
+        assert!(layout.warnings.is_empty());
+
+        let layout =
+            calculate_sequence_layout_with_config(&diagram, &auto_width_config(|| Some(80)));
+        assert!(layout.width <= 80);
+        assert!(layout.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_auto_width_too_narrow_terminal() {
+        let diagram = parse(tokenize(WIDE_DIAGRAM).unwrap()).unwrap();
+
+        let layout =
+            calculate_sequence_layout_with_config(&diagram, &auto_width_config(|| Some(40)));
+        assert!(layout.width > 40);
+        assert_eq!(layout.warnings.len(), 1);
+        assert!(layout.warnings[0].contains("terminal width of 40"));
+    }
+
+    #[test]
+    fn test_auto_width_unknown_terminal() {
+        let diagram = parse(tokenize(WIDE_DIAGRAM).unwrap()).unwrap();
+        let default_layout = calculate_sequence_layout(&diagram);
+
+        let layout = calculate_sequence_layout_with_config(&diagram, &auto_width_config(|| None));
+        assert_eq!(layout.width, default_layout.width);
+        assert!(layout.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_max_width() {
+        let diagram = parse(tokenize(WIDE_DIAGRAM).unwrap()).unwrap();
+        let config = SequenceLayoutConfig {
+            max_width: Some(80),
+            ..SequenceLayoutConfig::default()
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert!(layout.width <= 80);
+        assert!(layout.warnings.is_empty());
+
+        // A narrower terminal wins
+        let config = SequenceLayoutConfig {
+            max_width: Some(80),
+            ..auto_width_config(|| Some(40))
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert!(layout.warnings[0].contains("terminal width of 40"));
+
+        let config = SequenceLayoutConfig {
+            max_width: Some(40),
+            ..auto_width_config(|| Some(120))
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert!(layout.warnings[0].contains("maximum width of 40"));
+    }
+
+    #[test]
+    fn test_title_shifts_diagram_down() {
+        let input = "Client -> Server: GET /api/data";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let plain_layout = calculate_sequence_layout(&diagram);
+
+        let input = "title A rather long diagram title\nClient -> Server: GET /api/data";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+
+        let title = layout.title.unwrap();
+        assert_eq!(title.y, MARGIN_TOP);
+        // The diagram is widened to fit the title, which is centered
+        assert_eq!(
+            layout.width,
+            MARGIN_LEFT + title.text.width() + MARGIN_RIGHT
+        );
+        assert_eq!(title.x, MARGIN_LEFT);
+
+        let shift = 1 + EDGE_SPACING;
+        assert_eq!(layout.height, plain_layout.height + shift);
+        assert_eq!(
+            layout.participant_layouts[0].top_box_y,
+            plain_layout.participant_layouts[0].top_box_y + shift
+        );
+        assert_eq!(
+            layout.edge_layouts[0].y,
+            plain_layout.edge_layouts[0].y + shift
+        );
+    }
+
+    #[test]
+    fn test_use_indices() {
+        let input = "\
+Client -> Server
+Server -> Database
+Database -> Server
+Server -> Client";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let name_layout = calculate_sequence_layout(&diagram);
+
+        let config = SequenceLayoutConfig {
+            use_indices: true,
+            ..SequenceLayoutConfig::default()
+        };
+        let index_layout = calculate_sequence_layout_with_config(&diagram, &config);
+
+        let labels: Vec<&str> = index_layout
+            .participant_layouts
+            .iter()
+            .map(|layout| layout.label.as_str())
+            .collect();
+        assert_eq!(labels, ["1", "2", "3"]);
+        assert!(index_layout.width < name_layout.width);
+        // The first box starts at the left margin with an odd label width too, so its center
+        // rounds half the label width up
+        let first = &index_layout.participant_layouts[0];
+        assert_eq!(first.center_x + 1 - first.width.div_ceil(2), MARGIN_LEFT);
+        assert!(
+            index_layout.participant_layouts[2].center_x
+                < name_layout.participant_layouts[2].center_x
+        );
+
+        let legend = index_layout.legend.unwrap();
+        assert_eq!(legend.text, "1=Client 2=Server 3=Database");
+        assert_eq!(legend.y, MARGIN_TOP);
+        assert!(name_layout.legend.is_none());
+    }
+
+    #[test]
+    fn test_rtl_mirrors_participant_order() {
+        let input = "\
+Client -> Server: Login(username)
+Client <- Server: Token";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let ltr_layout = calculate_sequence_layout(&diagram);
+
+        let config = SequenceLayoutConfig {
+            rtl: true,
+            ..SequenceLayoutConfig::default()
+        };
+        let rtl_layout = calculate_sequence_layout_with_config(&diagram, &config);
+
+        let centers = |layout: &SequenceDiagramLayout| -> (usize, usize) {
+            (
+                layout.participant_layouts[0].center_x,
+                layout.participant_layouts[1].center_x,
+            )
+        };
+        let (ltr_client, ltr_server) = centers(&ltr_layout);
+        let (rtl_client, rtl_server) = centers(&rtl_layout);
+        assert!(ltr_client < ltr_server);
+        assert!(rtl_client > rtl_server);
+        assert_eq!(rtl_layout.width, ltr_layout.width);
+
+        // The request still points from Client to Server, which is now leftwards
+        assert_eq!(rtl_layout.edge_layouts[0].direction, ArrowDirection::Left);
+        assert_eq!(rtl_layout.edge_layouts[1].direction, ArrowDirection::Right);
+
+        let output = render(&rtl_layout);
+        let header = output.lines().nth(MARGIN_TOP + 1).unwrap();
+        assert!(header.find("Server").unwrap() < header.find("Client").unwrap());
+    }
+
+    #[test]
+    fn test_order_hint_places_participants() {
+        let input = "\
+order: Bob, Alice
+Alice -> Bob: Hello";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+
+        let bob = &layout.participant_layouts[0];
+        let alice = &layout.participant_layouts[1];
+        assert_eq!((bob.name.as_str(), alice.name.as_str()), ("Bob", "Alice"));
+        assert!(bob.center_x < alice.center_x);
+        assert_eq!(layout.edge_layouts[0].direction, ArrowDirection::Left);
+    }
+
+    #[test]
+    fn test_max_participant_display_len() {
+        let input = "\
+AuthenticationService -> Db
+Db -> ShortName";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let labels = |layout: &SequenceDiagramLayout| -> Vec<String> {
+            layout
+                .participant_layouts
+                .iter()
+                .map(|participant| participant.label.clone())
+                .collect()
+        };
+
+        let full_layout = calculate_sequence_layout(&diagram);
+        assert_eq!(
+            labels(&full_layout),
+            ["AuthenticationService", "Db", "ShortName"]
+        );
+
+        let config = SequenceLayoutConfig {
+            max_participant_display_len: Some(10),
+            ..SequenceLayoutConfig::default()
+        };
+        let truncated_layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert_eq!(labels(&truncated_layout), ["Authentic…", "Db", "ShortName"]);
+        assert_eq!(
+            truncated_layout.participant_layouts[0].name,
+            "AuthenticationService"
+        );
+        assert!(truncated_layout.width < full_layout.width);
+        let output = render(&truncated_layout);
+        assert!(output.contains("Authentic…"));
+        assert!(!output.contains("AuthenticationService"));
+
+        // Labels stay within the limit even when only the `…` or nothing fits
+        assert_eq!(truncate_name("Db", 1), "…");
+        let config = SequenceLayoutConfig {
+            max_participant_display_len: Some(0),
+            ..SequenceLayoutConfig::default()
+        };
+        let empty_layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert_eq!(labels(&empty_layout), ["", "", ""]);
+    }
+
+    #[test]
+    fn test_hide_participants() {
+        let input = "\
+Client -> Server: GET /api/data
+Server -> Logger: Access log
+note over Logger: Rotated daily
+Client <- Server: JSON response";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let full_layout = calculate_sequence_layout(&diagram);
+
+        let config = SequenceLayoutConfig {
+            hide_participants: vec!["Logger".to_string()],
+            ..SequenceLayoutConfig::default()
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+
+        let names: Vec<&str> = layout
+            .participant_layouts
+            .iter()
+            .map(|participant| participant.name.as_str())
+            .collect();
+        assert_eq!(names, ["Client", "Server"]);
+        assert_eq!(layout.lifeline_layouts.len(), 2);
+
+        // The edge still reaches the place of the hidden participant
+        assert_eq!(layout.edge_layouts.len(), 3);
+        assert_eq!(
+            layout.edge_layouts[1].end_x,
+            full_layout.edge_layouts[1].end_x
+        );
+        assert_eq!(layout.width, full_layout.width);
+        assert_eq!(layout.warnings, ["Note over hidden participant 'Logger'"]);
+
+        let output = render(&layout);
+        assert!(output.contains("Access log"));
+        assert!(!output.contains("Logger"));
+        assert!(full_layout.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_label_positions() {
+        let input = "\
+Client -> Server: Above
+Client -> Server: {below}Below
+Client -> Server: {centered}Centered
+Client -> Server";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let rows = |edge: &EdgeLayout| (edge.y, edge.arrow_y, edge.message_y);
+
+        let above = &layout.edge_layouts[0];
+        let y = above.y;
+        assert_eq!(rows(above), (y, y + 1, Some(y)));
+        // Edges with a message above or below take two rows, centered ones take one
+        let y = y + 2 + EDGE_SPACING;
+        assert_eq!(rows(&layout.edge_layouts[1]), (y, y, Some(y + 1)));
+        let y = y + 2 + EDGE_SPACING;
+        assert_eq!(rows(&layout.edge_layouts[2]), (y, y, Some(y)));
+        let y = y + 1 + EDGE_SPACING;
+        assert_eq!(rows(&layout.edge_layouts[3]), (y, y, None));
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[above.y].contains("Above"));
+        let below = &layout.edge_layouts[1];
+        assert!(lines[below.y].contains("──>"));
+        assert!(lines[below.y + 1].contains("Below"));
+        // The message covers the middle of the arrow, the arrowhead stays
+        let centered_row = lines[layout.edge_layouts[2].y];
+        assert!(centered_row.contains("─Centered─") && centered_row.contains("─>"));
+        assert_eq!(layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_par_block() {
+        let input = "\
+Client -> Server: login
+par Fetch data
+Server -> Database: SELECT
+and Notify
+Server -> Logger: log
+end
+Server -> Client: ok";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let row = 1 + EDGE_SPACING;
+
+        let [block] = &layout.block_layouts[..] else {
+            panic!("expected one block");
+        };
+        let edges = &layout.edge_layouts;
+        assert_eq!(block.y_start, edges[0].arrow_y + row);
+        assert_eq!(edges[1].y, block.y_start + row);
+        assert_eq!(block.dividers.len(), 1);
+        assert_eq!(block.dividers[0].y, edges[1].arrow_y + row);
+        assert_eq!(block.dividers[0].label, "Notify");
+        assert_eq!(edges[2].y, block.dividers[0].y + row);
+        assert_eq!(block.y_end, edges[2].arrow_y + row);
+        assert_eq!(edges[3].y, block.y_end + row);
+
+        // Around the lifelines of Server, Database and Logger, but not Client
+        let lifeline_x = |index: usize| layout.lifeline_layouts[index].x;
+        assert!(block.x_start > lifeline_x(0) && block.x_start < lifeline_x(1));
+        assert!(block.x_end > lifeline_x(3));
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[block.y_start].contains("┌─ par Fetch data ─"));
+        assert!(lines[block.y_start].contains("┼─┐"));
+        assert!(lines[block.dividers[0].y].contains("├┄ Notify ┄"));
+        assert!(lines[block.y_end].contains("└─┼"));
+        assert_eq!(layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_nested_blocks_and_notes() {
+        let input = "\
+par
+note over Client: first
+par Inner
+Client -> Server
+end
+and
+end";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let [outer, inner] = &layout.block_layouts[..] else {
+            panic!("expected two blocks");
+        };
+        let note = &layout.note_layouts[0];
+        let row = 1 + EDGE_SPACING;
+
+        assert_eq!(note.y, outer.y_start + row);
+        assert_eq!(inner.y_start, note.y + NOTE_HEIGHT + EDGE_SPACING);
+        // The inner block closes before the empty second region of the outer one starts
+        assert_eq!(outer.dividers[0].y, inner.y_end + row);
+        assert_eq!(outer.y_end, outer.dividers[0].y + row);
+        assert!(outer.x_start < inner.x_start && outer.x_end > inner.x_end);
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let input = "\
+group Checkout
+Client -> Server: order
+group Payment
+Server -> Bank: charge
+group
+Bank -> Server: log
+end
+end
+Server -> Client: receipt
+end";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let [outer, middle, inner] = &layout.block_layouts[..] else {
+            panic!("expected three blocks");
+        };
+        assert_eq!(outer.kind, BlockKind::Group);
+        assert_eq!(
+            [&outer.label, &middle.label, &inner.label],
+            ["Checkout", "Payment", "group"]
+        );
+
+        for (parent, child) in [(outer, middle), (middle, inner)] {
+            assert!(parent.x_start < child.x_start && child.x_end < parent.x_end);
+            assert!(parent.y_start < child.y_start && child.y_end < parent.y_end);
+        }
+        let edges = &layout.edge_layouts;
+        assert!(middle.y_start < edges[1].y && edges[1].y < middle.y_end);
+        assert!(edges[3].y > middle.y_end && edges[3].y < outer.y_end);
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[outer.y_start].contains("┌─ Checkout "));
+        assert!(lines[middle.y_start].contains("┌─ Payment "));
+    }
+
+    #[test]
+    fn test_critical_block() {
+        let input = "Client -> Server\nServer -> Database\nServer -> Client";
+        let plain_layout = calculate_sequence_layout(&parse(tokenize(input).unwrap()).unwrap());
+
+        let input = "\
+Client -> Server
+critical Lock
+Server -> Database
+end
+Server -> Client";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let row = 1 + EDGE_SPACING;
+        let edges = &layout.edge_layouts;
+        let block = &layout.block_layouts[0];
+        assert_eq!(block.kind, BlockKind::Critical);
+        assert_eq!(block.label, "critical Lock");
+
+        assert_eq!(edges[0].y, plain_layout.edge_layouts[0].y);
+        assert_eq!(block.y_start, edges[0].y + row + CRITICAL_BLOCK_MARGIN);
+        assert_eq!(edges[1].y, block.y_start + row);
+        assert_eq!(block.y_end, edges[1].y + row);
+        assert_eq!(edges[2].y, block.y_end + row + CRITICAL_BLOCK_MARGIN);
+        // Two border rows and the margins around them
+        let shift = 2 * (row + CRITICAL_BLOCK_MARGIN);
+        assert_eq!(edges[2].y, plain_layout.edge_layouts[2].y + shift);
+        assert_eq!(layout.height, plain_layout.height + shift);
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[block.y_start].contains("╔═ critical Lock ═"));
+        assert!(lines[block.y_end].contains("╚═") && lines[block.y_end].contains("╪"));
+        assert!(lines[block.y_start - 1].trim_matches([' ', '│']).is_empty());
+    }
+
+    #[test]
+    fn test_break_block() {
+        let input = "Client -> Server\nServer -> Client\nClient -> Server\nServer -> Client";
+        let plain_layout = calculate_sequence_layout(&parse(tokenize(input).unwrap()).unwrap());
+
+        let input = "\
+Client -> Server
+break token expired
+Server -> Client
+Client -> Server
+end
+Server -> Client";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let row = 1 + EDGE_SPACING;
+        let edges = &layout.edge_layouts;
+        let block = &layout.block_layouts[0];
+        assert_eq!(block.kind, BlockKind::Break);
+        assert_eq!(block.label, "break token expired");
+
+        assert_eq!(block.y_start, edges[0].y + row);
+        assert_eq!(edges[1].y, block.y_start + row);
+        assert_eq!(edges[2].y, edges[1].y + row);
+        assert_eq!(block.y_end, edges[2].y + row);
+        assert_eq!(edges[3].y, block.y_end + row);
+        // One row for each border
+        let shift = 2 * row;
+        assert_eq!(edges[3].y, plain_layout.edge_layouts[3].y + shift);
+        assert_eq!(layout.height, plain_layout.height + shift);
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[block.y_start].contains("┌╌ break token expired ╌"));
+        assert!(lines[block.y_end].contains("└╌") && lines[block.y_end].contains("┼"));
+        assert!(lines[edges[1].y].contains('╎'));
+    }
+
+    #[test]
+    fn test_ref_over_one_participant() {
+        let input = "Client -> Server: login\nref over Server: Authenticate\nServer -> Client";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let [ref_layout] = &layout.ref_layouts[..] else {
+            panic!("expected one ref");
+        };
+        let server_x = layout.lifeline_layouts[1].x;
+
+        assert_eq!(
+            ref_layout.y,
+            layout.edge_layouts[0].arrow_y + 1 + EDGE_SPACING
+        );
+        assert_eq!(
+            layout.edge_layouts[1].y,
+            ref_layout.y + NOTE_HEIGHT + EDGE_SPACING
+        );
+        // Wide enough for the tag and the label, centered on the lifeline
+        let width = ref_layout.x_end - ref_layout.x_start + 1;
+        assert_eq!(width, REF_TAG_WIDTH + "Authenticate".len() + 3);
+        assert!(ref_layout.x_start < server_x && server_x < ref_layout.x_end);
+        assert!((server_x - ref_layout.x_start).abs_diff(ref_layout.x_end - server_x) <= 1);
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[ref_layout.y + 1].contains("│ ref │ Authenticate │"));
+        assert_eq!(layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_ref_over_non_adjacent_participants() {
+        let input = "\
+Client -> Server
+Server -> Database
+ref over Database, Client: Sync
+Client -> Server";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let config = SequenceLayoutConfig {
+            hide_participants: vec!["Database".to_string()],
+            ..SequenceLayoutConfig::default()
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        let ref_layout = &layout.ref_layouts[0];
+
+        // Spans from the first to the last lifeline, covering the one in between
+        let client_x = layout.lifeline_layouts[0].x;
+        let database_x = layout.edge_layouts[1].end_x + 1;
+        assert_eq!(ref_layout.x_start, client_x - REF_PADDING_X);
+        assert_eq!(ref_layout.x_end, database_x + REF_PADDING_X);
+        assert_eq!(layout.warnings, ["Ref over hidden participant 'Database'"]);
+
+        let output = render(&layout);
+        let label_row = output.lines().nth(ref_layout.y + 1).unwrap();
+        let server_x = layout.lifeline_layouts[1].x;
+        assert_eq!(label_row.chars().nth(server_x), Some(' '));
+        assert!(label_row.contains("│ ref │"));
+    }
+
+    #[test]
+    fn test_spacer() {
+        let input = "Client -> Server\nServer -> Database";
+        let plain_layout = calculate_sequence_layout(&parse(tokenize(input).unwrap()).unwrap());
+
+        let input = "Client -> Server\n---\nServer -> Database";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        assert_eq!(layout.height, plain_layout.height + SPACER_HEIGHT);
+        assert_eq!(
+            layout.spacer_layouts[0].y,
+            layout.edge_layouts[0].y + 1 + EDGE_SPACING
+        );
+        assert_eq!(
+            layout.edge_layouts[1].y,
+            plain_layout.edge_layouts[1].y + SPACER_HEIGHT
+        );
+        assert!(layout.spacer_layouts[0].divider.is_none());
+
+        let config = SequenceLayoutConfig {
+            spacer_height: 3,
+            spacer_divider: true,
+            ..SequenceLayoutConfig::default()
+        };
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        assert_eq!(layout.height, plain_layout.height + 3);
+        let spacer = &layout.spacer_layouts[0];
+        let first_x = layout.lifeline_layouts[0].x;
+        let last_x = layout.lifeline_layouts[2].x;
+        assert_eq!(spacer.divider, Some((first_x, spacer.y + 1, last_x)));
+
+        let output = render(&layout);
+        let divider_row = output.lines().nth(spacer.y + 1).unwrap();
+        assert!(divider_row.contains("│┈┈") && divider_row.contains("┈┈│"));
+        assert!(!divider_row.contains('─'));
+    }
+
+    #[test]
+    fn test_created_and_destroyed_lifelines() {
+        let input = "\
+Client -> Server: Order
+create Worker
+Server -> Worker: Start
+destroy Worker
+Server -> Client: Shipped";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+        let [client, server, worker] = &layout.participant_layouts[..] else {
+            panic!("expected three participants");
+        };
+        let start = &layout.edge_layouts[1];
+
+        // The box starts on the row of the creating edge, with the arrow at its middle row
+        assert_eq!(worker.top_box_y, start.y);
+        assert!(worker.top_box_y > client.top_box_y);
+        assert_eq!(start.arrow_y, worker.top_box_y + 1);
+        let worker_left_x = (worker.center_x + 1) - worker.width.div_ceil(2);
+        assert_eq!(start.end_x + 1, worker_left_x);
+        assert!(server.bottom_box_y.is_some());
+
+        let lifeline = &layout.lifeline_layouts[2];
+        assert_eq!(lifeline.start_y, worker.top_box_y + PARTICIPANT_HEIGHT);
+        assert!(lifeline.start_y > layout.lifeline_layouts[0].start_y);
+        // Ends with a cross above the last edge, without a bottom box
+        assert!(lifeline.destroyed);
+        assert_eq!(worker.bottom_box_y, None);
+        assert_eq!(lifeline.end_y + 1 + EDGE_SPACING, layout.edge_layouts[2].y);
+
+        let output = render(&layout);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[start.arrow_y].contains("─>│ Worker │"));
+        assert_eq!(lines[lifeline.end_y].chars().nth(lifeline.x), Some('✕'));
+        assert_eq!(output.matches("Worker").count(), 1);
+        assert_eq!(layout.bounding_box(), rendered_bounds(&output));
+    }
+
+    #[test]
+    fn test_message_widths_keyed_by_pair() {
+        let input = "\
+Client -> Server: GET
+Client <- Server: JSON response
+Server -> Database
+Database -> Client: callback";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let message_widths = calculate_message_widths(&diagram, &SequenceLayoutConfig::default());
+
+        let padding = MESSAGE_PADDING_X * 2;
+        assert_eq!(message_widths[&(0, 1)], "JSON response".width() + padding);
+        assert_eq!(message_widths[&(0, 2)], "callback".width() + padding);
+        assert_eq!(message_widths.get(&(1, 2)), None);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let input = "\
+title Fetching data
+note over Client: A very long note text here
+Client -> Server: GET /api/data
+Server -> Database
+Client <- Server: JSON response";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+
+        let bounding_box = layout.bounding_box();
+        let (min_x, min_y, max_x, max_y) = bounding_box;
+        // Tighter than the canvas by the margins
+        assert_eq!(min_x, MARGIN_LEFT);
+        assert_eq!(min_y, MARGIN_TOP);
+        assert_eq!(max_x, layout.width - 1 - MARGIN_RIGHT);
+        assert_eq!(max_y, layout.height - 1 - MARGIN_BOTTOM);
+
+        assert_eq!(bounding_box, rendered_bounds(&render(&layout)));
+    }
+
+    #[test]
+    fn test_measure_matches_render() {
+        let input = "\
+title Login
+Client -> Server: Login(username, password)
+note over Server: Checks the password hash
+Server -> Database: SELECT user
+return: UserData
+Client <- Server: Token";
+        let (width, height) = measure(input).unwrap();
+
+        let rendered = render(&calculate_sequence_layout(
+            &parse(tokenize(input).unwrap()).unwrap(),
+        ));
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(height, lines.len());
+        assert_eq!(width, lines.iter().map(|line| line.width()).max().unwrap());
+
+        assert!(matches!(
+            measure("Client Server"),
+            Err(SequenceError::Tokenize(_))
+        ));
+        assert!(matches!(measure("end"), Err(SequenceError::Parse(_))));
+    }
+}
@@ -1,7 +1,11 @@
+pub mod canvas;
+pub mod color;
 pub mod gantt;
 pub mod git_graph;
 pub mod graph;
 pub mod layout;
+pub mod options;
 pub mod parser;
 pub mod renderer;
 pub mod tokenizer;
+pub mod validation;
@@ -1,6 +1,30 @@
-use gram::{parser::parse, tokenizer::tokenize};
+use gram::{
+    gantt,
+    parser::parse,
+    tokenizer::{tokenize, tokenize_iter},
+};
+
+const GANTT_SAMPLE_INPUT: &str = "\
+title Release plan
+section Design
+Wireframes: 05-01-2026, 5d
+Review: continue, 2d
+section Build
+Implementation: continue, 10d | crit
+Testing: continue, 4d
+Release: 30-01-2026, 30-01-2026
+";
 
 fn main() {
+    // `--gantt` renders a sample gantt chart instead
+    if std::env::args().any(|arg| arg == "--gantt") {
+        match gantt::render_str(GANTT_SAMPLE_INPUT) {
+            Ok(output) => print!("{}", output),
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+
     let sample_input = "\
 Client -> Server: Login(username, password)
 Server -> Database: ValidateCredentials()
@@ -22,7 +46,12 @@ Client -> MessageQueue: PublishEvent(profileUpdated)
 MessageQueue -> NotificationService: ProfileUpdatedEvent
 NotificationService -> Client: PushNotification(changes)
 ";
-    let tokens = tokenize(sample_input);
+    // `--stream` tokenizes line by line through the streaming tokenizer
+    let tokens = if std::env::args().any(|arg| arg == "--stream") {
+        tokenize_iter(sample_input).collect()
+    } else {
+        tokenize(sample_input)
+    };
     if let Ok(tokens) = tokens {
         let graph = parse(tokens);
 
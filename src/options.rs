@@ -0,0 +1,112 @@
+use crate::layout::SequenceLayoutConfig;
+use crate::renderer::RenderOptions;
+
+/// Layout and render options of a sequence diagram, set with chained calls like
+/// `SequenceOptions::new().ascii(true).numbered(true)`
+#[derive(Debug, Clone, Default)]
+pub struct SequenceOptions {
+    pub layout: SequenceLayoutConfig,
+    pub render: RenderOptions,
+}
+
+impl SequenceOptions {
+    pub fn new() -> Self {
+        SequenceOptions::default()
+    }
+
+    /// Draw with plain ASCII instead of box-drawing characters
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.render.ascii = ascii;
+        self
+    }
+
+    /// Color the edges with ANSI escape codes
+    pub fn color(mut self, color: bool) -> Self {
+        self.render.output.color = color;
+        self
+    }
+
+    /// Strip the spaces padding each line to the diagram width
+    pub fn trim_trailing_whitespace(mut self, trim_trailing_whitespace: bool) -> Self {
+        self.render.output.trim_trailing_whitespace = trim_trailing_whitespace;
+        self
+    }
+
+    /// End the output with a newline
+    pub fn final_newline(mut self, final_newline: bool) -> Self {
+        self.render.output.final_newline = final_newline;
+        self
+    }
+
+    /// Number the participants and list their names in a legend row
+    pub fn numbered(mut self, numbered: bool) -> Self {
+        self.layout.use_indices = numbered;
+        self
+    }
+
+    /// Shrink the padding so the diagram fits this many columns
+    pub fn max_width(mut self, max_width: Option<usize>) -> Self {
+        self.layout.max_width = max_width;
+        self
+    }
+
+    /// Shrink the padding so the diagram fits the terminal
+    pub fn auto_width(mut self, auto_width: bool) -> Self {
+        self.layout.auto_width = auto_width;
+        self
+    }
+
+    /// Place the first participant on the right
+    pub fn rtl(mut self, rtl: bool) -> Self {
+        self.layout.rtl = rtl;
+        self
+    }
+
+    pub fn max_participant_display_len(mut self, len: Option<usize>) -> Self {
+        self.layout.max_participant_display_len = len;
+        self
+    }
+
+    pub fn hide_participants(mut self, names: &[&str]) -> Self {
+        self.layout.hide_participants = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    pub fn spacer_divider(mut self, spacer_divider: bool) -> Self {
+        self.layout.spacer_divider = spacer_divider;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let options = SequenceOptions::new();
+        let config = SequenceLayoutConfig::default();
+        assert_eq!(options.layout.use_indices, config.use_indices);
+        assert_eq!(options.layout.max_width, None);
+        assert_eq!(options.layout.edge_spacing, config.edge_spacing);
+        assert!(!options.render.ascii);
+        assert!(!options.render.output.color);
+    }
+
+    #[test]
+    fn test_chained_setters() {
+        let options = SequenceOptions::new()
+            .ascii(true)
+            .numbered(true)
+            .max_width(Some(80))
+            .hide_participants(&["Cache"]);
+        assert!(options.render.ascii);
+        assert!(options.layout.use_indices);
+        assert_eq!(options.layout.max_width, Some(80));
+        assert_eq!(options.layout.hide_participants, ["Cache"]);
+
+        // Later calls override earlier ones
+        let options = options.numbered(false);
+        assert!(!options.layout.use_indices);
+    }
+}
@@ -1,18 +1,465 @@
-use crate::tokenizer::Token;
+use std::collections::HashMap;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::color::Color;
+use crate::tokenizer::{Token, TokenizeConfig, TokenizeError, tokenize_with_config};
+use crate::validation::validate_participant_name;
 
 pub type Participant = String;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Edge {
     pub from: Participant,
     pub to: Participant,
     pub message: Option<String>,
+    /// Set by a leading `{style}` in the message, e.g. `{bold red}`
+    pub style: Option<EdgeStyle>,
 }
 
-#[derive(Debug)]
+impl Edge {
+    /// Whether the edge starts or ends at `participant`
+    pub fn involves(&self, participant: &str) -> bool {
+        self.from == participant || self.to == participant
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EdgeStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub label_position: LabelPosition,
+}
+
+/// Where the message of an edge goes relative to its arrow
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LabelPosition {
+    /// On the row above the arrow
+    #[default]
+    Above,
+    /// On the row below the arrow
+    Below,
+    /// Over the middle of the arrow, on the same row
+    Centered,
+}
+
+/// Stereotype of a participant, from the keyword of its declaration, e.g. `actor Client`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParticipantKind {
+    #[default]
+    Participant,
+    Actor,
+    Database,
+    Queue,
+}
+
+impl ParticipantKind {
+    pub const ALL: [ParticipantKind; 4] = [
+        ParticipantKind::Participant,
+        ParticipantKind::Actor,
+        ParticipantKind::Database,
+        ParticipantKind::Queue,
+    ];
+
+    /// The keyword declaring a participant of this kind
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ParticipantKind::Participant => "participant",
+            ParticipantKind::Actor => "actor",
+            ParticipantKind::Database => "database",
+            ParticipantKind::Queue => "queue",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Note {
+    pub participant: Participant,
+    pub text: String,
+    /// Number of edges that come before the note
+    pub position: usize,
+}
+
+/// A point between the edges, notes, spacers and refs of a diagram, given by how many of each
+/// come before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Position {
+    pub edges: usize,
+    pub notes: usize,
+    pub spacers: usize,
+    pub refs: usize,
+}
+
+/// A frame around a run of edges and notes
+#[derive(Debug, PartialEq)]
+pub enum Block {
+    Par(ParBlock),
+    Critical(CriticalBlock),
+    Break(BreakBlock),
+    Group(GroupBlock),
+}
+
+impl Block {
+    pub fn start(&self) -> Position {
+        match self {
+            Block::Par(par) => par.start,
+            Block::Critical(critical) => critical.start,
+            Block::Break(break_block) => break_block.start,
+            Block::Group(group) => group.start,
+        }
+    }
+
+    pub fn end(&self) -> Position {
+        match self {
+            Block::Par(par) => par.end,
+            Block::Critical(critical) => critical.end,
+            Block::Break(break_block) => break_block.end,
+            Block::Group(group) => group.end,
+        }
+    }
+
+    /// Where the regions after the first one start
+    pub fn dividers(&self) -> &[Position] {
+        match self {
+            Block::Par(par) => &par.dividers,
+            Block::Critical(_) | Block::Break(_) | Block::Group(_) => &[],
+        }
+    }
+
+    fn set_end(&mut self, position: Position) {
+        match self {
+            Block::Par(par) => par.end = position,
+            Block::Critical(critical) => critical.end = position,
+            Block::Break(break_block) => break_block.end = position,
+            Block::Group(group) => group.end = position,
+        }
+    }
+
+    /// Adds an edge to the current region
+    fn push_edge(&mut self, edge: Edge) {
+        match self {
+            Block::Par(par) => {
+                if let Some(region) = par.regions.last_mut() {
+                    region.push(edge);
+                }
+            }
+            Block::Critical(critical) => critical.edges.push(edge),
+            Block::Break(break_block) => break_block.edges.push(edge),
+            Block::Group(group) => group.edges.push(edge),
+        }
+    }
+}
+
+/// Edges that happen in parallel, from `par` to `end` with regions separated by `and`
+#[derive(Debug, PartialEq)]
+pub struct ParBlock {
+    pub label: String,
+    /// Edges of each region. They are also listed in order in `SequenceDiagram::edges`.
+    pub regions: Vec<Vec<Edge>>,
+    /// Labels of the `and` lines, one per region after the first
+    pub and_labels: Vec<String>,
+    pub start: Position,
+    /// Where each region after the first starts
+    pub dividers: Vec<Position>,
+    pub end: Position,
+}
+
+/// Edges that must not interleave with others, from `critical` to `end`
+#[derive(Debug, PartialEq)]
+pub struct CriticalBlock {
+    pub label: String,
+    /// Also listed in order in `SequenceDiagram::edges`
+    pub edges: Vec<Edge>,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Edges that end the enclosing interaction when `condition` holds, from `break` to `end`
+#[derive(Debug, PartialEq)]
+pub struct BreakBlock {
+    pub condition: String,
+    /// Also listed in order in `SequenceDiagram::edges`
+    pub edges: Vec<Edge>,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A labelled frame around edges, from `group` to `end`
+#[derive(Debug, PartialEq)]
+pub struct GroupBlock {
+    pub label: String,
+    /// Also listed in order in `SequenceDiagram::edges`
+    pub edges: Vec<Edge>,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A `ref over` box standing for an interaction defined elsewhere
+#[derive(Debug, PartialEq)]
+pub struct RefBlock {
+    pub participants: Vec<Participant>,
+    pub label: String,
+    pub position: Position,
+}
+
+/// A participant created or destroyed partway through the diagram, from `create` and `destroy`
+/// lines
+#[derive(Debug, PartialEq)]
+pub struct Lifetime {
+    pub participant: Participant,
+    /// Index into `SequenceDiagram::edges` of the first edge of the participant after its
+    /// `create` line. `None` when it exists from the start.
+    pub created_by: Option<usize>,
+    /// Where the `destroy` line is. `None` when it lives until the end.
+    pub destroyed_at: Option<Position>,
+}
+
+#[derive(Debug, PartialEq)]
 pub struct SequenceDiagram {
+    pub title: Option<String>,
     pub participants: Vec<Participant>,
     pub edges: Vec<Edge>,
+    pub notes: Vec<Note>,
+    /// In order of their opening line, so enclosing blocks come before the blocks inside them
+    pub blocks: Vec<Block>,
+    /// Where the `---` lines are
+    pub spacers: Vec<Position>,
+    pub refs: Vec<RefBlock>,
+    /// In order of the first `create` or `destroy` line of each participant
+    pub lifetimes: Vec<Lifetime>,
+    /// Participants declared with a stereotype keyword. The others are plain participants.
+    pub kinds: HashMap<Participant, ParticipantKind>,
+}
+
+/// Writes the diagram in the input format, so that tokenizing and parsing the text gives the
+/// diagram back. Participants are declared first to keep their order, and each edge is written
+/// with the arrow pointing the way of the participant order.
+impl std::fmt::Display for SequenceDiagram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "title {}", title)?;
+        }
+        for participant in &self.participants {
+            writeln!(f, "{} {}", self.kind(participant).keyword(), participant)?;
+        }
+
+        let mut destroys: Vec<(Position, &Participant)> = self
+            .lifetimes
+            .iter()
+            .filter_map(|lifetime| Some((lifetime.destroyed_at?, &lifetime.participant)))
+            .collect();
+        destroys.sort_by_key(|&(position, _)| position);
+        let mut destroys = destroys.into_iter().peekable();
+
+        // Indices into `blocks` of the open blocks with the number of their dividers written
+        let mut open_blocks: Vec<(usize, usize)> = Vec::new();
+        let mut next_block = 0;
+        let mut position = Position::default();
+        loop {
+            // Dividers before the end of their block, and blocks closed before a block opened at
+            // the same position, which is then a sibling. Empty blocks keep the same edges
+            // either way.
+            loop {
+                if let Some((index, dividers)) = open_blocks.last_mut() {
+                    let block = &self.blocks[*index];
+                    if block.dividers().get(*dividers) == Some(&position) {
+                        let Block::Par(par) = block else {
+                            unreachable!("only par blocks have dividers")
+                        };
+                        write_keyword(f, "and", &par.and_labels[*dividers])?;
+                        *dividers += 1;
+                        continue;
+                    }
+                    if block.end() == position && *dividers == block.dividers().len() {
+                        writeln!(f, "end")?;
+                        open_blocks.pop();
+                        continue;
+                    }
+                }
+                match self.blocks.get(next_block) {
+                    Some(block) if block.start() == position => {
+                        let (keyword, label) = match block {
+                            Block::Par(par) => ("par", &par.label),
+                            Block::Critical(critical) => ("critical", &critical.label),
+                            Block::Break(break_block) => ("break", &break_block.condition),
+                            Block::Group(group) => ("group", &group.label),
+                        };
+                        write_keyword(f, keyword, label)?;
+                        open_blocks.push((next_block, 0));
+                        next_block += 1;
+                    }
+                    _ => break,
+                }
+            }
+
+            // Spacers, refs and destroy lines record every count, notes only the edges before
+            // them
+            if let Some((_, participant)) = destroys.next_if(|&(at, _)| at == position) {
+                writeln!(f, "destroy {}", participant)?;
+            } else if self.spacers.get(position.spacers) == Some(&position) {
+                writeln!(f, "---")?;
+                position.spacers += 1;
+            } else if let Some(ref_block) = self
+                .refs
+                .get(position.refs)
+                .filter(|ref_block| ref_block.position == position)
+            {
+                writeln!(
+                    f,
+                    "ref over {}: {}",
+                    ref_block.participants.join(", "),
+                    ref_block.label
+                )?;
+                position.refs += 1;
+            } else if let Some(note) = self
+                .notes
+                .get(position.notes)
+                .filter(|note| note.position == position.edges)
+            {
+                writeln!(f, "note over {}: {}", note.participant, note.text)?;
+                position.notes += 1;
+            } else if let Some(edge) = self.edges.get(position.edges) {
+                for lifetime in &self.lifetimes {
+                    if lifetime.created_by == Some(position.edges) {
+                        writeln!(f, "create {}", lifetime.participant)?;
+                    }
+                }
+                self.write_edge(f, edge)?;
+                position.edges += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Edges of a participant, from `SequenceDiagram::stats`. A self message counts as both.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ParticipantStats {
+    pub outgoing: usize,
+    pub incoming: usize,
+}
+
+impl ParticipantStats {
+    pub fn total(&self) -> usize {
+        self.outgoing + self.incoming
+    }
+}
+
+/// Summary of a diagram for linting, from `SequenceDiagram::stats`
+#[derive(Debug, PartialEq)]
+pub struct SequenceStats {
+    pub edges: usize,
+    /// Every participant, in diagram order
+    pub participants: Vec<(Participant, ParticipantStats)>,
+    /// Participant with the most edges, the leftmost one on a tie. `None` without edges.
+    pub busiest: Option<Participant>,
+    /// Participants without any edge, e.g. only declared or only in notes
+    pub orphans: Vec<Participant>,
+    /// Edges without a message
+    pub unlabeled_edges: usize,
+    /// Display width of the longest message. 0 without messages.
+    pub max_message_width: usize,
+}
+
+impl SequenceDiagram {
+    /// Stereotype of `participant`, plain unless declared with another keyword
+    pub fn kind(&self, participant: &str) -> ParticipantKind {
+        self.kinds.get(participant).copied().unwrap_or_default()
+    }
+
+    pub fn stats(&self) -> SequenceStats {
+        let mut participants: Vec<(Participant, ParticipantStats)> = self
+            .participants
+            .iter()
+            .map(|name| (name.clone(), ParticipantStats::default()))
+            .collect();
+        for edge in &self.edges {
+            for (name, stats) in &mut participants {
+                if *name == edge.from {
+                    stats.outgoing += 1;
+                }
+                if *name == edge.to {
+                    stats.incoming += 1;
+                }
+            }
+        }
+
+        // `max_by_key` keeps the last of equal elements, so search from the right
+        let busiest = participants
+            .iter()
+            .rev()
+            .filter(|(_, stats)| stats.total() > 0)
+            .max_by_key(|(_, stats)| stats.total())
+            .map(|(name, _)| name.clone());
+        let orphans = participants
+            .iter()
+            .filter(|(_, stats)| stats.total() == 0)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        SequenceStats {
+            edges: self.edges.len(),
+            busiest,
+            orphans,
+            participants,
+            unlabeled_edges: self
+                .edges
+                .iter()
+                .filter(|edge| edge.message.is_none())
+                .count(),
+            max_message_width: self
+                .edges
+                .iter()
+                .filter_map(|edge| edge.message.as_deref())
+                .map(|message| message.width())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    fn write_edge(&self, f: &mut std::fmt::Formatter<'_>, edge: &Edge) -> std::fmt::Result {
+        let index = |name: &Participant| self.participants.iter().position(|p| p == name);
+        if index(&edge.from) <= index(&edge.to) {
+            write!(f, "{} -> {}", edge.from, edge.to)?;
+        } else {
+            write!(f, "{} <- {}", edge.to, edge.from)?;
+        }
+
+        if edge.style.is_some() || edge.message.is_some() {
+            write!(f, ":")?;
+        }
+        if let Some(style) = edge.style {
+            let mut words = Vec::new();
+            if style.bold {
+                words.push("bold");
+            }
+            if let Some(color) = style.color {
+                words.push(color.name());
+            }
+            // A style needs at least one word
+            match style.label_position {
+                LabelPosition::Above if !words.is_empty() => {}
+                LabelPosition::Above => words.push("above"),
+                LabelPosition::Below => words.push("below"),
+                LabelPosition::Centered => words.push("centered"),
+            }
+            write!(f, " {{{}}}", words.join(" "))?;
+        }
+        if let Some(message) = &edge.message {
+            write!(f, " {}", message)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Writes a block line, leaving out the space before an empty label
+fn write_keyword(f: &mut std::fmt::Formatter<'_>, keyword: &str, label: &str) -> std::fmt::Result {
+    if label.is_empty() {
+        writeln!(f, "{}", keyword)
+    } else {
+        writeln!(f, "{} {}", keyword, label)
+    }
 }
 
 #[derive(Debug)]
@@ -26,12 +473,271 @@ impl std::fmt::Display for ParseError {
     }
 }
 
+/// Tokenizes and parses sequence diagrams with options set once, for reuse across inputs
+#[derive(Debug, Clone, Default)]
+pub struct SequenceParser {
+    pub config: TokenizeConfig,
+}
+
+impl SequenceParser {
+    pub fn new() -> Self {
+        SequenceParser::default()
+    }
+
+    pub fn with_config(config: TokenizeConfig) -> Self {
+        SequenceParser { config }
+    }
+
+    pub fn tokenize(&self, input: &str) -> Result<Vec<Token>, TokenizeError> {
+        tokenize_with_config(input, &self.config)
+    }
+
+    pub fn parse(&self, tokens: Vec<Token>) -> Result<SequenceDiagram, ParseError> {
+        parse(tokens)
+    }
+}
+
 pub fn parse(tokens: Vec<Token>) -> Result<SequenceDiagram, ParseError> {
     let mut participants = Vec::new();
-    let mut edges = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut notes: Vec<Note> = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut spacers = Vec::new();
+    let mut refs: Vec<RefBlock> = Vec::new();
+    let mut lifetimes: Vec<Lifetime> = Vec::new();
+    let mut kinds = HashMap::new();
+    // Participants with a `create` line and no edge yet
+    let mut awaiting_creation: Vec<Participant> = Vec::new();
+    // Indices into `blocks` of the blocks that are not closed yet, innermost last
+    let mut open_blocks: Vec<usize> = Vec::new();
+    let mut title = None;
+    let mut order_hint: Option<Vec<Participant>> = None;
     let mut i = 0;
 
     while i < tokens.len() {
+        if let Token::Title(text) = &tokens[i] {
+            if title.is_some() {
+                return Err(ParseError {
+                    message: "Duplicate title. A diagram has at most one title".to_string(),
+                });
+            }
+            title = Some(text.clone());
+            i += 1;
+            continue;
+        }
+
+        if let Token::OrderHint(order) = &tokens[i] {
+            if order_hint.is_some() {
+                return Err(ParseError {
+                    message: "Duplicate order. A diagram has at most one order directive"
+                        .to_string(),
+                });
+            }
+            order_hint = Some(order.clone());
+            i += 1;
+            continue;
+        }
+
+        let position = Position {
+            edges: edges.len(),
+            notes: notes.len(),
+            spacers: spacers.len(),
+            refs: refs.len(),
+        };
+        match &tokens[i] {
+            Token::Declaration { name, kind } => {
+                add_participant(&mut participants, name)?;
+                if *kind != ParticipantKind::Participant
+                    && let Some(previous) = kinds.insert(name.clone(), *kind)
+                    && previous != *kind
+                {
+                    return Err(ParseError {
+                        message: format!(
+                            "Participant '{}' is declared both as {} and as {}",
+                            name,
+                            previous.keyword(),
+                            kind.keyword()
+                        ),
+                    });
+                }
+                i += 1;
+                continue;
+            }
+            Token::Create(name) => {
+                add_participant(&mut participants, name)?;
+                let used = edges.iter().any(|edge| edge.involves(name))
+                    || notes.iter().any(|note| &note.participant == name)
+                    || refs
+                        .iter()
+                        .any(|ref_block| ref_block.participants.contains(name));
+                if used {
+                    return Err(ParseError {
+                        message: format!("Participant '{}' is used before it is created", name),
+                    });
+                }
+                if lifetimes
+                    .iter()
+                    .any(|lifetime| &lifetime.participant == name)
+                {
+                    return Err(ParseError {
+                        message: format!("Participant '{}' is created twice", name),
+                    });
+                }
+                lifetimes.push(Lifetime {
+                    participant: name.clone(),
+                    created_by: None,
+                    destroyed_at: None,
+                });
+                awaiting_creation.push(name.clone());
+                i += 1;
+                continue;
+            }
+            Token::Destroy(name) => {
+                if !participants.contains(name) {
+                    return Err(ParseError {
+                        message: format!("Cannot destroy unknown participant '{}'", name),
+                    });
+                }
+                check_alive(name, &lifetimes, &awaiting_creation)?;
+                match lifetimes
+                    .iter_mut()
+                    .find(|lifetime| &lifetime.participant == name)
+                {
+                    Some(lifetime) => lifetime.destroyed_at = Some(position),
+                    None => lifetimes.push(Lifetime {
+                        participant: name.clone(),
+                        created_by: None,
+                        destroyed_at: Some(position),
+                    }),
+                }
+                i += 1;
+                continue;
+            }
+            Token::Return(message) => {
+                let Some(last_edge) = edges.last() else {
+                    return Err(ParseError {
+                        message: "'return' without a previous edge to reply to".to_string(),
+                    });
+                };
+                let edge = Edge {
+                    from: last_edge.to.clone(),
+                    to: last_edge.from.clone(),
+                    message: message.clone(),
+                    style: None,
+                };
+                record_creation(&edge, edges.len(), &mut lifetimes, &mut awaiting_creation)?;
+                for &index in &open_blocks {
+                    blocks[index].push_edge(edge.clone());
+                }
+                edges.push(edge);
+                i += 1;
+                continue;
+            }
+            Token::Spacer => {
+                spacers.push(position);
+                i += 1;
+                continue;
+            }
+            Token::Ref {
+                participants: names,
+                label,
+            } => {
+                for name in names {
+                    add_participant(&mut participants, name)?;
+                    check_alive(name, &lifetimes, &awaiting_creation)?;
+                }
+                refs.push(RefBlock {
+                    participants: names.clone(),
+                    label: label.clone(),
+                    position,
+                });
+                i += 1;
+                continue;
+            }
+            Token::Par(label) => {
+                open_blocks.push(blocks.len());
+                blocks.push(Block::Par(ParBlock {
+                    label: label.clone(),
+                    regions: vec![Vec::new()],
+                    and_labels: Vec::new(),
+                    start: position,
+                    dividers: Vec::new(),
+                    end: position,
+                }));
+                i += 1;
+                continue;
+            }
+            Token::Critical(label) => {
+                open_blocks.push(blocks.len());
+                blocks.push(Block::Critical(CriticalBlock {
+                    label: label.clone(),
+                    edges: Vec::new(),
+                    start: position,
+                    end: position,
+                }));
+                i += 1;
+                continue;
+            }
+            Token::Break(condition) => {
+                open_blocks.push(blocks.len());
+                blocks.push(Block::Break(BreakBlock {
+                    condition: condition.clone(),
+                    edges: Vec::new(),
+                    start: position,
+                    end: position,
+                }));
+                i += 1;
+                continue;
+            }
+            Token::Group(label) => {
+                open_blocks.push(blocks.len());
+                blocks.push(Block::Group(GroupBlock {
+                    label: label.clone(),
+                    edges: Vec::new(),
+                    start: position,
+                    end: position,
+                }));
+                i += 1;
+                continue;
+            }
+            Token::And(label) => {
+                let Some(Block::Par(par)) = open_blocks.last().map(|&index| &mut blocks[index])
+                else {
+                    return Err(ParseError {
+                        message: "'and' outside of a 'par' block".to_string(),
+                    });
+                };
+                par.regions.push(Vec::new());
+                par.and_labels.push(label.clone());
+                par.dividers.push(position);
+                i += 1;
+                continue;
+            }
+            Token::End => {
+                let Some(index) = open_blocks.pop() else {
+                    return Err(ParseError {
+                        message: "'end' without an open block".to_string(),
+                    });
+                };
+                blocks[index].set_end(position);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Token::Note { participant, text } = &tokens[i] {
+            add_participant(&mut participants, participant)?;
+            check_alive(participant, &lifetimes, &awaiting_creation)?;
+            notes.push(Note {
+                participant: participant.clone(),
+                text: text.clone(),
+                position: edges.len(),
+            });
+            i += 1;
+            continue;
+        }
+
         if i + 2 >= tokens.len() {
             return Err(ParseError {
                 message: "Incomplete edge. Expected at least 3 tokens".to_string(),
@@ -66,6 +772,14 @@ pub fn parse(tokens: Vec<Token>) -> Result<SequenceDiagram, ParseError> {
             }
         };
 
+        let style = match tokens.get(i + 3) {
+            Some(Token::EdgeStyle(style)) => {
+                i += 1;
+                Some(*style)
+            }
+            _ => None,
+        };
+
         let message = if i + 3 < tokens.len() {
             match &tokens[i + 3] {
                 Token::ArrowMessage(msg) => {
@@ -78,28 +792,132 @@ pub fn parse(tokens: Vec<Token>) -> Result<SequenceDiagram, ParseError> {
             None
         };
 
-        if !participants.contains(&from_participant) {
-            participants.push(from_participant.clone());
-        }
-        if !participants.contains(&to_participant) {
-            participants.push(to_participant.clone());
-        }
+        add_participant(&mut participants, &from_participant)?;
+        add_participant(&mut participants, &to_participant)?;
 
-        edges.push(Edge {
+        let edge = Edge {
             from: from_participant,
             to: to_participant,
             message,
-        });
+            style,
+        };
+        record_creation(&edge, edges.len(), &mut lifetimes, &mut awaiting_creation)?;
+        for &index in &open_blocks {
+            blocks[index].push_edge(edge.clone());
+        }
+        edges.push(edge);
 
         i += 3;
     }
 
+    if !open_blocks.is_empty() {
+        return Err(ParseError {
+            message: format!("{} block(s) not closed. Expected 'end'", open_blocks.len()),
+        });
+    }
+
+    if let Some(name) = awaiting_creation.first() {
+        return Err(ParseError {
+            message: format!(
+                "Participant '{}' is created but has no edge after its 'create' line",
+                name
+            ),
+        });
+    }
+
+    if let Some(order) = order_hint {
+        participants = apply_order_hint(order, participants);
+    }
+
     Ok(SequenceDiagram {
+        title,
         participants,
         edges,
+        notes,
+        blocks,
+        spacers,
+        refs,
+        lifetimes,
+        kinds,
     })
 }
 
+/// Rejects notes and refs over a participant before its creating edge, and anything after its
+/// `destroy` line
+fn check_alive(
+    name: &str,
+    lifetimes: &[Lifetime],
+    awaiting_creation: &[Participant],
+) -> Result<(), ParseError> {
+    if awaiting_creation.iter().any(|awaiting| awaiting == name) {
+        return Err(ParseError {
+            message: format!("Participant '{}' is used before its creating edge", name),
+        });
+    }
+    check_not_destroyed(name, lifetimes)
+}
+
+fn check_not_destroyed(name: &str, lifetimes: &[Lifetime]) -> Result<(), ParseError> {
+    let destroyed = lifetimes
+        .iter()
+        .any(|lifetime| lifetime.participant == name && lifetime.destroyed_at.is_some());
+    if destroyed {
+        return Err(ParseError {
+            message: format!("Participant '{}' is used after it is destroyed", name),
+        });
+    }
+    Ok(())
+}
+
+/// Makes `edge`, at `index` into the edges, the creating edge of the participants awaiting one
+fn record_creation(
+    edge: &Edge,
+    index: usize,
+    lifetimes: &mut [Lifetime],
+    awaiting_creation: &mut Vec<Participant>,
+) -> Result<(), ParseError> {
+    check_not_destroyed(&edge.from, lifetimes)?;
+    check_not_destroyed(&edge.to, lifetimes)?;
+    awaiting_creation.retain(|name| {
+        if !edge.involves(name) {
+            return true;
+        }
+        for lifetime in lifetimes.iter_mut() {
+            if &lifetime.participant == name {
+                lifetime.created_by = Some(index);
+            }
+        }
+        false
+    });
+    Ok(())
+}
+
+/// Adds `name` unless it is already a participant. Names are checked again here because the
+/// tokens may not come from the tokenizer.
+fn add_participant(participants: &mut Vec<Participant>, name: &str) -> Result<(), ParseError> {
+    validate_participant_name(name).map_err(|message| ParseError {
+        message: format!("Participant '{}' {}", name, message),
+    })?;
+    if !participants.iter().any(|participant| participant == name) {
+        participants.push(name.to_string());
+    }
+    Ok(())
+}
+
+/// Puts the hinted participants first, in hint order, followed by the others in order of
+/// appearance. Hinted participants are declared even if no edge or note uses them.
+fn apply_order_hint(
+    mut order: Vec<Participant>,
+    participants: Vec<Participant>,
+) -> Vec<Participant> {
+    for participant in participants {
+        if !order.contains(&participant) {
+            order.push(participant);
+        }
+    }
+    order
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,4 +1075,471 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().message.contains("Expected participant"));
     }
+
+    #[test]
+    fn test_note_registers_participant() {
+        let tokens = vec![
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Server".to_string()),
+            Token::Note {
+                participant: "Logger".to_string(),
+                text: "Request logged".to_string(),
+            },
+            Token::Participant("Server".to_string()),
+            Token::RightArrow,
+            Token::Participant("Database".to_string()),
+        ];
+
+        let diagram = parse(tokens).unwrap();
+
+        assert_eq!(
+            diagram.participants,
+            vec![
+                "Client".to_string(),
+                "Server".to_string(),
+                "Logger".to_string(),
+                "Database".to_string()
+            ]
+        );
+        assert_eq!(diagram.notes.len(), 1);
+        assert_eq!(diagram.notes[0].participant, "Logger");
+        assert_eq!(diagram.notes[0].text, "Request logged");
+        assert_eq!(diagram.notes[0].position, 1);
+    }
+
+    #[test]
+    fn test_title() {
+        let tokens = vec![
+            Token::Title("Login flow".to_string()),
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Server".to_string()),
+        ];
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.title.as_deref(), Some("Login flow"));
+        assert_eq!(diagram.edges.len(), 1);
+
+        let tokens = vec![
+            Token::Title("Login flow".to_string()),
+            Token::Title("Logout flow".to_string()),
+        ];
+        assert!(
+            parse(tokens)
+                .unwrap_err()
+                .message
+                .contains("Duplicate title")
+        );
+    }
+
+    #[test]
+    fn test_edge_style() {
+        let style = EdgeStyle {
+            color: Some(Color::Red),
+            bold: true,
+            ..EdgeStyle::default()
+        };
+        let tokens = vec![
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Server".to_string()),
+            Token::EdgeStyle(style),
+            Token::ArrowMessage("Error".to_string()),
+            Token::Participant("Client".to_string()),
+            Token::LeftArrow,
+            Token::Participant("Server".to_string()),
+        ];
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.edges[0].style, Some(style));
+        assert_eq!(diagram.edges[0].message.as_deref(), Some("Error"));
+        assert_eq!(diagram.edges[1].style, None);
+    }
+
+    #[test]
+    fn test_order_hint() {
+        let tokens = vec![
+            Token::OrderHint(vec!["Bob".to_string(), "Carol".to_string()]),
+            Token::Participant("Alice".to_string()),
+            Token::RightArrow,
+            Token::Participant("Bob".to_string()),
+            Token::Participant("Dave".to_string()),
+            Token::LeftArrow,
+            Token::Participant("Alice".to_string()),
+        ];
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.participants, ["Bob", "Carol", "Alice", "Dave"]);
+
+        let tokens = vec![
+            Token::OrderHint(vec!["Bob".to_string()]),
+            Token::OrderHint(vec!["Alice".to_string()]),
+        ];
+        assert!(
+            parse(tokens)
+                .unwrap_err()
+                .message
+                .contains("Duplicate order")
+        );
+    }
+
+    #[test]
+    fn test_par_block() {
+        let edge = |from: &str, to: &str| {
+            [
+                Token::Participant(from.to_string()),
+                Token::RightArrow,
+                Token::Participant(to.to_string()),
+            ]
+        };
+        let mut tokens = vec![Token::Par("Fetch".to_string())];
+        tokens.extend(edge("Server", "Database"));
+        tokens.push(Token::And("Notify".to_string()));
+        tokens.extend(edge("Server", "Logger"));
+        tokens.extend(edge("Logger", "Disk"));
+        tokens.push(Token::End);
+        tokens.extend(edge("Server", "Client"));
+
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.edges.len(), 4);
+        let [Block::Par(par)] = &diagram.blocks[..] else {
+            panic!("expected one par block");
+        };
+        assert_eq!(par.label, "Fetch");
+        assert_eq!(par.and_labels, ["Notify"]);
+        assert_eq!(par.regions.len(), 2);
+        assert_eq!(par.regions[0][0].to, "Database");
+        assert_eq!(par.regions[1].len(), 2);
+        assert_eq!(par.start, Position::default());
+        let position = |edges| Position {
+            edges,
+            ..Position::default()
+        };
+        assert_eq!(par.dividers, [position(1)]);
+        assert_eq!(par.end, position(3));
+
+        let message = |tokens: Vec<Token>| parse(tokens).unwrap_err().message;
+        assert!(message(vec![Token::And(String::new())]).contains("outside of a 'par'"));
+        let critical_and = vec![Token::Critical(String::new()), Token::And(String::new())];
+        assert!(message(critical_and).contains("outside of a 'par'"));
+        assert!(message(vec![Token::End]).contains("without an open block"));
+        assert!(message(vec![Token::Par(String::new())]).contains("not closed"));
+    }
+
+    #[test]
+    fn test_critical_block() {
+        let tokens = vec![
+            Token::Critical("Lock".to_string()),
+            Token::Participant("Server".to_string()),
+            Token::RightArrow,
+            Token::Participant("Database".to_string()),
+            Token::End,
+        ];
+        let diagram = parse(tokens).unwrap();
+        let [Block::Critical(critical)] = &diagram.blocks[..] else {
+            panic!("expected one critical block");
+        };
+        assert_eq!(critical.label, "Lock");
+        assert_eq!(critical.edges.len(), 1);
+        assert_eq!(critical.end.edges, 1);
+        assert!(diagram.blocks[0].dividers().is_empty());
+    }
+
+    #[test]
+    fn test_break_block() {
+        let tokens = vec![
+            Token::Break("timeout".to_string()),
+            Token::Participant("Server".to_string()),
+            Token::RightArrow,
+            Token::Participant("Client".to_string()),
+            Token::End,
+        ];
+        let diagram = parse(tokens).unwrap();
+        let [Block::Break(break_block)] = &diagram.blocks[..] else {
+            panic!("expected one break block");
+        };
+        assert_eq!(break_block.condition, "timeout");
+        assert_eq!(break_block.edges.len(), 1);
+        assert_eq!(break_block.end.edges, 1);
+        assert_eq!(diagram.participants.len(), 2);
+    }
+
+    #[test]
+    fn test_return() {
+        let tokens = vec![
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Server".to_string()),
+            Token::ArrowMessage("Login".to_string()),
+            Token::Return(Some("Token".to_string())),
+            Token::Return(None),
+        ];
+        let diagram = parse(tokens).unwrap();
+        let edges: Vec<(&str, &str, Option<&str>)> = diagram
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.from.as_str(),
+                    edge.to.as_str(),
+                    edge.message.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("Client", "Server", Some("Login")),
+                ("Server", "Client", Some("Token")),
+                // A return answers the previous edge, which may itself be a return
+                ("Client", "Server", None),
+            ]
+        );
+
+        let err = parse(vec![Token::Return(None)]).unwrap_err();
+        assert!(err.message.contains("'return' without a previous edge"));
+    }
+
+    #[test]
+    fn test_invalid_participant_tokens() {
+        let long_name = "A".repeat(81);
+        let tokens = vec![
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant(long_name),
+        ];
+        let err = parse(tokens).unwrap_err();
+        assert!(err.message.contains("is too long (max 80 characters)"));
+
+        let err = parse(vec![Token::Declaration {
+            name: "Cli\nent".to_string(),
+            kind: ParticipantKind::Participant,
+        }])
+        .unwrap_err();
+        assert!(err.message.contains("cannot contain a new line"));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        use crate::tokenizer::tokenize;
+
+        let input = "\
+title Checkout
+participant Auditor
+Server <- Client: {bold red} POST /orders
+note over Server: Validates the cart
+par
+    Server -> Stock: Reserve
+    note over Stock: Locks rows
+and payment
+    critical {charge}
+        Server -> Payments: {below} Charge
+        return: Receipt
+    end
+end
+---
+ref over Server, Stock: Release on failure
+break out of stock
+    group retry
+        Server -> Server: Retry
+    end
+end
+group
+end
+note over Auditor: Logged
+Client <- Server: {above}";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let text = diagram.to_string();
+        assert_eq!(parse(tokenize(&text).unwrap()).unwrap(), diagram);
+
+        // Arrows point the way of the participant order
+        assert!(text.starts_with("title Checkout\nparticipant Auditor\nparticipant Client\n"));
+        assert!(text.contains("\nClient -> Server: {bold red} POST /orders\n"));
+        assert!(text.contains("\nServer <- Payments: Receipt\n"));
+        assert!(text.ends_with("\nClient <- Server: {above}\n"));
+    }
+
+    #[test]
+    fn test_create_and_destroy() {
+        use crate::tokenizer::tokenize;
+
+        let input = "\
+Client -> Server: Order
+create participant Worker
+note over Server: Queues the job
+Server -> Worker: Start
+Worker -> Server: Done
+destroy Worker
+Server -> Client: Shipped";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        assert_eq!(
+            diagram.lifetimes,
+            [Lifetime {
+                participant: "Worker".to_string(),
+                created_by: Some(1),
+                destroyed_at: Some(Position {
+                    edges: 3,
+                    notes: 1,
+                    spacers: 0,
+                    refs: 0,
+                }),
+            }]
+        );
+        let text = diagram.to_string();
+        assert!(text.contains("\ncreate Worker\nServer -> Worker: Start\n"));
+        assert_eq!(parse(tokenize(&text).unwrap()).unwrap(), diagram);
+
+        let message = |input: &str| parse(tokenize(input).unwrap()).unwrap_err().message;
+        assert_eq!(
+            message("A -> B\ncreate B\nA -> B"),
+            "Participant 'B' is used before it is created"
+        );
+        assert_eq!(
+            message("create B\nnote over B: Hi\nA -> B"),
+            "Participant 'B' is used before its creating edge"
+        );
+        assert_eq!(
+            message("A -> B\ndestroy B\nB -> A"),
+            "Participant 'B' is used after it is destroyed"
+        );
+        assert_eq!(
+            message("create B"),
+            "Participant 'B' is created but has no edge after its 'create' line"
+        );
+        assert_eq!(
+            message("destroy B"),
+            "Cannot destroy unknown participant 'B'"
+        );
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let edge = |from: &str, to: &str| {
+            vec![
+                Token::Participant(from.to_string()),
+                Token::RightArrow,
+                Token::Participant(to.to_string()),
+            ]
+        };
+        let mut tokens = vec![Token::Group("Checkout".to_string())];
+        tokens.extend(edge("Client", "Server"));
+        tokens.push(Token::Group("Payment".to_string()));
+        tokens.extend(edge("Server", "Bank"));
+        tokens.push(Token::End);
+        tokens.push(Token::End);
+        let diagram = parse(tokens).unwrap();
+
+        let [Block::Group(outer), Block::Group(inner)] = &diagram.blocks[..] else {
+            panic!("expected two groups");
+        };
+        assert_eq!(outer.label, "Checkout");
+        // Edges of the inner group belong to the outer one too
+        assert_eq!(outer.edges.len(), 2);
+        assert_eq!(inner.label, "Payment");
+        assert_eq!(inner.edges.len(), 1);
+        assert_eq!(inner.edges[0].to, "Bank");
+    }
+
+    #[test]
+    fn test_participant_kinds() {
+        use crate::tokenizer::tokenize;
+
+        let diagram = parse(tokenize("actor Client\nClient -> Server").unwrap()).unwrap();
+        assert_eq!(diagram.kind("Client"), ParticipantKind::Actor);
+        assert_eq!(diagram.kind("Server"), ParticipantKind::Participant);
+        assert!(
+            diagram
+                .to_string()
+                .starts_with("actor Client\nparticipant Server\n")
+        );
+
+        // Redeclaring as a plain participant keeps the kind, another kind is an error
+        let diagram = parse(tokenize("queue Jobs\nparticipant Jobs").unwrap()).unwrap();
+        assert_eq!(diagram.kind("Jobs"), ParticipantKind::Queue);
+        let err = parse(tokenize("queue Jobs\ndatabase Jobs").unwrap()).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Participant 'Jobs' is declared both as queue and as database"
+        );
+    }
+
+    #[test]
+    fn test_sequence_parser_reuse() {
+        let parser = SequenceParser::with_config(TokenizeConfig {
+            strict_participants: true,
+            ..TokenizeConfig::default()
+        });
+
+        let tokens = parser
+            .tokenize("participant A\nparticipant B\nA -> B: Hi")
+            .unwrap();
+        assert_eq!(parser.parse(tokens).unwrap().edges.len(), 1);
+        // The same options apply to the next input
+        assert!(parser.tokenize("A -> B: Hi").is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        use crate::tokenizer::tokenize;
+
+        let input = "\
+participant Auditor
+Client -> Server: Login(username, password)
+Server -> Database: ValidateCredentials()
+Server <- Database: UserData
+Server -> Cache: StoreSession()
+Client <- Server: LoginSuccess(token)
+Client -> Server";
+        let stats = parse(tokenize(input).unwrap()).unwrap().stats();
+
+        assert_eq!(stats.edges, 6);
+        assert_eq!(stats.busiest.as_deref(), Some("Server"));
+        assert_eq!(
+            stats.participants[2],
+            (
+                "Server".to_string(),
+                ParticipantStats {
+                    outgoing: 3,
+                    incoming: 3,
+                }
+            )
+        );
+        assert_eq!(stats.orphans, ["Auditor"]);
+        assert_eq!(stats.unlabeled_edges, 1);
+        assert_eq!(stats.max_message_width, "Login(username, password)".len());
+    }
+
+    #[test]
+    fn test_declarations_set_participant_order() {
+        let tokens = vec![
+            Token::Declaration {
+                name: "Database".to_string(),
+                kind: ParticipantKind::Participant,
+            },
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Database".to_string()),
+            Token::Declaration {
+                name: "Unused".to_string(),
+                kind: ParticipantKind::Participant,
+            },
+        ];
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.participants, ["Database", "Client", "Unused"]);
+    }
+
+    #[test]
+    fn test_ref_registers_participants() {
+        let tokens = vec![
+            Token::Participant("Client".to_string()),
+            Token::RightArrow,
+            Token::Participant("Server".to_string()),
+            Token::Ref {
+                participants: vec!["Client".to_string(), "Auth".to_string()],
+                label: "Login".to_string(),
+            },
+        ];
+        let diagram = parse(tokens).unwrap();
+        assert_eq!(diagram.participants, ["Client", "Server", "Auth"]);
+        assert_eq!(diagram.refs[0].label, "Login");
+        assert_eq!(diagram.refs[0].position.edges, 1);
+    }
 }
@@ -1,129 +1,359 @@
 use unicode_width::UnicodeWidthStr;
 
+use crate::canvas::{Canvas, OutputOptions};
+use crate::color::Style;
 use crate::layout::{
-    ArrowDirection, EdgeLayout, LifelineLayout, PARTICIPANT_HEIGHT, ParticipantLayout,
-    SequenceDiagramLayout,
+    ArrowDirection, BlockKind, BlockLayout, EdgeLayout, LifelineLayout, NoteLayout,
+    ParticipantLayout, REF_TAG_WIDTH, RefLayout, SequenceDiagramLayout, TextLayout,
+    calculate_sequence_layout_with_config,
 };
+use crate::options::SequenceOptions;
+use crate::parser::{ParticipantKind, SequenceDiagram};
 
-#[derive(Debug)]
-pub struct Canvas {
-    pub grid: Vec<Vec<char>>,
-    pub width: usize,
-    pub height: usize,
+/// Faint `┈` lines of spacers
+const DIVIDER_STYLE: Style = Style {
+    color: None,
+    bold: false,
+    dim: true,
+};
+
+/// Label of a `break` block
+const BREAK_LABEL_STYLE: Style = Style {
+    color: None,
+    bold: true,
+    dim: false,
+};
+
+/// Characters of a block frame
+struct FrameChars {
+    horizontal: char,
+    vertical: char,
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+    /// Where a border crosses a lifeline
+    crossing: char,
+    divider_left: char,
+    divider_right: char,
 }
 
-impl Canvas {
-    pub fn new(width: usize, height: usize) -> Self {
-        let grid = vec![vec![' '; width]; height];
-        Canvas {
-            grid,
-            width: width,
-            height: height,
-        }
-    }
+const SINGLE_FRAME: FrameChars = FrameChars {
+    horizontal: '─',
+    vertical: '│',
+    top_left: '┌',
+    top_right: '┐',
+    bottom_left: '└',
+    bottom_right: '┘',
+    crossing: '┼',
+    divider_left: '├',
+    divider_right: '┤',
+};
 
-    pub fn set_char(&mut self, x: usize, y: usize, ch: char) {
-        if y < self.height && x < self.width {
-            self.grid[y][x] = ch;
-        } else {
-            panic!("Index out of range.")
-        }
+const DASHED_FRAME: FrameChars = FrameChars {
+    horizontal: '╌',
+    vertical: '╎',
+    ..SINGLE_FRAME
+};
+
+const DOUBLE_FRAME: FrameChars = FrameChars {
+    horizontal: '═',
+    vertical: '║',
+    top_left: '╔',
+    top_right: '╗',
+    bottom_left: '╚',
+    bottom_right: '╝',
+    crossing: '╪',
+    divider_left: '╟',
+    divider_right: '╢',
+};
+
+/// Top and bottom borders of a participant box as left corner, line and right corner. They vary
+/// with the stereotype of the participant.
+struct BoxBorders {
+    top: [char; 3],
+    bottom: [char; 3],
+}
+
+const PLAIN_BORDERS: BoxBorders = BoxBorders {
+    top: ['┌', '─', '┐'],
+    bottom: ['└', '─', '┘'],
+};
+
+fn box_borders(kind: ParticipantKind) -> BoxBorders {
+    match kind {
+        ParticipantKind::Participant => PLAIN_BORDERS,
+        // Doubled top, like a head
+        ParticipantKind::Actor => BoxBorders {
+            top: ['╒', '═', '╕'],
+            ..PLAIN_BORDERS
+        },
+        // Rounded like a cylinder
+        ParticipantKind::Database => BoxBorders {
+            top: ['╭', '─', '╮'],
+            bottom: ['╰', '─', '╯'],
+        },
+        ParticipantKind::Queue => BoxBorders {
+            top: ['┌', '╌', '┐'],
+            bottom: ['└', '╌', '┘'],
+        },
     }
+}
 
-    pub fn get_char(&self, x: usize, y: usize) -> char {
-        if y < self.height && x < self.width {
-            self.grid[y][x]
-        } else {
-            panic!("Index out of range.")
-        }
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Color, padding and final newline of the output
+    pub output: OutputOptions,
+    /// Replace the box-drawing characters with plain ASCII (`+`, `-`, `|`)
+    pub ascii: bool,
+}
+
+/// Replaces every char of the canvas with its plain ASCII counterpart, keeping the styles
+fn to_ascii(canvas: &mut Canvas) {
+    for ch in canvas.grid.iter_mut().flatten() {
+        *ch = ascii_char(*ch);
     }
+}
 
-    pub fn to_string(&self) -> String {
-        self.grid
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
+/// Plain ASCII replacement of a box-drawing character. Other characters are kept.
+fn ascii_char(ch: char) -> char {
+    match ch {
+        '┌' | '┐' | '└' | '┘' | '┬' | '┴' | '├' | '┤' | '┼' | '╔' | '╗' | '╚' | '╝' | '╟' | '╢'
+        | '╪' | '╒' | '╕' | '╧' | '╭' | '╮' | '╰' | '╯' => '+',
+        '─' | '╌' => '-',
+        '═' => '=',
+        '│' | '║' => '|',
+        '╎' => ':',
+        '┄' | '┈' => '.',
+        '✕' => 'X',
+        _ => ch,
     }
 }
 
 pub fn render(seq_diagram_layout: &SequenceDiagramLayout) -> String {
+    render_with_options(seq_diagram_layout, &RenderOptions::default())
+}
+
+/// Lays out and renders a diagram with the layout and render parts of `options`
+pub fn render_diagram(sequence_diagram: &SequenceDiagram, options: &SequenceOptions) -> String {
+    let layout = calculate_sequence_layout_with_config(sequence_diagram, &options.layout);
+    render_with_options(&layout, &options.render)
+}
+
+pub fn render_with_options(
+    seq_diagram_layout: &SequenceDiagramLayout,
+    options: &RenderOptions,
+) -> String {
     let mut canvas = Canvas::new(seq_diagram_layout.width, seq_diagram_layout.height);
 
+    for text_layout in [&seq_diagram_layout.title, &seq_diagram_layout.legend]
+        .into_iter()
+        .flatten()
+    {
+        draw_text(&mut canvas, text_layout);
+    }
+
     for participant_layout in &seq_diagram_layout.participant_layouts {
         draw_participant_boxes(&mut canvas, participant_layout);
     }
     for lifeline_layout in &seq_diagram_layout.lifeline_layouts {
         draw_lifeline(&mut canvas, lifeline_layout);
     }
+    for block_layout in &seq_diagram_layout.block_layouts {
+        draw_block(&mut canvas, block_layout);
+
+        if options.output.color && block_layout.kind == BlockKind::Break {
+            // Inside the blanks around the label
+            let x = block_layout.x_start + 3;
+            let width = block_layout.label.width();
+            let y = block_layout.y_start;
+            canvas.set_style(x, y, x + width - 1, y, BREAK_LABEL_STYLE);
+        }
+    }
+    for (x_start, y, x_end) in seq_diagram_layout
+        .spacer_layouts
+        .iter()
+        .filter_map(|spacer| spacer.divider)
+    {
+        // Only in the gaps, so lifelines and block frames stay whole
+        for x in x_start..=x_end {
+            if canvas.get_char(x, y) == ' ' {
+                canvas.set_char(x, y, '┈');
+                if options.output.color {
+                    canvas.set_style(x, y, x, y, DIVIDER_STYLE);
+                }
+            }
+        }
+    }
     for edge_layout in &seq_diagram_layout.edge_layouts {
         draw_edge(&mut canvas, edge_layout);
+
+        // A style with only a label position has nothing to color
+        if options.output.color
+            && let Some(edge_style) = edge_layout.style
+            && (edge_style.color.is_some() || edge_style.bold)
+        {
+            let style = Style {
+                color: edge_style.color,
+                bold: edge_style.bold,
+                dim: false,
+            };
+            style_edge(&mut canvas, edge_layout, style);
+        }
+    }
+    for note_layout in &seq_diagram_layout.note_layouts {
+        draw_note(&mut canvas, note_layout);
+    }
+    for ref_layout in &seq_diagram_layout.ref_layouts {
+        draw_ref(&mut canvas, ref_layout);
+    }
+    if options.ascii {
+        to_ascii(&mut canvas);
     }
 
-    canvas.to_string()
+    canvas.to_string_with_options(&options.output)
 }
 
 fn draw_participant_boxes(canvas: &mut Canvas, participant_layout: &ParticipantLayout) {
-    let half_width = (participant_layout.width + 1) / 2;
+    let half_width = participant_layout.width.div_ceil(2);
 
     let center_x = participant_layout.center_x;
-    let left_x = center_x - half_width + 1;
+    let left_x = (center_x + 1).saturating_sub(half_width);
     let right_x = left_x + participant_layout.width - 1;
 
     draw_box(
         canvas,
-        center_x,
+        participant_layout,
         left_x,
         right_x,
         participant_layout.top_box_y,
-        participant_layout.name.clone(),
         true,
     );
 
-    draw_box(
-        canvas,
-        center_x,
-        left_x,
-        right_x,
-        participant_layout.bottom_box_y - PARTICIPANT_HEIGHT,
-        participant_layout.name.clone(),
-        false,
-    );
+    if let Some(bottom_box_y) = participant_layout.bottom_box_y {
+        draw_box(
+            canvas,
+            participant_layout,
+            left_x,
+            right_x,
+            bottom_box_y.saturating_sub(participant_layout.height),
+            false,
+        );
+    }
+}
+
+fn draw_text(canvas: &mut Canvas, text_layout: &TextLayout) {
+    for (i, ch) in text_layout.text.chars().enumerate() {
+        canvas.set_char(text_layout.x + i, text_layout.y, ch);
+    }
+}
+
+fn draw_note(canvas: &mut Canvas, note_layout: &NoteLayout) {
+    let center_x = note_layout.center_x;
+    let left_x = (center_x + 1).saturating_sub(note_layout.width.div_ceil(2));
+    let right_x = left_x + note_layout.width - 1;
+    let y = note_layout.y;
+
+    // Top border
+    canvas.set_char(left_x, y, '┌');
+    for x in left_x + 1..right_x {
+        canvas.set_char(x, y, '─');
+    }
+    canvas.set_char(right_x, y, '┐');
+    canvas.set_char(center_x, y, '┴');
+
+    // Middle line
+    canvas.set_char(left_x, y + 1, '│');
+    for x in left_x + 1..right_x {
+        canvas.set_char(x, y + 1, ' ');
+    }
+    let text_start_x = center_x.saturating_sub(note_layout.text.width().saturating_sub(1) / 2);
+    for (i, ch) in note_layout.text.chars().enumerate() {
+        canvas.set_char(text_start_x + i, y + 1, ch);
+    }
+    canvas.set_char(right_x, y + 1, '│');
+
+    // Bottom border
+    canvas.set_char(left_x, y + 2, '└');
+    for x in left_x + 1..right_x {
+        canvas.set_char(x, y + 2, '─');
+    }
+    canvas.set_char(right_x, y + 2, '┘');
+    canvas.set_char(center_x, y + 2, '┬');
+}
+
+/// Draws a ref box over the lifelines it spans:
+/// ```text
+/// ┌─────┬──────────────┐
+/// │ ref │ Authenticate │
+/// └─────┴──────────────┘
+/// ```
+fn draw_ref(canvas: &mut Canvas, ref_layout: &RefLayout) {
+    let (left_x, right_x, y) = (ref_layout.x_start, ref_layout.x_end, ref_layout.y);
+    let tag_x = left_x + REF_TAG_WIDTH - 1;
+
+    for x in left_x + 1..right_x {
+        canvas.set_char(x, y, '─');
+        canvas.set_char(x, y + 1, ' ');
+        canvas.set_char(x, y + 2, '─');
+    }
+    for (dy, left, tag, right) in [(0, '┌', '┬', '┐'), (1, '│', '│', '│'), (2, '└', '┴', '┘')]
+    {
+        canvas.set_char(left_x, y + dy, left);
+        canvas.set_char(tag_x, y + dy, tag);
+        canvas.set_char(right_x, y + dy, right);
+    }
+
+    for (i, ch) in "ref".chars().enumerate() {
+        canvas.set_char(left_x + 2 + i, y + 1, ch);
+    }
+    let label_width = ref_layout.label.width();
+    let label_x = (tag_x + right_x + 1).saturating_sub(label_width) / 2;
+    for (i, ch) in ref_layout.label.chars().enumerate() {
+        canvas.set_char(label_x + i, y + 1, ch);
+    }
 }
 
 fn draw_box(
     canvas: &mut Canvas,
-    center_x: usize,
+    participant_layout: &ParticipantLayout,
     left_x: usize,
     right_x: usize,
     y: usize,
-    name: String,
     is_top_box: bool,
 ) {
+    let center_x = participant_layout.center_x;
+    let name = &participant_layout.label;
+    let borders = box_borders(participant_layout.kind);
+    let [top_left, top, top_right] = borders.top;
+    let [bottom_left, bottom, bottom_right] = borders.bottom;
+
     // Top border
-    canvas.set_char(left_x, y, '┌');
+    canvas.set_char(left_x, y, top_left);
     for x in left_x + 1..right_x {
-        canvas.set_char(x, y, '─');
+        canvas.set_char(x, y, top);
     }
-    canvas.set_char(right_x, y, '┐');
+    canvas.set_char(right_x, y, top_right);
 
     // Middle line
     canvas.set_char(left_x, y + 1, '│');
-    let name_start_x = center_x - (name.width() - 1) / 2;
+    let name_start_x = center_x.saturating_sub(name.width().saturating_sub(1) / 2);
     for (i, ch) in name.chars().enumerate() {
         canvas.set_char(name_start_x + i, y + 1, ch);
     }
     canvas.set_char(right_x, y + 1, '│');
 
     // Bottom border
-    canvas.set_char(left_x, y + 2, '└');
+    canvas.set_char(left_x, y + 2, bottom_left);
     for x in left_x + 1..right_x {
-        canvas.set_char(x, y + 2, '─');
+        canvas.set_char(x, y + 2, bottom);
     }
-    canvas.set_char(right_x, y + 2, '┘');
+    canvas.set_char(right_x, y + 2, bottom_right);
 
     if is_top_box {
         canvas.set_char(center_x, y + 2, '┬');
+    } else if top == '═' {
+        canvas.set_char(center_x, y, '╧');
     } else {
         canvas.set_char(center_x, y, '┴');
     }
@@ -133,6 +363,71 @@ fn draw_lifeline(canvas: &mut Canvas, lifeline_layout: &LifelineLayout) {
     for y in lifeline_layout.start_y..=lifeline_layout.end_y {
         canvas.set_char(lifeline_layout.x, y, '│');
     }
+    if lifeline_layout.destroyed {
+        canvas.set_char(lifeline_layout.x, lifeline_layout.end_y, '✕');
+    }
+}
+
+fn draw_block(canvas: &mut Canvas, block_layout: &BlockLayout) {
+    let frame = match block_layout.kind {
+        BlockKind::Par | BlockKind::Group => &SINGLE_FRAME,
+        BlockKind::Critical => &DOUBLE_FRAME,
+        BlockKind::Break => &DASHED_FRAME,
+    };
+    let (left_x, right_x) = (block_layout.x_start, block_layout.x_end);
+    let (top_y, bottom_y) = (block_layout.y_start, block_layout.y_end);
+
+    for y in top_y + 1..bottom_y {
+        canvas.set_char(left_x, y, frame.vertical);
+        canvas.set_char(right_x, y, frame.vertical);
+    }
+    draw_block_border(canvas, frame, left_x, right_x, top_y);
+    canvas.set_char(left_x, top_y, frame.top_left);
+    canvas.set_char(right_x, top_y, frame.top_right);
+    draw_block_border(canvas, frame, left_x, right_x, bottom_y);
+    canvas.set_char(left_x, bottom_y, frame.bottom_left);
+    canvas.set_char(right_x, bottom_y, frame.bottom_right);
+    draw_block_label(canvas, left_x, top_y, &block_layout.label);
+
+    for divider in &block_layout.dividers {
+        canvas.set_char(left_x, divider.y, frame.divider_left);
+        for x in left_x + 1..right_x {
+            // Lifelines show through the dashes
+            if canvas.get_char(x, divider.y) != '│' {
+                canvas.set_char(x, divider.y, '┄');
+            }
+        }
+        canvas.set_char(right_x, divider.y, frame.divider_right);
+        draw_block_label(canvas, left_x, divider.y, &divider.label);
+    }
+}
+
+/// A horizontal border between the corners, crossing the lifelines
+fn draw_block_border(
+    canvas: &mut Canvas,
+    frame: &FrameChars,
+    left_x: usize,
+    right_x: usize,
+    y: usize,
+) {
+    for x in left_x + 1..right_x {
+        let ch = if canvas.get_char(x, y) == '│' {
+            frame.crossing
+        } else {
+            frame.horizontal
+        };
+        canvas.set_char(x, y, ch);
+    }
+}
+
+/// Writes ` label ` into a border, one column in from the corner
+fn draw_block_label(canvas: &mut Canvas, left_x: usize, y: usize, label: &str) {
+    if label.is_empty() {
+        return;
+    }
+    for (i, ch) in format!(" {label} ").chars().enumerate() {
+        canvas.set_char(left_x + 2 + i, y, ch);
+    }
 }
 
 fn draw_edge(canvas: &mut Canvas, edge_layout: &EdgeLayout) {
@@ -142,28 +437,148 @@ fn draw_edge(canvas: &mut Canvas, edge_layout: &EdgeLayout) {
         ArrowDirection::Left => (edge_layout.end_x, edge_layout.start_x, '<'),
     };
 
-    let edge_y: usize = if edge_layout.message.is_some() {
-        edge_layout.y + 1
-    } else {
-        edge_layout.y
-    };
+    let edge_y = edge_layout.arrow_y;
 
     for x in start_x..=end_x {
         canvas.set_char(x, edge_y, '─');
     }
 
+    if let (Some(msg), Some(message_y)) = (&edge_layout.message, edge_layout.message_y) {
+        let message_start_x = ((start_x + end_x) / 2).saturating_sub(msg.width() / 2);
+
+        for (i, ch) in msg.chars().enumerate() {
+            canvas.set_char(message_start_x + i, message_y, ch);
+        }
+    }
+
+    // Drawn after the message, which may be centered over the arrow
     let arrowhead_x: usize = match edge_layout.direction {
         ArrowDirection::Right => end_x,
         ArrowDirection::Left => start_x,
     };
     canvas.set_char(arrowhead_x, edge_y, arrow_head);
+}
 
-    if let Some(msg) = &edge_layout.message {
-        let message_start_x = (start_x + end_x) / 2 - msg.width() / 2;
-        let message_y = edge_layout.y;
+/// Styles the arrow line and the message of an edge, mirroring `draw_edge`
+fn style_edge(canvas: &mut Canvas, edge_layout: &EdgeLayout, style: Style) {
+    let start_x = edge_layout.start_x.min(edge_layout.end_x);
+    let end_x = edge_layout.start_x.max(edge_layout.end_x);
 
-        for (i, ch) in msg.chars().enumerate() {
-            canvas.set_char(message_start_x + i, message_y, ch);
+    let edge_y = edge_layout.arrow_y;
+    canvas.set_style(start_x, edge_y, end_x, edge_y, style);
+
+    if let (Some(msg), Some(message_y)) = (&edge_layout.message, edge_layout.message_y)
+        && !msg.is_empty()
+    {
+        let message_start_x = ((start_x + end_x) / 2).saturating_sub(msg.width() / 2);
+        let message_end_x = message_start_x + msg.chars().count() - 1;
+        canvas.set_style(message_start_x, message_y, message_end_x, message_y, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Color, RESET_CODE};
+    use crate::layout::calculate_sequence_layout;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenize;
+
+    use super::*;
+
+    #[test]
+    fn test_ascii_render() {
+        let input = "Client -> Server: GET /api/data";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let options = SequenceOptions::new().ascii(true);
+        let output = render_diagram(&diagram, &options);
+
+        assert!(output.is_ascii());
+        let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        assert_eq!(lines[1], " +--------+      +--------+");
+        assert_eq!(lines[2], " | Client |      | Server |");
+        assert_eq!(lines[3], " +---+----+      +---+----+");
+        assert_eq!(lines[6], "     |-------------->|");
+    }
+
+    #[test]
+    fn test_participant_kinds() {
+        let render = |input: &str| {
+            let diagram = parse(tokenize(input).unwrap()).unwrap();
+            render_diagram(&diagram, &SequenceOptions::new())
+        };
+        let plain = render("participant Client\nClient -> Server");
+        let actor = render("actor Client\nClient -> Server");
+        assert_ne!(actor, plain);
+
+        let lines: Vec<&str> = actor.lines().map(str::trim_end).collect();
+        assert_eq!(lines[1], " ╒════════╕┌────────┐");
+        assert_eq!(lines[2], " │ Client ││ Server │");
+        assert_eq!(lines[3], " └───┬────┘└───┬────┘");
+        assert!(lines.iter().any(|line| line.starts_with(" ╒═══╧════╕")));
+
+        let database = render("database Store\nClient -> Store");
+        let lines: Vec<&str> = database.lines().map(str::trim_end).collect();
+        assert!(lines[1].starts_with(" ╭───────╮"), "{}", database);
+        assert!(lines[3].starts_with(" ╰───┬───╯"), "{}", database);
+    }
+
+    #[test]
+    fn test_break_label_is_bold() {
+        let input = "break timeout\nServer -> Client\nend";
+        let layout = calculate_sequence_layout(&parse(tokenize(input).unwrap()).unwrap());
+        let block = &layout.block_layouts[0];
+        let colored = render_with_options(
+            &layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        let lines: Vec<&str> = colored.lines().collect();
+        let bold = format!(
+            "{}break timeout{}",
+            BREAK_LABEL_STYLE.escape_code(),
+            RESET_CODE
+        );
+        assert!(lines[block.y_start].contains(&bold));
+    }
+
+    #[test]
+    fn test_edge_style_colors() {
+        let input = "\
+Client -> Server: {red}Error
+Client <- Server: {below}OK";
+        let diagram = parse(tokenize(input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout(&diagram);
+
+        let plain = render(&layout);
+        assert!(!plain.contains('{'));
+        assert!(!plain.contains('\x1b'));
+
+        let colored = render_with_options(
+            &layout,
+            &RenderOptions {
+                output: OutputOptions {
+                    color: true,
+                    ..OutputOptions::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        let red = Style {
+            color: Some(Color::Red),
+            ..Style::default()
         }
+        .escape_code();
+        let lines: Vec<&str> = colored.lines().collect();
+        let error_edge = &layout.edge_layouts[0];
+        assert!(lines[error_edge.y].contains(&format!("{}Error{}", red, RESET_CODE)));
+        assert!(lines[error_edge.y + 1].contains(&red));
+        // Unstyled edges stay plain, as do edges styled with only a label position
+        assert!(!lines[layout.edge_layouts[1].y].contains('\x1b'));
+        assert_eq!(colored.replace(&red, "").replace(RESET_CODE, ""), plain);
     }
 }
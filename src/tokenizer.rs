@@ -1,11 +1,56 @@
 use regex::Regex;
 
-#[derive(Debug, PartialEq)]
+use crate::color::Color;
+use crate::parser::{EdgeStyle, LabelPosition, ParticipantKind};
+use crate::validation::validate_participant_name;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     LeftArrow,
     RightArrow,
     ArrowMessage(String),
     Participant(String),
+    Note {
+        participant: String,
+        text: String,
+    },
+    /// Placeholder for an interaction defined elsewhere, spanning the participants
+    Ref {
+        participants: Vec<String>,
+        label: String,
+    },
+    Title(String),
+    /// Declares a participant from a `participant <name>` line, before any edge uses it. The
+    /// line may start with a stereotype keyword instead, e.g. `actor <name>`.
+    Declaration {
+        name: String,
+        kind: ParticipantKind,
+    },
+    /// Reply to the previous edge from a `return` or `return: <message>` line
+    Return(Option<String>),
+    /// Left-to-right participant order from an `order: A, B, C` directive on the first line
+    OrderHint(Vec<String>),
+    /// Leading `{style}` of an edge message. Follows the second participant.
+    EdgeStyle(EdgeStyle),
+    /// Opens a parallel block, with an optional label
+    Par(String),
+    /// Opens a critical region, with an optional label
+    Critical(String),
+    /// Opens a region that ends the interaction when its condition holds
+    Break(String),
+    /// Opens a plain labelled frame
+    Group(String),
+    /// Starts the next region of the enclosing block, with an optional label
+    And(String),
+    /// Closes the innermost open block
+    End,
+    /// Blank rows between two phases of the diagram, from a `---` or `spacer` line
+    Spacer,
+    /// Declares a participant whose box and lifeline start at its first edge, from a
+    /// `create participant <name>` or `create <name>` line
+    Create(String),
+    /// Ends the lifeline of a participant with a cross, from a `destroy <name>` line
+    Destroy(String),
 }
 
 #[derive(Debug)]
@@ -20,15 +65,72 @@ impl std::fmt::Display for TokenizeError {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct TokenizeConfig {
+    /// Reject arrows that touch a participant, e.g. `Client->Server`
+    pub require_arrow_spacing: bool,
+    /// Reject edges, notes and refs naming a participant without an earlier `participant`
+    /// line, to catch typos. Otherwise such participants are added when first used.
+    pub strict_participants: bool,
+}
+
 pub fn tokenize(input: &str) -> Result<Vec<Token>, TokenizeError> {
-    let mut tokens: Vec<Token> = Vec::new();
+    tokenize_with_config(input, &TokenizeConfig::default())
+}
 
-    for (line_number, line) in input.trim().lines().enumerate() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        tokenize_line(line, line_number + 1, &mut tokens)?;
+pub fn tokenize_with_config(
+    input: &str,
+    config: &TokenizeConfig,
+) -> Result<Vec<Token>, TokenizeError> {
+    tokenize_iter_with_config(input, config).collect()
+}
+
+/// Yields the tokens lazily, line by line, so that they can be consumed before the whole input
+/// is tokenized
+pub fn tokenize_iter(input: &str) -> impl Iterator<Item = Result<Token, TokenizeError>> + '_ {
+    tokenize_iter_with_config(input, &TokenizeConfig::default())
+}
+
+/// A line that fails to tokenize yields its error in place of its tokens. The lines after it
+/// are still tokenized.
+pub fn tokenize_iter_with_config<'a>(
+    input: &'a str,
+    config: &TokenizeConfig,
+) -> impl Iterator<Item = Result<Token, TokenizeError>> + use<'a> {
+    let config = config.clone();
+    let mut declared: Vec<String> = Vec::new();
+
+    input.trim().lines().enumerate().flat_map(
+        move |(line_number, line)| match tokenize_source_line(
+            line,
+            line_number + 1,
+            &config,
+            &mut declared,
+        ) {
+            Ok(tokens) => tokens.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        },
+    )
+}
+
+/// Tokenizes an untrimmed input line. Blank lines have no tokens.
+fn tokenize_source_line(
+    line: &str,
+    line_number: usize,
+    config: &TokenizeConfig,
+    declared: &mut Vec<String>,
+) -> Result<Vec<Token>, TokenizeError> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Columns in errors count from the start of the untrimmed line
+    let indent = line.chars().count() - line.trim_start().chars().count();
+    let mut tokens = Vec::new();
+    tokenize_line(trimmed, line_number, indent, config, &mut tokens)?;
+
+    if config.strict_participants {
+        check_declared(&tokens, trimmed, line_number, indent, declared)?;
     }
 
     Ok(tokens)
@@ -37,9 +139,79 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, TokenizeError> {
 fn tokenize_line(
     line: &str,
     line_number: usize,
+    indent: usize,
+    config: &TokenizeConfig,
     tokens: &mut Vec<Token>,
 ) -> Result<(), TokenizeError> {
+    if let Some(rest) = line.strip_prefix("note over ") {
+        return tokenize_note(rest, line_number, tokens);
+    }
+
+    if let Some(rest) = line.strip_prefix("ref over ") {
+        return tokenize_ref(rest, line_number, tokens);
+    }
+
+    if let Some(title) = line.strip_prefix("title ") {
+        tokens.push(Token::Title(title.trim().to_string()));
+        return Ok(());
+    }
+
+    if let Some(names) = line.strip_prefix("order:") {
+        return tokenize_order_hint(names, line_number, tokens);
+    }
+
+    if line == "---" || line == "spacer" {
+        tokens.push(Token::Spacer);
+        return Ok(());
+    }
+
     let arrow_regex = Regex::new(r"->|<-").unwrap();
+    // Keywords never contain an arrow, so `and -> Server` is still an edge
+    if !arrow_regex.is_match(line) {
+        for kind in ParticipantKind::ALL {
+            if let Some(name) = line
+                .strip_prefix(kind.keyword())
+                .and_then(|rest| rest.strip_prefix(' '))
+            {
+                let name = name.trim();
+                validate_participant(name, line_number, "Declared")?;
+                tokens.push(Token::Declaration {
+                    name: name.to_string(),
+                    kind,
+                });
+                return Ok(());
+            }
+        }
+        if let Some(rest) = line.strip_prefix("create ") {
+            let rest = rest.trim();
+            let name = rest.strip_prefix("participant ").unwrap_or(rest).trim();
+            validate_participant(name, line_number, "Created")?;
+            tokens.push(Token::Create(name.to_string()));
+            return Ok(());
+        }
+        if let Some(name) = line.strip_prefix("destroy ") {
+            let name = name.trim();
+            validate_participant(name, line_number, "Destroyed")?;
+            tokens.push(Token::Destroy(name.to_string()));
+            return Ok(());
+        }
+        if let Some(token) = block_keyword(line) {
+            tokens.push(token);
+            return Ok(());
+        }
+        if line == "return" {
+            tokens.push(Token::Return(None));
+            return Ok(());
+        }
+        if let Some(message) = line.strip_prefix("return:") {
+            let message = message.trim();
+            tokens.push(Token::Return(
+                Some(message.to_string()).filter(|message| !message.is_empty()),
+            ));
+            return Ok(());
+        }
+    }
+
     let arrow_match = arrow_regex.find(line).ok_or_else(|| TokenizeError {
         line: line_number,
         message: "Missing arrow ('->' or '<-')".to_string(),
@@ -52,6 +224,24 @@ fn tokenize_line(
         });
     }
 
+    if config.require_arrow_spacing {
+        let before = line[..arrow_match.start()].chars().next_back();
+        let after = line[arrow_match.end()..].chars().next();
+        if [before, after]
+            .iter()
+            .any(|ch| ch.is_some_and(|ch| !ch.is_whitespace()))
+        {
+            let column = indent + line[..arrow_match.start()].chars().count() + 1;
+            return Err(TokenizeError {
+                line: line_number,
+                message: format!(
+                    "Arrow '{}' at column {column} must be surrounded by spaces",
+                    arrow_match.as_str()
+                ),
+            });
+        }
+    }
+
     let first_participant = line[..arrow_match.start()].trim();
     validate_participant(first_participant, line_number, "First")?;
     tokens.push(Token::Participant(first_participant.to_string()));
@@ -69,7 +259,13 @@ fn tokenize_line(
         validate_participant(second_participant, line_number, "Second")?;
         tokens.push(Token::Participant(second_participant.to_string()));
 
-        let message = rest[colon_pos + 1..].trim();
+        let mut message = rest[colon_pos + 1..].trim();
+        if let Some(markup) = message.strip_prefix('{')
+            && let Some((style, text)) = markup.split_once('}')
+        {
+            tokens.push(Token::EdgeStyle(parse_edge_style(style, line_number)?));
+            message = text.trim();
+        }
         if !message.is_empty() {
             tokens.push(Token::ArrowMessage(message.to_string()));
         }
@@ -81,37 +277,258 @@ fn tokenize_line(
     Ok(())
 }
 
-fn validate_participant(
-    name: &str,
+/// Parses the inside of a `{style}` markup: a color name, `bold` and a label position
+/// (`above`, `below` or `centered`), in any combination separated by spaces
+fn parse_edge_style(markup: &str, line_number: usize) -> Result<EdgeStyle, TokenizeError> {
+    let mut style = EdgeStyle::default();
+
+    for word in markup.split_whitespace() {
+        if word == "bold" {
+            style.bold = true;
+        } else if let Some(label_position) = match word {
+            "above" => Some(LabelPosition::Above),
+            "below" => Some(LabelPosition::Below),
+            "centered" => Some(LabelPosition::Centered),
+            _ => None,
+        } {
+            style.label_position = label_position;
+        } else if let Some(color) = Color::from_name(word) {
+            style.color = Some(color);
+        } else {
+            return Err(TokenizeError {
+                line: line_number,
+                message: format!(
+                    "Unknown style '{}'. Expected 'bold', 'above', 'below', 'centered' or a color: {}",
+                    word,
+                    Color::NAMES.join(", ")
+                ),
+            });
+        }
+    }
+
+    if markup.trim().is_empty() {
+        return Err(TokenizeError {
+            line: line_number,
+            message:
+                "Empty style. Expected 'bold', a label position or a color name between '{' and '}'"
+                    .to_string(),
+        });
+    }
+
+    Ok(style)
+}
+
+/// Recognises `par <label>`, `critical <label>`, `break <condition>`, `group <label>`,
+/// `and <label>` and a bare `end`
+fn block_keyword(line: &str) -> Option<Token> {
+    let (keyword, label) = line.split_once(' ').unwrap_or((line, ""));
+    let label = label.trim().to_string();
+    match keyword {
+        "par" => Some(Token::Par(label)),
+        "critical" => Some(Token::Critical(label)),
+        "break" => Some(Token::Break(label)),
+        "group" => Some(Token::Group(label)),
+        "and" => Some(Token::And(label)),
+        "end" if label.is_empty() => Some(Token::End),
+        _ => None,
+    }
+}
+
+fn tokenize_order_hint(
+    names: &str,
     line_number: usize,
-    position: &str,
+    tokens: &mut Vec<Token>,
 ) -> Result<(), TokenizeError> {
-    if name.is_empty() {
+    if line_number != 1 {
         return Err(TokenizeError {
             line: line_number,
-            message: format!("{} participant is empty.", position),
+            message: "The order directive must be on the first line".to_string(),
         });
     }
-    if name.len() > 80 {
+    let mut order: Vec<String> = Vec::new();
+
+    for name in names.split(',') {
+        let name = name.trim();
+        validate_participant(name, line_number, "Ordered")?;
+        if order.iter().any(|ordered| ordered == name) {
+            return Err(TokenizeError {
+                line: line_number,
+                message: format!("Participant {} appears more than once in the order", name),
+            });
+        }
+        order.push(name.to_string());
+    }
+
+    tokens.push(Token::OrderHint(order));
+
+    Ok(())
+}
+
+/// Records the participants declared by `tokens`, the tokens of `line`, and rejects the ones
+/// used without a declaration
+fn check_declared(
+    tokens: &[Token],
+    line: &str,
+    line_number: usize,
+    indent: usize,
+    declared: &mut Vec<String>,
+) -> Result<(), TokenizeError> {
+    // Participants are named left to right, so each is searched for after the previous one
+    let mut search_from = 0;
+
+    for token in tokens {
+        let names = match token {
+            Token::Declaration { name, .. } | Token::Create(name) => {
+                declared.push(name.clone());
+                continue;
+            }
+            Token::Participant(name) | Token::Destroy(name) => std::slice::from_ref(name),
+            Token::Note { participant, .. } => std::slice::from_ref(participant),
+            Token::Ref { participants, .. } => participants.as_slice(),
+            _ => continue,
+        };
+
+        for name in names {
+            let offset = line[search_from..]
+                .find(name.as_str())
+                .map_or(search_from, |offset| search_from + offset);
+            search_from = offset + name.len();
+
+            if !declared.contains(name) {
+                let column = indent + line[..offset].chars().count() + 1;
+                return Err(TokenizeError {
+                    line: line_number,
+                    message: format!(
+                        "Undeclared participant '{name}' at column {column}. Declare it with 'participant {name}'"
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn tokenize_note(
+    rest: &str,
+    line_number: usize,
+    tokens: &mut Vec<Token>,
+) -> Result<(), TokenizeError> {
+    let (participant, text) = rest.split_once(':').ok_or_else(|| TokenizeError {
+        line: line_number,
+        message: "Missing colon. Expected 'note over <participant>: <text>'".to_string(),
+    })?;
+
+    let participant = participant.trim();
+    validate_participant(participant, line_number, "Note")?;
+
+    let text = text.trim();
+    if text.is_empty() {
         return Err(TokenizeError {
             line: line_number,
-            message: format!("{} participant is too long (max 80 characters).", position),
+            message: "Note text is empty.".to_string(),
         });
     }
-    if name.contains('\n') {
+
+    tokens.push(Token::Note {
+        participant: participant.to_string(),
+        text: text.to_string(),
+    });
+
+    Ok(())
+}
+
+fn tokenize_ref(
+    rest: &str,
+    line_number: usize,
+    tokens: &mut Vec<Token>,
+) -> Result<(), TokenizeError> {
+    let (names, label) = rest.split_once(':').ok_or_else(|| TokenizeError {
+        line: line_number,
+        message: "Missing colon. Expected 'ref over <participant>, ...: <label>'".to_string(),
+    })?;
+
+    let mut participants: Vec<String> = Vec::new();
+    for name in names.split(',') {
+        let name = name.trim();
+        validate_participant(name, line_number, "Ref")?;
+        if !participants.iter().any(|participant| participant == name) {
+            participants.push(name.to_string());
+        }
+    }
+
+    let label = label.trim();
+    if label.is_empty() {
         return Err(TokenizeError {
             line: line_number,
-            message: format!("{} participant contains new line character.", position),
+            message: "Ref label is empty.".to_string(),
         });
     }
 
+    tokens.push(Token::Ref {
+        participants,
+        label: label.to_string(),
+    });
+
     Ok(())
 }
 
+fn validate_participant(
+    name: &str,
+    line_number: usize,
+    position: &str,
+) -> Result<(), TokenizeError> {
+    validate_participant_name(name).map_err(|message| TokenizeError {
+        line: line_number,
+        message: format!("{} participant {}.", position, message),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_iter_matches_batch() {
+        let input = "
+title Login
+participant Client
+Client -> Server: {red bold}Login
+note over Server: Checks the password
+par checks
+Client <- Server: OK
+and audit
+return: Logged
+end";
+        let batch = tokenize(input).unwrap();
+        let streamed: Vec<Token> = tokenize_iter(input).map(Result::unwrap).collect();
+        assert_eq!(streamed, batch);
+    }
+
+    #[test]
+    fn test_iter_continues_after_error() {
+        let input = "
+Client -> Server
+Client Server
+Server -> Database";
+        let mut tokens = tokenize_iter(input);
+        assert!(matches!(tokens.next(), Some(Ok(Token::Participant(_)))));
+
+        let errors: Vec<usize> = tokenize_iter(input)
+            .filter_map(Result::err)
+            .map(|err| err.line)
+            .collect();
+        assert_eq!(errors, [2]);
+        let participants: Vec<String> = tokenize_iter(input)
+            .filter_map(Result::ok)
+            .filter_map(|token| match token {
+                Token::Participant(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(participants, ["Client", "Server", "Server", "Database"]);
+    }
+
     #[test]
     fn test_normal_input() {
         let input = "\
@@ -310,4 +727,311 @@ Client <- Server: JSON response",
                 .contains("Second participant is too long (max 80 characters).")
         );
     }
+
+    #[test]
+    fn test_note() {
+        let input = "\
+Client -> Server: GET /api/data
+note over Server: Validates token";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Participant("Client".to_string()),
+                Token::RightArrow,
+                Token::Participant("Server".to_string()),
+                Token::ArrowMessage("GET /api/data".to_string()),
+                Token::Note {
+                    participant: "Server".to_string(),
+                    text: "Validates token".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_note_missing_colon() {
+        let input = "\
+Client -> Server: GET /api/data
+note over Server";
+        let result = tokenize(input);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("Missing colon"));
+    }
+
+    #[test]
+    fn test_title() {
+        let input = "\
+title  Login flow
+Client -> Server";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(tokens[0], Token::Title("Login flow".to_string()));
+        assert_eq!(tokens.len(), 4);
+    }
+
+    #[test]
+    fn test_edge_style() {
+        let input = "\
+Client -> Server: {red}Error
+Client <- Server: {bold green} OK
+Client -> Server: {blue}";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(
+            tokens[3],
+            Token::EdgeStyle(EdgeStyle {
+                color: Some(Color::Red),
+                ..EdgeStyle::default()
+            })
+        );
+        assert_eq!(tokens[4], Token::ArrowMessage("Error".to_string()));
+        assert_eq!(
+            tokens[8],
+            Token::EdgeStyle(EdgeStyle {
+                color: Some(Color::Green),
+                bold: true,
+                ..EdgeStyle::default()
+            })
+        );
+        assert_eq!(tokens[9], Token::ArrowMessage("OK".to_string()));
+        // A style without text only styles the arrow
+        assert_eq!(tokens.len(), 14);
+
+        // Braces without a closing brace are plain text
+        let tokens = tokenize("Client -> Server: {not markup").unwrap();
+        assert_eq!(tokens[3], Token::ArrowMessage("{not markup".to_string()));
+
+        let tokens =
+            tokenize("Client -> Server: {below red}Error\nClient -> Server: {centered}Hi").unwrap();
+        assert_eq!(
+            tokens[3],
+            Token::EdgeStyle(EdgeStyle {
+                color: Some(Color::Red),
+                label_position: LabelPosition::Below,
+                ..EdgeStyle::default()
+            })
+        );
+        assert_eq!(
+            tokens[8],
+            Token::EdgeStyle(EdgeStyle {
+                label_position: LabelPosition::Centered,
+                ..EdgeStyle::default()
+            })
+        );
+        // The default position on its own is a valid style
+        assert!(tokenize("Client -> Server: {above}Hi").is_ok());
+
+        let err = tokenize("Client -> Server: Hi\nClient -> Server: {purple}Error").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.starts_with("Unknown style 'purple'"));
+        assert!(tokenize("Client -> Server: {}Error").is_err());
+    }
+
+    #[test]
+    fn test_order_hint() {
+        let input = "\
+order: Bob , Alice
+Alice -> Bob";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(
+            tokens[0],
+            Token::OrderHint(vec!["Bob".to_string(), "Alice".to_string()])
+        );
+        assert_eq!(tokens.len(), 4);
+
+        let err = tokenize("order: Bob, Alice, Bob").unwrap_err();
+        assert!(err.message.contains("more than once"));
+        assert!(tokenize("order: Bob,").is_err());
+
+        let err = tokenize("Alice -> Bob\norder: Bob, Alice").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("first line"));
+    }
+
+    #[test]
+    fn test_require_arrow_spacing() {
+        // Allowed by default
+        let tokens = tokenize("Client->Server: Hi").unwrap();
+        assert_eq!(tokens[0], Token::Participant("Client".to_string()));
+        assert_eq!(tokens[2], Token::Participant("Server".to_string()));
+
+        let config = TokenizeConfig {
+            require_arrow_spacing: true,
+            ..TokenizeConfig::default()
+        };
+        let err = tokenize_with_config("Client->Server: Hi", &config).unwrap_err();
+        assert_eq!(err.line, 1);
+        assert_eq!(
+            err.message,
+            "Arrow '->' at column 7 must be surrounded by spaces"
+        );
+
+        let input = "Client -> Server: Hi\n  Server <-Client";
+        let err = tokenize_with_config(input, &config).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.message,
+            "Arrow '<-' at column 10 must be surrounded by spaces"
+        );
+
+        assert!(tokenize_with_config("Client\t->  Server: Hi", &config).is_ok());
+    }
+
+    #[test]
+    fn test_par_block() {
+        let input = "\
+par Fetch data
+Client -> Server
+and
+and -> Server
+end";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(tokens[0], Token::Par("Fetch data".to_string()));
+        assert_eq!(tokens[4], Token::And(String::new()));
+        assert_eq!(tokens[5], Token::Participant("and".to_string()));
+        assert_eq!(tokens[8], Token::End);
+
+        assert!(tokenize("end now").is_err());
+        assert_eq!(
+            tokenize("critical Lock row").unwrap(),
+            [Token::Critical("Lock row".to_string())]
+        );
+        assert_eq!(
+            tokenize("break timeout").unwrap(),
+            [Token::Break("timeout".to_string())]
+        );
+        assert_eq!(
+            tokenize("group Checkout").unwrap(),
+            [Token::Group("Checkout".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_spacer() {
+        let input = "\
+Client -> Server
+---
+  spacer
+Server -> Database";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(tokens[3], Token::Spacer);
+        assert_eq!(tokens[4], Token::Spacer);
+        assert_eq!(tokens.len(), 8);
+
+        assert!(tokenize("-- -").is_err());
+    }
+
+    #[test]
+    fn test_ref() {
+        let tokens = tokenize("ref over Client , Database: Authenticate").unwrap();
+        assert_eq!(
+            tokens,
+            [Token::Ref {
+                participants: vec!["Client".to_string(), "Database".to_string()],
+                label: "Authenticate".to_string(),
+            }]
+        );
+
+        assert!(tokenize("ref over Client").is_err());
+        assert!(tokenize("ref over Client,: Login").is_err());
+        assert!(tokenize("ref over Client:").is_err());
+    }
+
+    #[test]
+    fn test_return() {
+        let tokens = tokenize("Client -> Server: Login\nreturn: Token\nreturn").unwrap();
+        assert_eq!(tokens[4], Token::Return(Some("Token".to_string())));
+        assert_eq!(tokens[5], Token::Return(None));
+        assert_eq!(tokenize("return:").unwrap(), [Token::Return(None)]);
+        // Still an edge with an arrow
+        let tokens = tokenize("return -> Server").unwrap();
+        assert_eq!(tokens[0], Token::Participant("return".to_string()));
+    }
+
+    #[test]
+    fn test_participant_kinds() {
+        let tokens = tokenize("actor Client\ndatabase Store\nqueue Jobs\nactors -> Store").unwrap();
+        let kinds: Vec<(&str, ParticipantKind)> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Declaration { name, kind } => Some((name.as_str(), *kind)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("Client", ParticipantKind::Actor),
+                ("Store", ParticipantKind::Database),
+                ("Jobs", ParticipantKind::Queue),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_participants() {
+        let input = "\
+participant Client
+participant Server
+Client -> Server: Hi
+  Server -> Sever: Typo";
+        // Undeclared participants are added by default
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(
+            tokens[0],
+            Token::Declaration {
+                name: "Client".to_string(),
+                kind: ParticipantKind::Participant,
+            }
+        );
+        assert_eq!(tokens[8], Token::Participant("Sever".to_string()));
+
+        let config = TokenizeConfig {
+            strict_participants: true,
+            ..TokenizeConfig::default()
+        };
+        let err = tokenize_with_config(input, &config).unwrap_err();
+        assert_eq!(err.line, 4);
+        assert_eq!(
+            err.message,
+            "Undeclared participant 'Sever' at column 13. Declare it with 'participant Sever'"
+        );
+
+        // Declarations must come first, and cover notes and refs
+        let err = tokenize_with_config("note over Client: Hi", &config).unwrap_err();
+        assert!(err.message.contains("'Client' at column 11"));
+        let input = "participant Client\nref over Client, Server: Login";
+        let err = tokenize_with_config(input, &config).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("'Server' at column 18"));
+        let input = "participant A\nparticipant B\nA -> B\nnote over B: Hi\nref over A, B: Login";
+        assert!(tokenize_with_config(input, &config).is_ok());
+    }
+
+    #[test]
+    fn test_create_and_destroy() {
+        let tokens = tokenize("create participant Worker\ncreate Cache\ndestroy Worker").unwrap();
+        assert_eq!(
+            tokens,
+            [
+                Token::Create("Worker".to_string()),
+                Token::Create("Cache".to_string()),
+                Token::Destroy("Worker".to_string()),
+            ]
+        );
+        // Still an edge with an arrow
+        let tokens = tokenize("create -> Worker").unwrap();
+        assert_eq!(tokens[0], Token::Participant("create".to_string()));
+
+        let config = TokenizeConfig {
+            strict_participants: true,
+            ..TokenizeConfig::default()
+        };
+        assert!(tokenize_with_config("create Worker\nA -> Worker", &config).is_err());
+        let input = "participant A\ncreate Worker\nA -> Worker\ndestroy Worker";
+        assert!(tokenize_with_config(input, &config).is_ok());
+        let err = tokenize_with_config("destroy Worker", &config).unwrap_err();
+        assert!(err.message.contains("'Worker' at column 9"));
+    }
 }
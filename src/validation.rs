@@ -0,0 +1,64 @@
+/// Checks a participant or node name: non-empty, at most 80 bytes, on one line and without an
+/// arrow. The error reads as the end of a sentence about the name, e.g. `is empty`.
+pub fn validate_participant_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("is empty".to_string());
+    }
+    if name.len() > 80 {
+        return Err("is too long (max 80 characters)".to_string());
+    }
+    if name.contains('\n') {
+        return Err("cannot contain a new line".to_string());
+    }
+    if name.contains("->") {
+        return Err("cannot contain '->'".to_string());
+    }
+
+    Ok(())
+}
+
+/// Checks a git graph branch or tag name: non-empty, at most 80 bytes and without whitespace,
+/// like git names. The error reads as the end of a sentence about the name, e.g. `is empty`.
+pub fn validate_branch_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("is empty".to_string());
+    }
+    if name.len() > 80 {
+        return Err("is too long (max 80 characters)".to_string());
+    }
+    if name.contains(char::is_whitespace) {
+        return Err("cannot contain whitespace".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_participant_name() {
+        assert!(validate_participant_name("Client").is_ok());
+        assert!(validate_participant_name(&"A".repeat(80)).is_ok());
+        assert_eq!(
+            validate_participant_name(&"A".repeat(81)).unwrap_err(),
+            "is too long (max 80 characters)"
+        );
+        assert_eq!(validate_participant_name("").unwrap_err(), "is empty");
+        assert!(validate_participant_name("Cli\nent").is_err());
+        assert!(validate_participant_name("a -> b").is_err());
+    }
+
+    #[test]
+    fn test_validate_branch_name() {
+        assert!(validate_branch_name("feature/search-1").is_ok());
+        assert_eq!(validate_branch_name("").unwrap_err(), "is empty");
+        assert!(validate_branch_name(&"a".repeat(81)).is_err());
+        assert_eq!(
+            validate_branch_name("with spaces").unwrap_err(),
+            "cannot contain whitespace"
+        );
+        assert!(validate_branch_name("tab\there").is_err());
+    }
+}
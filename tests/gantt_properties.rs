@@ -0,0 +1,123 @@
+//! Property tests of the gantt parser on generated valid charts
+
+use chrono::{Days, NaiveDate};
+use proptest::prelude::*;
+
+use gram::gantt::parser::parse;
+
+#[derive(Debug, Clone)]
+enum Start {
+    Date(u64),
+    Continue,
+}
+
+#[derive(Debug, Clone)]
+enum End {
+    /// Days after the start, `0` for a milestone
+    Date(u64),
+    Duration(u64),
+}
+
+#[derive(Debug, Clone)]
+struct TaskLine {
+    section: Option<String>,
+    name: String,
+    start: Start,
+    end: End,
+    overlays: Vec<&'static str>,
+}
+
+fn base_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z0-9 ]{0,12}[A-Za-z0-9]".prop_filter("directive", |name| name != "today marker")
+}
+
+fn task_line() -> impl Strategy<Value = TaskLine> {
+    (
+        proptest::option::weighted(0.2, name()),
+        name(),
+        prop_oneof![(0..3650u64).prop_map(Start::Date), Just(Start::Continue)],
+        prop_oneof![
+            (0..400u64).prop_map(End::Date),
+            (1..400u64).prop_map(End::Duration)
+        ],
+        proptest::sample::subsequence(vec!["progress 50%", "done", "crit"], 0..=3),
+    )
+        .prop_map(|(section, name, start, end, overlays)| TaskLine {
+            section,
+            name,
+            start,
+            end,
+            overlays,
+        })
+}
+
+/// Writes the lines as input. The first task has nothing to continue from and starts on a date,
+/// and tasks that continue end after a duration, which cannot fall before their start.
+fn to_input(lines: &[TaskLine]) -> String {
+    let mut input = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(section) = &line.section {
+            input.push_str(&format!("section {}\n", section));
+        }
+
+        let (start, end) = match line.start {
+            Start::Continue if index > 0 => {
+                let days = match line.end {
+                    End::Date(days) | End::Duration(days) => days.max(1),
+                };
+                ("continue".to_string(), format!("{}d", days))
+            }
+            Start::Continue => dated(0, &line.end),
+            Start::Date(offset) => dated(offset, &line.end),
+        };
+
+        input.push_str(&format!("{}: {}, {}", line.name, start, end));
+        for overlay in &line.overlays {
+            input.push_str(&format!(" | {}", overlay));
+        }
+        input.push('\n');
+    }
+    input
+}
+
+/// Start and end of a task starting `offset` days after the base date
+fn dated(offset: u64, end: &End) -> (String, String) {
+    let start_date = base_date() + Days::new(offset);
+    let end = match *end {
+        End::Date(days) => (start_date + Days::new(days))
+            .format("%d-%m-%Y")
+            .to_string(),
+        End::Duration(days) => format!("{}d", days),
+    };
+    (start_date.format("%d-%m-%Y").to_string(), end)
+}
+
+proptest! {
+    #[test]
+    fn test_valid_charts_parse(lines in proptest::collection::vec(task_line(), 0..20)) {
+        let input = to_input(&lines);
+        let gantt_chart = parse(&input).unwrap();
+
+        prop_assert_eq!(gantt_chart.tasks.len(), lines.len());
+        for task in &gantt_chart.tasks {
+            prop_assert!(task.start_date <= task.end_date);
+        }
+        let (min_date, max_date) = gantt_chart.date_range();
+        prop_assert_eq!(
+            gantt_chart.tasks_in_range(min_date, max_date).len(),
+            gantt_chart.tasks.len()
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip(lines in proptest::collection::vec(task_line(), 0..20)) {
+        let gantt_chart = parse(&to_input(&lines)).unwrap();
+
+        let text = gantt_chart.to_string();
+        prop_assert_eq!(parse(&text).unwrap(), gantt_chart, "written as:\n{}", text);
+    }
+}
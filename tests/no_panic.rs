@@ -0,0 +1,387 @@
+//! Runs randomly generated but valid diagrams through parse, layout and render to check that
+//! none of them panic.
+
+use chrono::{Days, NaiveDate};
+
+use gram::canvas::OutputOptions;
+use gram::gantt;
+use gram::layout::{SequenceLayoutConfig, calculate_sequence_layout_with_config};
+use gram::parser::parse;
+use gram::renderer::{RenderOptions, render_with_options};
+use gram::tokenizer::tokenize;
+
+const ITERATIONS: usize = 500;
+
+const NAMES: [&str; 8] = [
+    "A",
+    "Bo",
+    "Client",
+    "Server",
+    "Database",
+    "数据库",
+    "x y",
+    "Auditor",
+];
+const WORDS: [&str; 8] = [
+    "GET",
+    "/api/data",
+    "ok",
+    "JSON response",
+    "重试",
+    "a",
+    "",
+    "ack",
+];
+
+/// Small deterministic xorshift generator so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+fn random_message(rng: &mut Rng) -> String {
+    (0..1 + rng.below(3))
+        .map(|_| rng.pick(&WORDS))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+fn random_sequence_input(rng: &mut Rng) -> String {
+    let mut lines = Vec::new();
+    if rng.chance(30) {
+        lines.push(format!("title {}", rng.pick(&NAMES)));
+    }
+
+    let mut open_blocks = Vec::new();
+    // A participant outside `NAMES`, created and destroyed partway through
+    let mut worker_created = false;
+    let mut worker_destroyed = false;
+    for _ in 0..rng.below(25) {
+        let from = rng.pick(&NAMES);
+        let to = rng.pick(&NAMES);
+
+        if !worker_created && rng.chance(5) {
+            lines.push("create participant Worker".to_string());
+            lines.push(format!("{} -> Worker: {}", from, random_message(rng)));
+            worker_created = true;
+        } else if worker_created && !worker_destroyed && rng.chance(10) {
+            lines.push(format!("Worker -> {}", to));
+            if rng.chance(30) {
+                lines.push("destroy Worker".to_string());
+                worker_destroyed = true;
+            }
+        }
+
+        if rng.chance(15) {
+            lines.push(format!("note over {}: {}", from, rng.pick(&NAMES)));
+            continue;
+        }
+
+        if rng.chance(5) {
+            // The same kind each time, as redeclaring with another kind is an error
+            let keyword = ["participant", "actor", "database", "queue"][from.len() % 4];
+            lines.push(format!("{} {}", keyword, from));
+        }
+        if rng.chance(5) {
+            lines.push("---".to_string());
+        }
+        if rng.chance(5) {
+            lines.push(format!("ref over {}, {}: {}", from, to, rng.pick(&NAMES)));
+        }
+        if rng.chance(10) {
+            let keyword = rng.pick(&["par", "critical", "break", "group"]);
+            lines.push(format!("{} {}", keyword, random_message(rng)));
+            open_blocks.push(keyword);
+        } else if open_blocks.last() == Some(&"par") && rng.chance(10) {
+            lines.push(format!("and {}", random_message(rng)));
+        } else if !open_blocks.is_empty() && rng.chance(10) {
+            lines.push("end".to_string());
+            open_blocks.pop();
+        }
+
+        let arrow = if rng.chance(50) { "->" } else { "<-" };
+        let mut message = random_message(rng);
+        if rng.chance(20) {
+            let style = rng.pick(&["red", "bold", "bold cyan", "below", "centered green"]);
+            message = format!("{{{}}}{}", style, message);
+        }
+        if message.is_empty() || rng.chance(30) {
+            lines.push(format!("{} {} {}", from, arrow, to));
+        } else {
+            lines.push(format!("{} {} {}: {}", from, arrow, to, message));
+        }
+    }
+    for _ in open_blocks {
+        lines.push("end".to_string());
+    }
+
+    lines.join("\n")
+}
+
+fn random_sequence_config(rng: &mut Rng) -> SequenceLayoutConfig {
+    let mut config = SequenceLayoutConfig {
+        edge_spacing: rng.below(3),
+        participant_padding_x: rng.below(3),
+        message_padding_x: rng.below(3),
+        use_indices: rng.chance(30),
+        auto_width: rng.chance(30),
+        max_width: rng.chance(20).then(|| rng.below(100)),
+        rtl: rng.chance(30),
+        spacer_height: rng.below(4),
+        spacer_divider: rng.chance(50),
+        ..SequenceLayoutConfig::default()
+    };
+    config.margins.left = rng.below(3);
+    config.margins.right = rng.below(3);
+    config.margins.top = rng.below(3);
+    config.margins.bottom = rng.below(3);
+    config.terminal_width = match rng.below(3) {
+        0 => || None,
+        1 => || Some(20),
+        _ => || Some(120),
+    };
+    if rng.chance(30) {
+        config.hide_participants = vec![rng.pick(&NAMES).to_string()];
+    }
+    config
+}
+
+#[test]
+fn test_random_sequence_diagrams_do_not_panic() {
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+
+    for _ in 0..ITERATIONS {
+        let input = random_sequence_input(&mut rng);
+        let config = random_sequence_config(&mut rng);
+
+        let diagram = parse(tokenize(&input).unwrap()).unwrap();
+        let layout = calculate_sequence_layout_with_config(&diagram, &config);
+        let options = RenderOptions {
+            output: OutputOptions {
+                color: rng.chance(50),
+                trim_trailing_whitespace: rng.chance(30),
+                final_newline: rng.chance(30),
+            },
+            ascii: rng.chance(30),
+        };
+        let output = render_with_options(&layout, &options);
+
+        // Trimmed blank rows at the end read as a final newline
+        if !options.output.trim_trailing_whitespace {
+            assert_eq!(output.lines().count(), layout.height, "input:\n{}", input);
+        }
+
+        let text = diagram.to_string();
+        let reparsed = parse(tokenize(&text).unwrap()).unwrap();
+        assert_eq!(
+            reparsed, diagram,
+            "input:\n{}\nwritten as:\n{}",
+            input, text
+        );
+    }
+}
+
+fn random_date(rng: &mut Rng) -> NaiveDate {
+    let base = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    base + Days::new(rng.below(1500) as u64)
+}
+
+fn random_date_range(rng: &mut Rng, can_continue: bool) -> String {
+    let start = random_date(rng);
+    let start_str = if can_continue && rng.chance(30) {
+        "continue".to_string()
+    } else {
+        start.format("%d-%m-%Y").to_string()
+    };
+
+    let end_str = match rng.below(3) {
+        // Milestone, or an end date relative to a `continue` start that may be too early
+        0 if start_str != "continue" => start.format("%d-%m-%Y").to_string(),
+        1 => format!("{}d", 1 + rng.below(400)),
+        _ if start_str == "continue" => format!("{}d", 1 + rng.below(30)),
+        _ => (start + Days::new(rng.below(200) as u64))
+            .format("%d-%m-%Y")
+            .to_string(),
+    };
+
+    format!("{}, {}", start_str, end_str)
+}
+
+fn random_gantt_input(rng: &mut Rng) -> String {
+    let mut lines = Vec::new();
+    if rng.chance(30) {
+        lines.push("today marker: on".to_string());
+    }
+    if rng.chance(20) {
+        lines.push(format!("title {}", rng.pick(&NAMES)));
+    }
+    if rng.chance(10) {
+        lines.push(format!("subtitle {}", rng.pick(&NAMES)));
+    }
+
+    for index in 0..rng.below(12) {
+        if rng.chance(15) {
+            lines.push(format!("section {}", rng.pick(&NAMES)));
+        }
+        let mut line = format!(
+            "{}: {}",
+            rng.pick(&NAMES),
+            random_date_range(rng, index > 0)
+        );
+        // Refers to an earlier task, which may not have an id
+        if index > 0 && rng.chance(30) {
+            line = format!(
+                "{}: after t{}, {}d",
+                rng.pick(&NAMES),
+                rng.below(index),
+                1 + rng.below(30)
+            );
+        }
+        if rng.chance(25) {
+            line = format!("{} | actual {}", line, random_date_range(rng, index > 0));
+        }
+        if rng.chance(25) {
+            line = format!("{} | progress {}%", line, rng.below(101));
+        }
+        if rng.chance(25) {
+            line = format!("{} | {}", line, rng.pick(&["done", "active", "crit"]));
+        }
+        if rng.chance(40) {
+            line = format!("{} | id t{}", line, index);
+        }
+        if rng.chance(40) {
+            line = format!("{} | assignee {}", line, rng.pick(&NAMES));
+        }
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+fn random_gantt_config(rng: &mut Rng) -> gantt::layout::GanttLayoutConfig {
+    let mut config = gantt::layout::GanttLayoutConfig {
+        chart_width: match rng.below(3) {
+            0 => gantt::layout::WidthMode::Fixed(rng.below(200)),
+            1 => gantt::layout::WidthMode::FitDays(rng.below(6)),
+            _ => gantt::layout::WidthMode::Max(rng.below(200)),
+        },
+        today_marker: rng.chance(30),
+        section_rules: rng.chance(50),
+        legend: rng.chance(30),
+        month_header: rng.chance(30),
+        weekday_letters: rng.chance(30),
+        weekend_shading: rng.chance(30),
+        row_guides: match rng.below(3) {
+            0 => gantt::layout::RowGuides::Off,
+            1 => gantt::layout::RowGuides::AlternateRows,
+            _ => gantt::layout::RowGuides::TaskLines,
+        },
+        row_stripes: rng.chance(30),
+        compress_gaps: rng.chance(40).then(|| rng.below(60)),
+        duration_labels: match rng.below(3) {
+            0 => gantt::layout::DurationLabels::Off,
+            1 => gantt::layout::DurationLabels::Append,
+            _ => gantt::layout::DurationLabels::NarrowBars,
+        },
+        working_days: rng.chance(30),
+        resource_lanes: rng.chance(30),
+        summary_rows: match rng.below(3) {
+            0 => gantt::layout::SummaryRows::Off,
+            1 => gantt::layout::SummaryRows::Project,
+            _ => gantt::layout::SummaryRows::Sections,
+        },
+        clock: match rng.below(3) {
+            0 => || NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            1 => || NaiveDate::from_ymd_opt(2025, 6, 15).unwrap(),
+            _ => || NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(),
+        },
+        ..gantt::layout::GanttLayoutConfig::default()
+    };
+    if rng.chance(50) {
+        config.label_placement = gantt::layout::LabelPlacement::Gutter;
+    }
+    if rng.chance(20) {
+        config.orientation = gantt::layout::Orientation::Vertical;
+        config.chart_height = rng.below(60);
+    }
+    config.margins.left = rng.below(7);
+    config.margins.right = rng.below(7);
+    config.margins.top = rng.below(3);
+    config.margins.bottom = rng.below(4);
+    config
+}
+
+#[test]
+fn test_random_gantt_charts_do_not_panic() {
+    let mut rng = Rng(0x0dd_ba11_cafe_f00d);
+
+    for _ in 0..ITERATIONS {
+        let input = random_gantt_input(&mut rng);
+        let config = random_gantt_config(&mut rng);
+
+        // Randomly chosen `continue` ranges can end before they start; those are rejected
+        let Ok(gantt_chart) = gantt::parser::parse(&input) else {
+            continue;
+        };
+        let gantt_layout = gantt::layout::layout_with_config(&gantt_chart, &config);
+        let (min_date, max_date) = gantt_chart.date_range();
+        let has_span = !gantt_chart.tasks.is_empty() && min_date < max_date;
+        assert_eq!(
+            gantt::layout::try_layout_with_config(&gantt_chart, &config).is_ok(),
+            has_span,
+            "input:\n{}",
+            input
+        );
+        let options = gantt::renderer::RenderOptions {
+            output: OutputOptions {
+                color: rng.chance(50),
+                trim_trailing_whitespace: rng.chance(30),
+                final_newline: rng.chance(30),
+            },
+            critical_path: rng.chance(50),
+            theme: match rng.below(3) {
+                0 => gantt::renderer::GanttTheme::boxes(),
+                1 => gantt::renderer::GanttTheme::blocks(),
+                _ => gantt::renderer::GanttTheme::ascii(),
+            },
+            name_fit: match rng.below(3) {
+                0 => gantt::renderer::NameFit::SpillRight,
+                1 => gantt::renderer::NameFit::TruncateInside,
+                _ => gantt::renderer::NameFit::Initials,
+            },
+        };
+        let output = gantt::renderer::render_with_options(&gantt_layout, &options);
+        if let Ok(checked) = gantt::renderer::try_render_with_options(&gantt_layout, &options) {
+            assert_eq!(checked, output);
+        }
+
+        if !options.output.trim_trailing_whitespace {
+            assert_eq!(
+                output.lines().count(),
+                gantt_layout.height,
+                "input:\n{}",
+                input
+            );
+        }
+    }
+}